@@ -0,0 +1,175 @@
+use std::{
+    f64::consts::TAU,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use mandelbrot::{Bounds, Location, Origin, C};
+use serde::{Deserialize, Serialize};
+
+/// How long a zoom transition takes.
+const DURATION: Duration = Duration::from_millis(300);
+
+/// Bounds a fraction `t` of the way from `from` to `to`, interpolating the
+/// scale exponentially. The center moves in step with the scale, which
+/// keeps the point zoomed into still on screen even through deep zooms, or
+/// linearly if the scale doesn't change.
+pub fn interpolate(from: Bounds, to: Bounds, t: f64) -> Bounds {
+    let axis = |(min0, extent0): (f64, f64), (min1, extent1): (f64, f64)| {
+        let extent = extent0 * (extent1 / extent0).powf(t);
+        let along = match extent0 - extent1 {
+            change if change.abs() > extent0.abs() * 1e-9 => (extent0 - extent) / change,
+            _ => t,
+        };
+        let center = min0 + extent0 / 2. + (min1 + extent1 / 2. - min0 - extent0 / 2.) * along;
+        (center - extent / 2., extent)
+    };
+
+    (axis(from.0, to.0), axis(from.1, to.1))
+}
+
+/// How transitions between two views pace themselves.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    /// Move the bounds at a constant rate, which rushes through the start
+    /// of deep zooms and crawls through their end
+    Linear,
+    /// Zoom exponentially, starting and stopping gently
+    EaseInOut,
+    /// Zoom exponentially at a constant rate
+    #[default]
+    Exponential,
+}
+
+impl Easing {
+    /// Bounds a fraction `t` of the way from `from` to `to`.
+    pub fn interpolate(self, from: Bounds, to: Bounds, t: f64) -> Bounds {
+        match self {
+            Easing::Linear => {
+                let lerp = |a: f64, b: f64| a + (b - a) * t;
+                let axis = |(min0, extent0): (f64, f64), (min1, extent1): (f64, f64)| {
+                    (lerp(min0, min1), lerp(extent0, extent1))
+                };
+                (axis(from.0, to.0), axis(from.1, to.1))
+            }
+            Easing::EaseInOut => interpolate(from, to, t * t * (3. - 2. * t)),
+            Easing::Exponential => interpolate(from, to, t),
+        }
+    }
+}
+
+/// A transition of the view between two locations, in bounds relative to
+/// the origin of the last one.
+pub struct Zoom {
+    from: Bounds,
+    to: Bounds,
+    origin: Origin,
+    /// Angles turned from and to.
+    rotation: (f64, f64),
+    start: Instant,
+    easing: Easing,
+}
+
+impl Zoom {
+    pub fn new(from: Location, to: Location, easing: Easing) -> Self {
+        let (dx, dy) = from.origin.difference(&to.origin);
+        let ((x, width), (y, height)) = from.bounds();
+
+        Zoom {
+            from: ((x + dx, width), (y + dy, height)),
+            to: to.bounds(),
+            origin: to.origin,
+            rotation: (from.rotation, to.rotation),
+            start: Instant::now(),
+            easing,
+        }
+    }
+
+    /// Fraction of the transition done, in `0..=1`.
+    pub fn progress(&self) -> f64 {
+        (self.start.elapsed().as_secs_f64() / DURATION.as_secs_f64()).min(1.)
+    }
+
+    /// Location to show at this point of the transition.
+    pub fn location(&self) -> Location {
+        self.location_at(self.progress())
+    }
+
+    /// Location a fraction `t` of the way through the transition.
+    pub fn location_at(&self, t: f64) -> Location {
+        let (from, to) = self.rotation;
+        Location::new(
+            self.origin.clone(),
+            self.easing.interpolate(self.from, self.to, t),
+            from + (to - from) * t,
+        )
+    }
+
+    pub fn done(&self) -> bool {
+        self.progress() >= 1.
+    }
+}
+
+/// Path the constant of a Julia set is swept along.
+#[derive(Debug, Clone, Copy)]
+pub enum Sweep {
+    /// Once around the circle of a radius about a point, counterclockwise
+    /// from its right.
+    Circle(C, f64),
+    /// From one constant to the other.
+    Line(C, C),
+}
+
+impl Sweep {
+    /// The constant a fraction `t` of the way along the path.
+    pub fn at(self, t: f64) -> C {
+        match self {
+            Sweep::Circle(center, radius) => {
+                let (sin, cos) = (TAU * t).sin_cos();
+                C {
+                    re: center.re + radius * cos,
+                    im: center.im + radius * sin,
+                }
+            }
+            Sweep::Line(from, to) => C {
+                re: from.re + (to.re - from.re) * t,
+                im: from.im + (to.im - from.im) * t,
+            },
+        }
+    }
+
+    /// The constant `sweeps` sweeps into going over the path again and
+    /// again: around circles, and back and forth along lines.
+    pub fn looped(self, sweeps: f64) -> C {
+        let t = sweeps.rem_euclid(2.);
+        match self {
+            Sweep::Circle(..) => self.at(t.fract()),
+            Sweep::Line(..) => self.at(if t > 1. { 2. - t } else { t }),
+        }
+    }
+}
+
+/// A sweep played in real time, from when it started.
+pub struct Morph {
+    sweep: Sweep,
+    /// Seconds each sweep takes.
+    period: f64,
+    start: Instant,
+}
+
+impl Morph {
+    pub fn new(sweep: Sweep, period: f64) -> Self {
+        Morph {
+            sweep,
+            period,
+            start: Instant::now(),
+        }
+    }
+
+    /// Constant to show now.
+    pub fn constant(&self) -> C {
+        self.sweep
+            .looped(self.start.elapsed().as_secs_f64() / self.period)
+    }
+}
@@ -0,0 +1,120 @@
+use std::{env, error::Error, fs, io, path::PathBuf};
+
+use mandelbrot::{Bounds, Location, Origin};
+use serde::{Deserialize, Serialize};
+
+/// A named view to come back to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    /// Bounds of the view, relative to `origin`.
+    pub bounds: Bounds,
+    pub iterations: u32,
+    /// Angle in radians the view is turned by.
+    #[serde(default, skip_serializing_if = "is_straight")]
+    pub rotation: f64,
+    /// Decimal coordinates of the origin of deep views, which f64 bounds
+    /// can't place on their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<[String; 2]>,
+}
+
+impl Bookmark {
+    pub fn new(name: String, location: Location, iterations: u32) -> Self {
+        Bookmark {
+            name,
+            bounds: location.bounds(),
+            iterations,
+            rotation: location.rotation,
+            origin: encode_origin(&location.origin),
+        }
+    }
+
+    /// Where the bookmarked view looks, or `None` if its origin is malformed.
+    pub fn location(&self) -> Option<Location> {
+        let origin = decode_origin(&self.origin)?;
+        Some(Location::new(origin, self.bounds, self.rotation))
+    }
+}
+
+fn is_straight(rotation: &f64) -> bool {
+    *rotation == 0.
+}
+
+/// Decimal coordinates of `origin`, or `None` for the zero origin of views
+/// that aren't deep.
+pub fn encode_origin(origin: &Origin) -> Option<[String; 2]> {
+    (*origin != Origin::default()).then(|| {
+        let (re, im) = origin.to_decimal();
+        [re, im]
+    })
+}
+
+/// The origin [`encode_origin`] encoded, or `None` if it is malformed.
+pub fn decode_origin(origin: &Option<[String; 2]>) -> Option<Origin> {
+    match origin {
+        Some([re, im]) => Origin::parse(re, im),
+        None => Some(Origin::default()),
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct BookmarkFile {
+    #[serde(default, rename = "bookmark")]
+    bookmarks: Vec<Bookmark>,
+}
+
+/// Directory of the program's configuration, following the XDG base
+/// directory specification.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("mandelbrot"))
+}
+
+fn path() -> io::Result<PathBuf> {
+    config_dir()
+        .map(|dir| dir.join("bookmarks.toml"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))
+}
+
+/// Reads the saved bookmarks; there are none if the file doesn't exist yet.
+pub fn load() -> Result<Vec<Bookmark>, Box<dyn Error>> {
+    match fs::read_to_string(path()?) {
+        Ok(source) => Ok(toml::from_str::<BookmarkFile>(&source)?.bookmarks),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn save(bookmarks: &[Bookmark]) -> Result<(), Box<dyn Error>> {
+    let path = path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let file = BookmarkFile {
+        bookmarks: bookmarks.to_vec(),
+    };
+    fs::write(path, toml::to_string(&file)?)?;
+    Ok(())
+}
+
+/// Adds `bookmark`, replacing any bookmark of the same name.
+pub fn insert(bookmarks: &mut Vec<Bookmark>, bookmark: Bookmark) {
+    match bookmarks.iter_mut().find(|b| b.name == bookmark.name) {
+        Some(existing) => *existing = bookmark,
+        None => bookmarks.push(bookmark),
+    }
+}
+
+/// Finds a bookmark by name or by its 1-based position in the list.
+pub fn find<'a>(bookmarks: &'a [Bookmark], key: &str) -> Option<&'a Bookmark> {
+    bookmarks.iter().find(|b| b.name == key).or_else(|| {
+        key.parse::<usize>()
+            .ok()
+            .and_then(|i| bookmarks.get(i.checked_sub(1)?))
+    })
+}
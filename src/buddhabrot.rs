@@ -0,0 +1,179 @@
+//! Buddhabrot rendering: a density histogram of the orbits of escaping points.
+
+use rayon::prelude::*;
+
+use crate::{
+    color::{density_color, Cell, CellWriter, Shader, Style},
+    fractal::orbit,
+    ColorMode, Params, Viewport, C,
+};
+
+/// Region of the plane the starting points are drawn from.
+const SAMPLE_MIN: f64 = -2.;
+const SAMPLE_EXTENT: f64 = 4.;
+const CHUNK_SIZE: u64 = 1 << 14;
+
+/// Minimal xorshift generator, good enough for scattering sample points.
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // Spread consecutive seeds apart and avoid the all-zero state.
+        XorShift(seed.wrapping_mul(0x9E3779B97F4A7C15) | 1)
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn accumulate(
+    viewport: &Viewport,
+    params: &Params,
+    (width, height): (u32, u32),
+    chunk: u64,
+    samples: u64,
+    histogram: &mut [u32],
+    orbit_points: &mut Vec<C>,
+) {
+    let mut rng = XorShift::new(chunk);
+    let (sx, sy) = (
+        width as f64 / viewport.width as f64,
+        height as f64 / viewport.height as f64,
+    );
+
+    for _ in 0..samples {
+        let c = C {
+            re: SAMPLE_MIN + rng.next_f64() * SAMPLE_EXTENT,
+            im: SAMPLE_MIN + rng.next_f64() * SAMPLE_EXTENT,
+        };
+
+        orbit_points.clear();
+        if orbit(c, params, |z| orbit_points.push(z)).is_none() {
+            continue;
+        }
+
+        // The first iterate is the starting point itself, which would only
+        // add a uniform haze over the sampled region.
+        for z in orbit_points.iter().skip(1) {
+            let (x, y) = viewport.from_complex(z.re, z.im);
+            let (px, py) = (x * sx, y * sy);
+            if px >= 0. && py >= 0. && px < width as f64 && py < height as f64 {
+                histogram[py as usize * width as usize + px as usize] += 1;
+            }
+        }
+    }
+}
+
+/// Renders the Buddhabrot of `viewport` from `samples` random starting
+/// points into a row-major `width * height` grid of densities in `0..=1`.
+pub fn render(
+    viewport: &Viewport,
+    params: &Params,
+    width: u32,
+    height: u32,
+    samples: u64,
+) -> Vec<f64> {
+    let size = width as usize * height as usize;
+    let chunks = samples.div_ceil(CHUNK_SIZE);
+
+    let histogram = (0..chunks)
+        .into_par_iter()
+        .fold(
+            || (vec![0u32; size], Vec::new()),
+            |(mut histogram, mut orbit_points), chunk| {
+                let n = CHUNK_SIZE.min(samples - chunk * CHUNK_SIZE);
+                accumulate(
+                    viewport,
+                    params,
+                    (width, height),
+                    chunk,
+                    n,
+                    &mut histogram,
+                    &mut orbit_points,
+                );
+                (histogram, orbit_points)
+            },
+        )
+        .map(|(histogram, _)| histogram)
+        .reduce(
+            || vec![0u32; size],
+            |mut a, b| {
+                a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+                a
+            },
+        );
+
+    // The square root keeps faint orbits visible next to the bright core.
+    let max = histogram.iter().copied().max().unwrap_or(0).max(1) as f64;
+    histogram
+        .into_iter()
+        .map(|count| (count as f64 / max).sqrt())
+        .collect()
+}
+
+/// Renders the Buddhabrot of `viewport` into a string of `width * height` cells.
+pub fn render_to_buffer(
+    viewport: &Viewport,
+    params: &Params,
+    mode: ColorMode,
+    samples: u64,
+) -> String {
+    let density = render(
+        viewport,
+        params,
+        viewport.width as u32,
+        viewport.height as u32,
+        samples,
+    );
+
+    let style = Style::default();
+    let shader = Shader::new(&style, 1., 0, [].iter());
+    density
+        .chunks(viewport.width.max(1) as usize)
+        .map(|row| {
+            let mut writer = CellWriter::new(mode, &shader);
+            row.iter().for_each(|&t| writer.push_density(t));
+            writer.finish()
+        })
+        .collect()
+}
+
+/// Like [`render_to_buffer`], but into the cells themselves, row-major.
+pub fn render_to_cells(
+    viewport: &Viewport,
+    params: &Params,
+    mode: ColorMode,
+    samples: u64,
+) -> Vec<Cell> {
+    let density = render(
+        viewport,
+        params,
+        viewport.width as u32,
+        viewport.height as u32,
+        samples,
+    );
+
+    let style = Style::default();
+    let shader = Shader::new(&style, 1., 0, [].iter());
+    let mut writer = CellWriter::new(mode, &shader);
+    density.iter().for_each(|&t| writer.push_density(t));
+    writer.into_cells()
+}
+
+/// Renders the Buddhabrot of `viewport` into a row-major `width * height` RGB image.
+pub fn render_to_pixels(
+    viewport: &Viewport,
+    params: &Params,
+    width: u32,
+    height: u32,
+    samples: u64,
+) -> Vec<[u8; 3]> {
+    render(viewport, params, width, height, samples)
+        .into_iter()
+        .map(density_color)
+        .collect()
+}
@@ -0,0 +1,111 @@
+use mandelbrot::{Cell, Frame};
+use ratatui::{
+    buffer::{Buffer, CellDiffOption},
+    layout::Rect,
+    widgets::Widget,
+};
+
+use crate::{render, render::Picture, ui::put_cells, Settings};
+
+/// What the view shows of the fractal: text cells, or an image the graphics
+/// backend draws over them.
+pub(crate) struct Canvas {
+    /// Text cells, row-major.
+    cells: Vec<Cell>,
+    width: usize,
+    /// Image shown instead of the cells, and whether it is yet to be drawn.
+    image: Option<(String, bool)>,
+}
+
+impl Canvas {
+    pub fn new() -> Self {
+        Canvas {
+            cells: Vec::new(),
+            width: 0,
+            image: None,
+        }
+    }
+
+    /// Whether the canvas shows text, which can be drawn over cell by cell.
+    pub fn is_text(&self) -> bool {
+        self.image.is_none()
+    }
+
+    /// Shows the cells of the text `frame` in the colors of `settings`.
+    pub fn show(&mut self, frame: &Frame, settings: &Settings) {
+        self.show_cells(frame.to_cells(settings.mode, &settings.style), frame.width);
+    }
+
+    /// Shows `cells`, `width` to a row.
+    pub fn show_cells(&mut self, cells: Vec<Cell>, width: usize) {
+        (self.cells, self.width, self.image) = (cells, width, None);
+    }
+
+    /// Shows the text band `part` over the cells shown, from row `top` down,
+    /// as a render comes in.
+    pub fn show_band(&mut self, part: &Frame, top: usize, settings: &Settings) {
+        if self.width != part.width || self.image.is_some() {
+            (self.cells, self.width, self.image) = (Vec::new(), part.width, None);
+        }
+        let (start, end) = (top * self.width, (top + part.height) * self.width);
+        let blank = Cell {
+            symbol: ' ',
+            fg: None,
+            bg: None,
+        };
+        if self.cells.len() < end {
+            self.cells.resize(end, blank);
+        }
+        let cells = part.to_cells(settings.mode, &settings.style);
+        self.cells[start..end].copy_from_slice(&cells);
+    }
+
+    /// Shows `image`, encoded for the graphics backend.
+    pub fn show_image(&mut self, image: String) {
+        self.image = Some((image, true));
+    }
+
+    /// Shows `frame` again, colored anew.
+    pub fn recolor(&mut self, frame: &Frame, settings: &Settings) {
+        match render::frame_output(frame, settings) {
+            Picture::Image(image) => self.show_image(image),
+            _ => self.show(frame, settings),
+        }
+    }
+
+    /// Has the image drawn again, over whatever was drawn over it.
+    pub fn redraw(&mut self) {
+        if let Some((_, pending)) = &mut self.image {
+            *pending = true;
+        }
+    }
+
+    /// The image, if it is yet to be drawn, which it is not once this
+    /// returns it.
+    pub fn take_image(&mut self) -> Option<&str> {
+        match &mut self.image {
+            Some((image, pending)) if *pending => {
+                *pending = false;
+                Some(image)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Widget for &Canvas {
+    fn render(self, area: Rect, buffer: &mut Buffer) {
+        if self.image.is_some() {
+            // Ratatui leaves the cells under the image alone.
+            for position in area.positions() {
+                buffer[position].set_diff_option(CellDiffOption::Skip);
+            }
+            return;
+        }
+        let rows = self.cells.chunks(self.width.max(1));
+        for (y, row) in rows.take(area.height as usize).enumerate() {
+            let row = &row[..row.len().min(area.width as usize)];
+            put_cells(buffer, (area.x, area.y + y as u16), row, row.len().max(1));
+        }
+    }
+}
@@ -0,0 +1,752 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use mandelbrot::{
+    formula::Formula, palette::Palette, Bounds, Coloring, Fractal, Params, Precision, Ramp, Trap, C,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::{Easing, Sweep},
+    kfr,
+    link::{self, Link},
+    par, upr,
+};
+
+/// Magnification of each press of the zoom keys, unless configured.
+pub const DEFAULT_ZOOM_STEP: f64 = 2.;
+/// Pixels of exported images, unless given.
+pub const DEFAULT_SIZE: (u32, u32) = (3840, 2160);
+/// Cells of text rendered without a screen to match, unless given.
+pub const DEFAULT_TEXT_SIZE: (u32, u32) = (120, 40);
+
+#[derive(Parser, Clone)]
+#[command(about = "Explore the Mandelbrot set in the terminal")]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub open: Open,
+
+    #[command(flatten)]
+    pub interactive: Interactive,
+
+    /// Center of the view, as RE,IM [default: center of the fractal]
+    #[arg(long, global = true, allow_hyphen_values = true, value_parser = parse_complex)]
+    pub center: Option<(f64, f64)>,
+
+    /// Magnification relative to the default view
+    #[arg(long, global = true, default_value_t = 1., value_parser = parse_zoom)]
+    pub zoom: f64,
+
+    /// Degrees the view is turned by, counterclockwise
+    #[arg(long, global = true, default_value_t = 0., allow_hyphen_values = true)]
+    pub rotation: f64,
+
+    /// Magnification of each press of the zoom keys (+/- or z/x) [default: 2]
+    #[arg(long, global = true, value_parser = parse_zoom_step)]
+    pub zoom_step: Option<f64>,
+
+    /// Pace of zoom transitions, animations and tours [default:
+    /// exponential]
+    #[arg(long, global = true, value_enum)]
+    pub easing: Option<Easing>,
+
+    /// Fractal to render
+    #[arg(long, global = true, value_enum)]
+    pub fractal: Option<FractalKind>,
+
+    /// Constant of the Julia set, as RE,IM; implies `--fractal julia`. In the
+    /// view, the `toggle-julia` key (J unless the config binds it to another)
+    /// opens the Julia set of the point under the pointer
+    #[arg(long, global = true, allow_hyphen_values = true, value_parser = parse_complex)]
+    pub julia: Option<(f64, f64)>,
+
+    /// Iterate a custom formula of `z` and `c` such as "z^3 + c*z", starting from `z = c`
+    #[arg(long, global = true, value_parser = parse_formula)]
+    pub formula: Option<Formula>,
+
+    /// Exponent `d` of the iteration `z = z^d + c`, any number but 1
+    #[arg(long, global = true, allow_negative_numbers = true, default_value_t = Params::default().exponent, value_parser = parse_exponent)]
+    pub exponent: f64,
+
+    /// How escape times are mapped onto the palette
+    #[arg(long, global = true, value_enum, default_value_t = ColoringKind::Cyclic)]
+    pub coloring: ColoringKind,
+
+    /// Palette to color with: a built-in name (default, fire, ocean, grayscale)
+    /// or a Fractint `.map`, TOML gradient or hex color list file. May be repeated
+    #[arg(long, global = true, value_parser = parse_palette)]
+    pub palette: Vec<Palette>,
+
+    /// Characters shading monochrome text, from the quickest escaping
+    /// points to those that never escape [default: " .:-=+*#%@"]
+    #[arg(long, global = true, value_parser = parse_ramp)]
+    pub ramp: Option<Ramp>,
+
+    /// Orbit trap used by `--coloring orbit-trap`: point[:RE,IM], line[:DEGREES] or circle[:RADIUS]
+    #[arg(long, global = true, default_value = "point", value_parser = parse_trap)]
+    pub trap: Trap,
+
+    /// Render the density of escaping orbits (Buddhabrot) instead of escape times
+    #[arg(long, global = true)]
+    pub buddhabrot: bool,
+
+    /// Random starting points per pixel in Buddhabrot mode
+    #[arg(long, global = true, default_value_t = 64)]
+    pub samples: u64,
+
+    /// Maximum number of iterations per point [default: grows with the zoom]
+    #[arg(long, global = true)]
+    pub iterations: Option<u32>,
+
+    /// Samples along each axis averaged into every cell or pixel, for
+    /// antialiasing
+    #[arg(long, global = true, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=16))]
+    pub aa: u32,
+
+    /// Only antialias cells on edges, where neighbours escape after very
+    /// different numbers of iterations
+    #[arg(long, global = true)]
+    pub aa_adaptive: bool,
+
+    /// Arithmetic points are iterated in [default: the quickest that
+    /// resolves the view]
+    #[arg(long, global = true, value_enum)]
+    pub precision: Option<PrecisionKind>,
+
+    /// Squared magnitude above which a point counts as escaped
+    #[arg(long, global = true, default_value_t = Params::default().cutoff)]
+    pub cutoff: f64,
+
+    /// Height of a terminal cell over its width, which the view is stretched
+    /// by so the plane keeps its proportions on screen
+    #[arg(long, global = true, default_value_t = 2., value_parser = parse_cell_aspect)]
+    pub cell_aspect: f64,
+
+    /// Resolution of exported images, as WIDTHxHEIGHT, in cells for text up
+    /// to 65535 a side [default: 3840x2160, 640x360 for GIFs, 1920x1080 for
+    /// videos and benchmarks, and for text the screen or 120x40]
+    #[arg(long, global = true, value_parser = parse_size)]
+    pub size: Option<(u32, u32)>,
+
+    /// Session saved with Ctrl-s to restore, overriding the view and
+    /// settings its file holds
+    #[arg(long, global = true)]
+    pub session: Option<PathBuf>,
+
+    /// File that exported images are written to, or `-` for stdout
+    /// [default: mandelbrot.png, .mp4 for animations]
+    #[arg(short, long, global = true)]
+    pub output: Option<PathBuf>,
+
+    /// Format of exported images [default: from the extension of the file,
+    /// or PNG]
+    #[arg(long, global = true)]
+    pub format: Option<ImageFormat>,
+
+    /// Contours of SVG exports: the boundary of the set, and below it
+    /// iteration levels spread up to the iteration limit
+    #[arg(long, global = true, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..=256))]
+    pub contours: u32,
+
+    /// Render PNG and PPM exports in bands kept next to the output until it
+    /// is written, holding little of the image in memory and picking up
+    /// where an interrupted export stopped; images of over 100 megapixels
+    /// always are
+    #[arg(long, global = true)]
+    pub tiled: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FractalKind {
+    Mandelbrot,
+    Julia,
+    BurningShip,
+    Tricorn,
+    Newton,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PrecisionKind {
+    /// Quickest, for views whose points are at least about 1e-5 apart
+    F32,
+    /// Resolves views down to points about 1e-12 apart
+    F64,
+    /// Follow a reference orbit in arbitrary precision; Mandelbrot and Julia sets only
+    Perturbed,
+    /// Pairs of f64, down to points about 1e-28 apart
+    DoubleDouble,
+    /// As deep as the view goes, but slowest
+    Arbitrary,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ColoringKind {
+    /// Repeat the palette every fixed number of iterations
+    Cyclic,
+    /// Spread the palette evenly over the escape times of each frame
+    Histogram,
+    /// Color points by the closest approach of their orbit to `--trap`
+    OrbitTrap,
+    /// Highlight the boundary of the set by estimating each point's distance to it
+    Distance,
+}
+
+impl From<ColoringKind> for Coloring {
+    fn from(kind: ColoringKind) -> Self {
+        match kind {
+            ColoringKind::Cyclic => Coloring::Cyclic,
+            ColoringKind::Histogram => Coloring::Histogram,
+            ColoringKind::OrbitTrap => Coloring::OrbitTrap,
+            ColoringKind::Distance => Coloring::Distance,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ImageFormat {
+    Png,
+    /// Binary PPM of the colors
+    Ppm,
+    /// Binary PGM of the iteration counts, in 16 bits above 255 iterations
+    Pgm,
+    /// Text of the view as the screen shows it in ASCII
+    Txt,
+    /// Line art of the contours between iteration bands
+    Svg,
+    /// NumPy array of the iteration counts and smooth escape values
+    Npy,
+    /// Flat little-endian dump of the iteration counts and smooth escape
+    /// values, after a header of the size and iteration limit
+    Raw,
+}
+
+impl ImageFormat {
+    /// `format`, or the one the extension of `path` names.
+    pub fn of(path: &Path, format: Option<ImageFormat>) -> ImageFormat {
+        let extension = path.extension().and_then(|e| e.to_str());
+        match (format, extension.map(str::to_ascii_lowercase).as_deref()) {
+            (Some(format), _) => format,
+            (None, Some("ppm")) => ImageFormat::Ppm,
+            (None, Some("pgm")) => ImageFormat::Pgm,
+            (None, Some("txt")) => ImageFormat::Txt,
+            (None, Some("svg")) => ImageFormat::Svg,
+            (None, Some("npy")) => ImageFormat::Npy,
+            (None, Some("raw" | "bin")) => ImageFormat::Raw,
+            (None, _) => ImageFormat::Png,
+        }
+    }
+
+    /// Extension of files in the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Ppm => "ppm",
+            ImageFormat::Pgm => "pgm",
+            ImageFormat::Txt => "txt",
+            ImageFormat::Svg => "svg",
+            ImageFormat::Npy => "npy",
+            ImageFormat::Raw => "raw",
+        }
+    }
+}
+
+/// A zoom between two views, rendered frame by frame.
+#[derive(clap::Args, Clone)]
+pub struct Animation {
+    /// Bookmark or session file to start from [default: the view the other
+    /// flags give]
+    #[arg(long)]
+    pub from: Option<String>,
+    /// Bookmark or session file to end at
+    #[arg(long)]
+    pub to: String,
+    /// Frames rendered, including those of the two views
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(2..))]
+    pub frames: u32,
+    /// Frames shown per second
+    #[arg(long, default_value_t = 25, value_parser = clap::value_parser!(u32).range(1..))]
+    pub fps: u32,
+    /// Degrees the view turns by over the animation, counterclockwise
+    #[arg(long, default_value_t = 0., allow_hyphen_values = true)]
+    pub spin: f64,
+}
+
+/// Path the constant of the Julia set is swept along, one of which is given.
+#[derive(clap::Args, Clone)]
+#[group(required = true, multiple = false)]
+pub struct SweepPath {
+    /// Sweep the constant around a circle, as RE,IM:RADIUS, such as one
+    /// about a point of the Mandelbrot set
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_circle)]
+    pub circle: Option<Sweep>,
+    /// Sweep the constant along a line, as RE,IM:RE,IM
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_line)]
+    pub line: Option<Sweep>,
+}
+
+/// A sweep of the constant of the Julia set, played or rendered frame by
+/// frame.
+#[derive(clap::Args, Clone)]
+pub struct Morph {
+    #[command(flatten)]
+    pub path: SweepPath,
+    /// Frames rendered to the output, spread over one sweep
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(2..))]
+    pub frames: u32,
+    /// Frames shown per second in the output
+    #[arg(long, default_value_t = 25, value_parser = clap::value_parser!(u32).range(1..))]
+    pub fps: u32,
+    /// Seconds a sweep takes in the terminal, where lines are swept back
+    /// and forth
+    #[arg(long, default_value_t = 10., value_parser = parse_period)]
+    pub period: f64,
+}
+
+impl Morph {
+    pub fn sweep(&self) -> Sweep {
+        let path = &self.path;
+        path.circle
+            .or(path.line)
+            .expect("a circle or line is given")
+    }
+}
+
+/// View a subcommand starts from.
+#[derive(clap::Args, Clone)]
+pub struct Open {
+    /// View to open, overriding the flags and session it gives settings
+    /// of: a deep link such as `mandel:?re=RE&im=IM&zoom=ZOOM`, a Kalles
+    /// Fraktaler `.kfr` location file, an Ultra Fractal `.upr` parameter
+    /// file or a Fractint `.par` parameter file, as `FILE.par#NAME` for an
+    /// entry other than its first
+    #[arg(value_parser = parse_view)]
+    pub view: Option<Link>,
+}
+
+/// Flags of the interactive view, which runs without a subcommand too.
+#[derive(clap::Args, Clone)]
+pub struct Interactive {
+    /// Memory in MiB for escape results kept to reuse when panning
+    #[arg(long, default_value_t = 64)]
+    pub cache_size: usize,
+
+    /// File to append the render and draw times of every frame to, with
+    /// their rolling average
+    #[arg(long)]
+    pub frame_log: Option<PathBuf>,
+
+    /// Library the terminal is driven through [default: termion on Unix,
+    /// crossterm elsewhere]
+    #[arg(long, value_enum)]
+    pub terminal: Option<TerminalKind>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum TerminalKind {
+    /// Escape sequences on Unix terminals
+    Termion,
+    /// Also drives the Windows console, as in Windows Terminal and PowerShell
+    Crossterm,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// Explore the view in the terminal, as without a subcommand
+    View {
+        #[command(flatten)]
+        open: Open,
+        #[command(flatten)]
+        interactive: Interactive,
+    },
+    /// Render the view to the output file and exit
+    Render {
+        #[command(flatten)]
+        open: Open,
+        /// Render every view of a TOML file of `[[job]]` tables instead,
+        /// several at once, each with an `output` path and optionally a
+        /// `view`, `center` (RE,IM), `zoom`, `iterations`, `size`
+        /// (WIDTHxHEIGHT) and `palette` changed from the flags
+        #[arg(long)]
+        jobs: Option<PathBuf>,
+    },
+    /// Render a zoom from one saved view to another as a video, encoded by
+    /// ffmpeg from the extension of the output file, or as an animated GIF
+    /// for `.gif` files
+    #[command(alias = "video")]
+    Animate {
+        #[command(flatten)]
+        open: Open,
+        #[command(flatten)]
+        animation: Animation,
+        /// Write the frames as numbered PNG files into this directory
+        /// instead of running ffmpeg
+        #[arg(long)]
+        frame_dir: Option<PathBuf>,
+        /// ffmpeg program to run
+        #[arg(long, default_value = "ffmpeg")]
+        ffmpeg: PathBuf,
+    },
+    /// Render the view to an animated GIF; `animate` with a `.gif` output
+    #[command(hide = true)]
+    Gif(Animation),
+    /// Sweep the constant of the Julia set along a circle or line, playing
+    /// it in the terminal until a key is pressed, or rendering it as
+    /// `animate` does when an output file or frame directory is given
+    Morph {
+        #[command(flatten)]
+        morph: Morph,
+        /// Write the frames as numbered PNG files into this directory
+        /// instead of running ffmpeg
+        #[arg(long)]
+        frame_dir: Option<PathBuf>,
+        /// ffmpeg program to run
+        #[arg(long, default_value = "ffmpeg")]
+        ffmpeg: PathBuf,
+    },
+    /// Fly through the keyframes of a TOML tour file in the terminal, until
+    /// a key is pressed. Each `[[keyframe]]` has a `center` (RE,IM) and
+    /// `zoom`, and optionally the `duration` in seconds taken to fly there
+    /// [default: 4], how long to `hold` it [default: 0] and the `easing` of
+    /// the flight [default: --easing]
+    Tour {
+        file: PathBuf,
+        /// Start over at the end of the tour
+        #[arg(long = "loop")]
+        repeat: bool,
+    },
+    /// Time renders of the view at the export resolution without writing
+    /// them
+    Bench {
+        #[command(flatten)]
+        open: Open,
+        /// Renders timed, after one to warm up
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+        runs: u32,
+    },
+}
+
+impl Args {
+    pub fn params(&self) -> Params {
+        Params {
+            max_iterations: Params::default().max_iterations,
+            cutoff: self.cutoff,
+            fractal: self.fractal(),
+            exponent: self.exponent,
+            trap: match self.coloring {
+                ColoringKind::OrbitTrap => Some(self.trap),
+                _ => None,
+            },
+            distance_estimate: matches!(self.coloring, ColoringKind::Distance),
+            supersampling: self.aa,
+            adaptive_supersampling: self.aa_adaptive,
+            precision: self.precision.map(|precision| match precision {
+                PrecisionKind::F32 => Precision::F32,
+                PrecisionKind::F64 => Precision::F64,
+                PrecisionKind::Perturbed => Precision::Perturbed,
+                PrecisionKind::DoubleDouble => Precision::DoubleDouble,
+                PrecisionKind::Arbitrary => Precision::Arbitrary,
+            }),
+        }
+    }
+
+    fn fractal(&self) -> Fractal {
+        if let Some(formula) = &self.formula {
+            return Fractal::Formula(Arc::new(formula.clone()));
+        }
+
+        let julia = match &self.command {
+            Some(Command::Morph { morph, .. }) => morph.sweep().at(0.),
+            _ => self.julia.map_or(Fractal::DEFAULT_JULIA, C::from),
+        };
+
+        match (self.fractal, self.julia) {
+            _ if matches!(self.command, Some(Command::Morph { .. })) => Fractal::Julia(julia),
+            (Some(FractalKind::Julia), _) | (None, Some(_)) => Fractal::Julia(julia),
+            (Some(FractalKind::BurningShip), _) => Fractal::BurningShip,
+            (Some(FractalKind::Tricorn), _) => Fractal::Tricorn,
+            (Some(FractalKind::Newton), _) => Fractal::Newton,
+            (Some(FractalKind::Mandelbrot), _) | (None, None) => Fractal::Mandelbrot,
+        }
+    }
+
+    /// The view to open, given to the subcommand or before it.
+    pub fn view(&self) -> Option<&Link> {
+        let open = match &self.command {
+            Some(
+                Command::View { open, .. }
+                | Command::Render { open, .. }
+                | Command::Animate { open, .. }
+                | Command::Bench { open, .. },
+            ) if open.view.is_some() => open,
+            _ => &self.open,
+        };
+        open.view.as_ref()
+    }
+
+    /// Flags of the interactive view, given to `view` or without a
+    /// subcommand.
+    pub fn interactive(&self) -> &Interactive {
+        match &self.command {
+            Some(Command::View { interactive, .. }) => interactive,
+            _ => &self.interactive,
+        }
+    }
+
+    /// Whether the animation export writes an animated GIF.
+    pub fn is_gif(&self) -> bool {
+        match &self.command {
+            Some(Command::Gif(_)) => true,
+            Some(Command::Animate { .. } | Command::Morph { .. }) => self
+                .output
+                .as_ref()
+                .and_then(|path| path.extension())
+                .is_some_and(|e| e.eq_ignore_ascii_case("gif")),
+            _ => false,
+        }
+    }
+
+    /// File to export to, named for the command if not given.
+    pub fn output(&self) -> PathBuf {
+        match (&self.output, &self.command) {
+            (Some(output), _) => output.clone(),
+            (None, Some(Command::Gif(_))) => PathBuf::from("mandelbrot.gif"),
+            (None, Some(Command::Animate { .. } | Command::Morph { .. })) => {
+                PathBuf::from("mandelbrot.mp4")
+            }
+            (None, _) => PathBuf::from("mandelbrot.png"),
+        }
+    }
+
+    /// Resolution to export at, smaller for animations and benchmarks if not
+    /// given.
+    pub fn size(&self) -> (u32, u32) {
+        match (self.size, &self.command) {
+            (Some(size), _) => size,
+            (None, _) if self.is_gif() => (640, 360),
+            (
+                None,
+                Some(Command::Animate { .. } | Command::Morph { .. } | Command::Bench { .. }),
+            ) => (1920, 1080),
+            (None, _) => match ImageFormat::of(&self.output(), self.format) {
+                ImageFormat::Txt => DEFAULT_TEXT_SIZE,
+                _ => DEFAULT_SIZE,
+            },
+        }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        view_bounds(self.fractal().default_bounds(), self.center, self.zoom)
+    }
+}
+
+/// Bounds of `default` magnified by `zoom` around `center`, or around the
+/// center of `default` if none is given.
+pub fn view_bounds(default: Bounds, center: Option<(f64, f64)>, zoom: f64) -> Bounds {
+    let ((x_min, width), (y_min, height)) = default;
+    let (x, y) = center.unwrap_or((x_min + width / 2., y_min + height / 2.));
+    let (width, height) = (width / zoom, height / zoom);
+
+    ((x - width / 2., width), (y - height / 2., height))
+}
+
+/// Where to go: a center, with the magnification and iteration limit to
+/// show it at if they are given.
+pub type Place = ((f64, f64), Option<f64>, Option<u32>);
+
+/// Parses a location as `RE IM` or `RE,IM`, optionally followed by `@ ZOOM`
+/// and then by `; ITERATIONS`, as copied from the view.
+pub fn parse_location(s: &str) -> Result<Place, String> {
+    let (s, iterations) = match s.split_once(';') {
+        Some((s, iterations)) => {
+            let n = iterations.trim();
+            let n = n.strip_suffix("iterations").unwrap_or(n).trim();
+            match n.parse::<u32>() {
+                Ok(n) if n > 0 => (s, Some(n)),
+                _ => return Err(format!("invalid iteration limit `{}`", iterations.trim())),
+            }
+        }
+        None => (s, None),
+    };
+    let (center, zoom) = match s.split_once('@') {
+        Some((center, zoom)) => (center, Some(parse_zoom(zoom.trim())?)),
+        None => (s, None),
+    };
+
+    let center = center.trim();
+    let center = match center.split_once(',') {
+        Some(_) => parse_complex(center)?,
+        None => match center.split_whitespace().collect::<Vec<_>>()[..] {
+            [re, im] => parse_complex(&format!("{},{}", re, im))?,
+            _ => return Err(format!("expected RE IM [@ ZOOM], got `{}`", s)),
+        },
+    };
+
+    Ok((center, zoom, iterations))
+}
+
+pub fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let (width, height) = s
+        .split_once('x')
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got `{}`", s))?;
+    let parse = |v: &str| match v.parse::<u32>() {
+        Ok(0) | Err(_) => Err(format!("invalid dimension `{}`", v)),
+        Ok(v) => Ok(v),
+    };
+
+    Ok((parse(width)?, parse(height)?))
+}
+
+pub fn parse_view(s: &str) -> Result<Link, String> {
+    if link::is_link(s) {
+        return link::parse(s);
+    }
+    if par::is_par(s) {
+        return par::load(s);
+    }
+    let path = Path::new(s);
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(e) if e.eq_ignore_ascii_case("kfr") => kfr::load(path),
+        Some(e) if e.eq_ignore_ascii_case("upr") => upr::load(path),
+        _ => Err(format!(
+            "expected a link, or a .kfr, .upr or .par file, got `{}`",
+            s
+        )),
+    }
+}
+
+fn parse_complex(s: &str) -> Result<(f64, f64), String> {
+    let (re, im) = s
+        .split_once(',')
+        .ok_or_else(|| format!("expected RE,IM, got `{}`", s))?;
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<f64>()
+            .map_err(|_| format!("invalid coordinate `{}`", v))
+    };
+
+    Ok((parse(re)?, parse(im)?))
+}
+
+/// Parses a circle of constants, as RE,IM:RADIUS.
+fn parse_circle(s: &str) -> Result<Sweep, String> {
+    let (center, radius) = s
+        .rsplit_once(':')
+        .ok_or_else(|| format!("expected RE,IM:RADIUS, got `{}`", s))?;
+    match radius.trim().parse::<f64>() {
+        Ok(radius) if radius > 0. && radius.is_finite() => {
+            Ok(Sweep::Circle(parse_complex(center)?.into(), radius))
+        }
+        _ => Err(format!(
+            "radius must be a positive number, got `{}`",
+            radius
+        )),
+    }
+}
+
+/// Parses a line between two constants, as RE,IM:RE,IM.
+fn parse_line(s: &str) -> Result<Sweep, String> {
+    let (from, to) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected RE,IM:RE,IM, got `{}`", s))?;
+    Ok(Sweep::Line(
+        parse_complex(from)?.into(),
+        parse_complex(to)?.into(),
+    ))
+}
+
+fn parse_period(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(period) if period > 0. && period.is_finite() => Ok(period),
+        _ => Err(format!(
+            "period must be a positive number of seconds, got `{}`",
+            s
+        )),
+    }
+}
+
+fn parse_zoom(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(zoom) if zoom > 0. && zoom.is_finite() => Ok(zoom),
+        _ => Err(format!("zoom must be a positive number, got `{}`", s)),
+    }
+}
+
+pub fn parse_zoom_step(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(step) if step > 1. && step.is_finite() => Ok(step),
+        _ => Err(format!("zoom step must be a number above 1, got `{}`", s)),
+    }
+}
+
+/// Checks an exponent `d` of the iteration `z = z^d + c`, wherever it comes
+/// from: `z = z + c` only drifts off, and infinite ones have no iterates.
+pub fn check_exponent(d: f64) -> Result<f64, String> {
+    match d {
+        d if d != 1. && d.is_finite() => Ok(d),
+        d => Err(format!(
+            "exponent must be a number other than 1, got `{}`",
+            d
+        )),
+    }
+}
+
+fn parse_exponent(s: &str) -> Result<f64, String> {
+    let d = s
+        .parse::<f64>()
+        .map_err(|_| format!("exponent must be a number other than 1, got `{}`", s))?;
+    check_exponent(d)
+}
+
+fn parse_cell_aspect(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(aspect) if aspect > 0. && aspect.is_finite() => Ok(aspect),
+        _ => Err(format!(
+            "cell aspect must be a positive number, got `{}`",
+            s
+        )),
+    }
+}
+
+fn parse_formula(s: &str) -> Result<Formula, String> {
+    Formula::parse(s).map_err(|e| e.to_string())
+}
+
+pub fn parse_ramp(s: &str) -> Result<Ramp, String> {
+    Ramp::new(s).ok_or_else(|| format!("a ramp needs at least two characters, got `{}`", s))
+}
+
+fn parse_trap(s: &str) -> Result<Trap, String> {
+    let (shape, arg) = match s.split_once(':') {
+        Some((shape, arg)) => (shape, Some(arg)),
+        None => (s, None),
+    };
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<f64>()
+            .map_err(|_| format!("invalid trap parameter `{}`", v))
+    };
+
+    match (shape, arg) {
+        ("point", None) => Ok(Trap::Point(C::from((0., 0.)))),
+        ("point", Some(p)) => Ok(Trap::Point(C::from(parse_complex(p)?))),
+        ("line", None) => Ok(Trap::Line(0.)),
+        ("line", Some(degrees)) => Ok(Trap::Line(parse(degrees)?.to_radians())),
+        ("circle", None) => Ok(Trap::Circle(1.)),
+        ("circle", Some(radius)) => Ok(Trap::Circle(parse(radius)?)),
+        _ => Err(format!("expected point, line or circle, got `{}`", s)),
+    }
+}
+
+pub fn parse_palette(s: &str) -> Result<Palette, String> {
+    match Palette::builtin(s) {
+        Some(palette) => Ok(palette),
+        None => Palette::load(Path::new(s)).map_err(|e| format!("{}: {}", s, e)),
+    }
+}
@@ -0,0 +1,419 @@
+use std::{
+    fmt::{self, Display, Write},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{palette::Palette, EscapeResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorMode {
+    Ascii,
+    Ansi256,
+    TrueColor,
+}
+
+/// How escape values are spread over the palette.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Coloring {
+    /// The palette repeats every fixed number of iterations.
+    #[default]
+    Cyclic,
+    /// Palette positions follow the cumulative distribution of escape values
+    /// in the frame, so detail is spread evenly regardless of zoom depth.
+    Histogram,
+    /// Points are colored by how close their orbit came to `Params::trap`,
+    /// including points inside the set. Falls back to `Cyclic` without a trap.
+    OrbitTrap,
+    /// Points are colored by their estimated distance to the set relative to
+    /// the sample spacing, highlighting filaments finer than a sample.
+    /// Falls back to `Cyclic` without a distance estimate.
+    Distance,
+}
+
+/// How many samples are packed into each terminal cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CellMode {
+    Full,
+    /// A sample per cell drawn as a space on a background of its color, so
+    /// cells join into solid blocks. Falls back to `Full` in ASCII.
+    Background,
+    /// Two vertically stacked samples drawn as `▀` with distinct foreground
+    /// and background colors. Needs a color mode; falls back to `Full` in ASCII.
+    HalfBlock,
+    /// A monochrome 2x4 grid of samples drawn as a braille pattern, with a
+    /// dot for every sample inside the set.
+    Braille,
+}
+
+// Bit of each braille dot, indexed by [row][column].
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Color a cell is drawn in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// One of the 256 colors of the terminal's palette.
+    Ansi(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Color of the 6x6x6 cube of 256-color palettes with components in
+    /// `0..6`.
+    fn cube(r: u8, g: u8, b: u8) -> Color {
+        Color::Ansi(16 + 36 * r + 6 * g + b)
+    }
+}
+
+/// A cell of a frame: the glyph it shows, and the colors it is drawn in,
+/// which are the terminal's own where they are `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub symbol: char,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+
+struct Foreground(Color);
+struct Background(Color);
+
+impl Display for Foreground {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Color::Ansi(v) => write!(f, "\x1b[38;5;{}m", v),
+            Color::Rgb(r, g, b) => write!(f, "\x1b[38;2;{};{};{}m", r, g, b),
+        }
+    }
+}
+
+impl Display for Background {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Color::Ansi(v) => write!(f, "\x1b[48;5;{}m", v),
+            Color::Rgb(r, g, b) => write!(f, "\x1b[48;2;{};{};{}m", r, g, b),
+        }
+    }
+}
+
+const FG_RESET: &str = "\x1b[39m";
+const BG_RESET: &str = "\x1b[49m";
+
+const ANSI_CYCLE: u32 = 64;
+const RGB_CYCLE: u32 = 128;
+
+// Base colors of the basins of a Newton fractal's roots.
+const ROOT_COLORS: [(f64, f64, f64); 3] = [(0.9, 0.2, 0.2), (0.2, 0.8, 0.3), (0.2, 0.4, 0.95)];
+
+/// Color of a Newton basin, darkening with the iterations needed to converge.
+fn root_color(root: u8, i: u32) -> (f64, f64, f64) {
+    let (r, g, b) = ROOT_COLORS[root as usize % ROOT_COLORS.len()];
+    let shade = 1. - (i.min(40) as f64 / 50.);
+
+    (r * shade, g * shade, b * shade)
+}
+
+/// Glyphs monochrome text shades samples with, from the quickest to escape
+/// to the points that never do.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ramp(Vec<char>);
+
+impl Ramp {
+    /// The ramp of the characters of `glyphs`, or `None` if there are fewer
+    /// than two: one for escaping points and one for the others.
+    pub fn new(glyphs: &str) -> Option<Ramp> {
+        let glyphs: Vec<char> = glyphs.chars().collect();
+        (glyphs.len() >= 2).then_some(Ramp(glyphs))
+    }
+
+    /// Glyph of a sample escaping after `iterations`, out of at most
+    /// `max_iterations`, spreading the escaping glyphs evenly over them.
+    fn glyph(&self, iterations: Option<u32>, max_iterations: u32) -> char {
+        let escaping = self.0.len() - 1;
+        match iterations {
+            None => self.0[escaping],
+            Some(i) => {
+                let k = i as u64 * escaping as u64 / (max_iterations as u64 + 1);
+                self.0[(k as usize).min(escaping - 1)]
+            }
+        }
+    }
+}
+
+impl Default for Ramp {
+    fn default() -> Self {
+        Ramp::new(" .:-=+*#%@").unwrap()
+    }
+}
+
+/// A coloring of escape results for schemes [`Coloring`] doesn't have,
+/// plugged in through [`Style::color_map`]. Frames are colored again with
+/// it without iterating them again.
+pub trait ColorMap: fmt::Debug + Send + Sync {
+    /// Color of `result` with components in `0..=1`, for a frame whose
+    /// neighbouring samples are `spacing` apart in the plane.
+    fn color(&self, result: EscapeResult, spacing: f64) -> (f64, f64, f64);
+}
+
+/// How escape results are turned into colors.
+#[derive(Debug, Clone, Default)]
+pub struct Style {
+    pub coloring: Coloring,
+    pub palette: Palette,
+    /// Shift of palette positions in `0..1`, advanced for color cycling.
+    pub offset: f64,
+    /// Glyphs of monochrome text.
+    pub ramp: Ramp,
+    /// Coloring taking over from `coloring` and `palette`, if set.
+    pub color_map: Option<Arc<dyn ColorMap>>,
+}
+
+/// Per-frame mapping from escape results to colors.
+#[derive(Debug)]
+pub(crate) struct Shader<'a> {
+    style: &'a Style,
+    /// Sorted smooth escape values of the frame, for histogram coloring.
+    sorted: Vec<f64>,
+    /// Distance in the plane between neighbouring samples.
+    spacing: f64,
+    /// Iteration limit of the samples, which glyphs are spread over.
+    max_iterations: u32,
+}
+
+impl<'a> Shader<'a> {
+    pub fn new<'r>(
+        style: &'a Style,
+        spacing: f64,
+        max_iterations: u32,
+        results: impl Iterator<Item = &'r EscapeResult>,
+    ) -> Self {
+        let mut sorted = Vec::new();
+        if style.coloring == Coloring::Histogram {
+            sorted = results
+                .filter(|r| r.iterations.is_some() && r.root.is_none())
+                .map(|r| r.smooth)
+                .collect();
+            sorted.sort_by(f64::total_cmp);
+        }
+
+        Shader {
+            style,
+            sorted,
+            spacing,
+            max_iterations,
+        }
+    }
+
+    /// Position of an escaped sample in the palette, in `0..1`.
+    fn position(&self, smooth: f64, cycle: u32) -> f64 {
+        match self.style.coloring {
+            Coloring::Cyclic | Coloring::OrbitTrap | Coloring::Distance => {
+                (smooth % cycle as f64) / cycle as f64
+            }
+            Coloring::Histogram => {
+                // Stop short of the wrap-around so the most frequent and the
+                // rarest values don't end up with the same color.
+                let rank = self.sorted.partition_point(|&v| v < smooth);
+                rank as f64 / self.sorted.len().max(1) as f64 * 0.75
+            }
+        }
+    }
+
+    /// Palette color at position `p`, shifted by the style's offset.
+    fn sample(&self, p: f64) -> (f64, f64, f64) {
+        self.style
+            .palette
+            .sample((p + self.style.offset).rem_euclid(1.))
+    }
+
+    fn rgb(&self, result: EscapeResult, cycle: u32) -> (f64, f64, f64) {
+        if let Some(map) = &self.style.color_map {
+            return map.color(result, self.spacing);
+        }
+        if let (Coloring::OrbitTrap, Some(d)) = (self.style.coloring, result.trap) {
+            return self.sample((1. - (-4. * d).exp()) * 0.75);
+        }
+        if let (Coloring::Distance, Some(d)) = (self.style.coloring, result.distance) {
+            // White on the boundary, fading to dark blue a few samples away.
+            return self.sample(0.5 * (-d / self.spacing).exp());
+        }
+
+        match (result.iterations, result.root) {
+            (None, _) => (0., 0., 0.),
+            (Some(i), Some(root)) => root_color(root, i),
+            (Some(_), None) => self.sample(self.position(result.smooth, cycle)),
+        }
+    }
+
+    fn ansi(&self, result: EscapeResult) -> Color {
+        let (r, g, b) = self.rgb(result, ANSI_CYCLE);
+        let q = |v: f64| (v * 5.).round() as u8;
+
+        Color::cube(q(r), q(g), q(b))
+    }
+
+    /// Truecolor value of a sample, as used by the pixel renderers.
+    pub fn pixel(&self, result: EscapeResult) -> [u8; 3] {
+        let (r, g, b) = self.rgb(result, RGB_CYCLE);
+        let q = |v: f64| (v * 255.).round() as u8;
+
+        [q(r), q(g), q(b)]
+    }
+
+    fn glyph(&self, result: EscapeResult) -> char {
+        match result.root {
+            Some(root) => ['#', '+', '.'][root as usize % 3],
+            None => self
+                .style
+                .ramp
+                .glyph(result.iterations, self.max_iterations),
+        }
+    }
+
+    fn color(&self, mode: ColorMode, result: EscapeResult) -> Option<Color> {
+        match mode {
+            ColorMode::Ascii => None,
+            ColorMode::Ansi256 => Some(self.ansi(result)),
+            ColorMode::TrueColor => {
+                let [r, g, b] = self.pixel(result);
+                Some(Color::Rgb(r, g, b))
+            }
+        }
+    }
+}
+
+/// Truecolor value of a sample with cyclic coloring.
+pub fn pixel_color(result: EscapeResult) -> [u8; 3] {
+    Shader::new(&Style::default(), 1., 0, [].iter()).pixel(result)
+}
+
+const DENSITY_RAMP: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+fn density_rgb(t: f64) -> (f64, f64, f64) {
+    (t * t, t.powf(1.5), t)
+}
+
+/// Truecolor value of a density in `0..=1`, as used by the pixel renderers.
+pub(crate) fn density_color(t: f64) -> [u8; 3] {
+    let (r, g, b) = density_rgb(t);
+    let q = |v: f64| (v * 255.).round() as u8;
+
+    [q(r), q(g), q(b)]
+}
+
+/// Whether a monochrome renderer draws the sample: points that never escape,
+/// or for Newton fractals the basin of the first root.
+fn filled(result: EscapeResult) -> bool {
+    result.iterations.is_none() || result.root == Some(0)
+}
+
+/// Colors cells one after the other.
+pub(crate) struct CellWriter<'a> {
+    mode: ColorMode,
+    shader: &'a Shader<'a>,
+    cells: Vec<Cell>,
+}
+
+impl<'a> CellWriter<'a> {
+    pub fn new(mode: ColorMode, shader: &'a Shader<'a>) -> Self {
+        CellWriter {
+            mode,
+            shader,
+            cells: Vec::new(),
+        }
+    }
+
+    fn put(&mut self, symbol: char, fg: Option<Color>, bg: Option<Color>) {
+        self.cells.push(Cell { symbol, fg, bg });
+    }
+
+    pub fn push(&mut self, result: EscapeResult) {
+        match self.shader.color(self.mode, result) {
+            None => self.put(self.shader.glyph(result), None, None),
+            Some(color) => self.put('█', Some(color), None),
+        }
+    }
+
+    /// Pushes a cell as a space on the background color of `result`.
+    pub fn push_background(&mut self, result: EscapeResult) {
+        match self.shader.color(self.mode, result) {
+            None => self.put(self.shader.glyph(result), None, None),
+            Some(color) => self.put(' ', None, Some(color)),
+        }
+    }
+
+    /// Pushes a cell shaded by a density in `0..=1`.
+    pub fn push_density(&mut self, t: f64) {
+        match self.mode {
+            ColorMode::Ascii => {
+                let i = (t * (DENSITY_RAMP.len() - 1) as f64).round() as usize;
+                self.put(DENSITY_RAMP[i], None, None);
+            }
+            ColorMode::Ansi256 => {
+                let (r, g, b) = density_rgb(t);
+                let q = |v: f64| (v * 5.).round() as u8;
+                self.put('█', Some(Color::cube(q(r), q(g), q(b))), None);
+            }
+            ColorMode::TrueColor => {
+                let [r, g, b] = density_color(t);
+                self.put('█', Some(Color::Rgb(r, g, b)), None);
+            }
+        }
+    }
+
+    /// Pushes a cell made of a `top` and a `bottom` sample.
+    pub fn push_half(&mut self, top: EscapeResult, bottom: EscapeResult) {
+        match (
+            self.shader.color(self.mode, top),
+            self.shader.color(self.mode, bottom),
+        ) {
+            (Some(top), Some(bottom)) => self.put('▀', Some(top), Some(bottom)),
+            _ => self.push(top),
+        }
+    }
+
+    /// Pushes a braille cell from samples indexed by [row][column].
+    pub fn push_braille(&mut self, samples: [[EscapeResult; 2]; 4]) {
+        let mut bits = 0;
+        for (row, dots) in samples.iter().zip(BRAILLE_DOTS) {
+            for (sample, dot) in row.iter().zip(dots) {
+                if filled(*sample) {
+                    bits |= dot;
+                }
+            }
+        }
+
+        self.put(char::from_u32(0x2800 + bits as u32).unwrap(), None, None);
+    }
+
+    pub fn into_cells(self) -> Vec<Cell> {
+        self.cells
+    }
+
+    /// The cells as text, with color escapes where the colors change.
+    pub fn finish(self) -> String {
+        let mut text = String::new();
+        let (mut fg, mut bg) = (None, None);
+        for cell in self.cells {
+            if let Some(color) = cell.fg.filter(|&color| fg != Some(color)) {
+                write!(text, "{}", Foreground(color)).unwrap();
+                fg = Some(color);
+            }
+            if let Some(color) = cell.bg.filter(|&color| bg != Some(color)) {
+                write!(text, "{}", Background(color)).unwrap();
+                bg = Some(color);
+            }
+            text.push(cell.symbol);
+        }
+        if fg.is_some() {
+            text.push_str(FG_RESET);
+        }
+        if bg.is_some() {
+            text.push_str(BG_RESET);
+        }
+        text
+    }
+}
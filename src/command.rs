@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use mandelbrot::C;
+
+use crate::{
+    cli::{self, ColoringKind, FractalKind},
+    link::{self, Link},
+};
+
+/// A command typed at the `:` prompt.
+pub enum Command {
+    /// Move to a point, magnified as much as given or as the view is, and
+    /// iterated as far as given.
+    GoTo((f64, f64), Option<f64>, Option<u32>),
+    /// Open the view of a deep link.
+    Link(Link),
+    /// Magnify the current view, or zoom out below 1.
+    Zoom(f64),
+    /// Set the iteration limit, or `None` to follow the magnification.
+    Iterations(Option<u32>),
+    Palette(String),
+    Coloring(ColoringKind),
+    Fractal(FractalKind),
+    Julia(C),
+    Exponent(f64),
+    /// Export an image of the view to a file, at a size if given.
+    Export(PathBuf, Option<(u32, u32)>),
+    /// Save the session, or Ultra Fractal parameters to a `.upr` file.
+    SaveSession(Option<PathBuf>),
+    /// Open a session, or the parameters of a `.upr` file.
+    OpenSession(Option<PathBuf>),
+    Quit,
+}
+
+/// Names of the commands, for the error of an unknown one.
+const NAMES: &str =
+    "goto, zoom, iter, palette, coloring, fractal, julia, exponent, export, save, open, quit";
+
+/// Parses a command line such as `goto -0.75 0.1` or `export out.png 4k`.
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    let arg = || match rest {
+        "" => Err(format!("`{}` needs an argument", name)),
+        _ => Ok(rest),
+    };
+    let number = |what: &str| {
+        arg()?
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| format!("{} must be a number, got `{}`", what, rest))
+    };
+
+    match name {
+        "goto" | "g" if link::is_link(rest) => Ok(Command::Link(link::parse(rest)?)),
+        "goto" | "g" => {
+            let (center, zoom, iterations) = cli::parse_location(arg()?)?;
+            Ok(Command::GoTo(center, zoom, iterations))
+        }
+        "zoom" | "z" => match number("zoom")? {
+            zoom if zoom > 0. => Ok(Command::Zoom(zoom)),
+            _ => Err("zoom must be positive".to_string()),
+        },
+        "iter" | "iterations" => match arg()? {
+            "auto" => Ok(Command::Iterations(None)),
+            n => match n.parse::<u32>() {
+                Ok(n) if n > 0 => Ok(Command::Iterations(Some(n))),
+                _ => Err(format!(
+                    "expected an iteration limit or `auto`, got `{}`",
+                    n
+                )),
+            },
+        },
+        "palette" => Ok(Command::Palette(arg()?.to_string())),
+        "coloring" => Ok(Command::Coloring(ColoringKind::from_str(arg()?, true)?)),
+        "fractal" => Ok(Command::Fractal(FractalKind::from_str(arg()?, true)?)),
+        "julia" => Ok(Command::Julia(parse_complex(arg()?)?)),
+        "exponent" => Ok(Command::Exponent(cli::check_exponent(number("exponent")?)?)),
+        "export" | "w" => {
+            let mut words = arg()?.split_whitespace();
+            let path = PathBuf::from(words.next().unwrap_or_default());
+            let size = words.next().map(parse_size).transpose()?;
+            Ok(Command::Export(path, size))
+        }
+        "save" => Ok(Command::SaveSession(path(rest))),
+        "open" | "e" => Ok(Command::OpenSession(path(rest))),
+        "quit" | "q" => Ok(Command::Quit),
+        "" => Err("no command given".to_string()),
+        _ => Err(format!(
+            "unknown command `{}`; commands are {}",
+            name, NAMES
+        )),
+    }
+}
+
+fn path(s: &str) -> Option<PathBuf> {
+    (!s.is_empty()).then(|| PathBuf::from(s))
+}
+
+/// Parses an image size, as WIDTHxHEIGHT or a name such as `4k`.
+fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    match s.to_ascii_lowercase().as_str() {
+        "hd" | "720p" => Ok((1280, 720)),
+        "fhd" | "1080p" => Ok((1920, 1080)),
+        "qhd" | "1440p" => Ok((2560, 1440)),
+        "4k" | "2160p" => Ok((3840, 2160)),
+        "8k" => Ok((7680, 4320)),
+        _ => cli::parse_size(s),
+    }
+}
+
+/// Parses a complex number as `RE+IMi`, `RE-IMi` or `RE,IM`.
+fn parse_complex(s: &str) -> Result<C, String> {
+    let invalid = || format!("expected RE+IMi or RE,IM, got `{}`", s);
+    let s = s.replace(' ', "");
+    if let Some((re, im)) = s.split_once(',') {
+        let parse = |v: &str| v.parse::<f64>().map_err(|_| invalid());
+        return Ok(C::from((parse(re)?, parse(im)?)));
+    }
+
+    let Some(body) = s.strip_suffix('i') else {
+        let re = s.parse::<f64>().map_err(|_| invalid())?;
+        return Ok(C::from((re, 0.)));
+    };
+    // The sign between the parts, skipping a leading one or an exponent's.
+    let split = body
+        .char_indices()
+        .skip(1)
+        .filter(|&(i, c)| (c == '+' || c == '-') && !body[..i].ends_with(['e', 'E']))
+        .map(|(i, _)| i)
+        .last();
+    let (re, im) = match split {
+        Some(i) => (&body[..i], &body[i..]),
+        None => ("0", body),
+    };
+    let im = match im {
+        "" | "+" => "1",
+        "-" => "-1",
+        im => im,
+    };
+    let parse = |v: &str| v.parse::<f64>().map_err(|_| invalid());
+    Ok(C::from((parse(re)?, parse(im)?)))
+}
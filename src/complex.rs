@@ -1,9 +1,9 @@
 use std::{
     fmt::Display,
-    ops::{Add, Mul},
+    ops::{Add, Div, Mul, Sub},
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct C {
     pub im: f64,
     pub re: f64,
@@ -19,6 +19,91 @@ impl C {
     pub fn norm(self) -> f64 {
         self.im * self.im + self.re * self.re
     }
+
+    pub fn conj(self) -> C {
+        C {
+            re: self.re,
+            im: -self.im,
+        }
+    }
+
+    /// Raises `self` to a non-negative integer power by repeated squaring.
+    pub fn powi(self, mut n: u32) -> C {
+        let mut base = self;
+        let mut result = C { re: 1., im: 0. };
+
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            n >>= 1;
+        }
+
+        result
+    }
+
+    /// Raises `self` to a real power using the polar form. Negative powers
+    /// of zero are infinite.
+    pub fn powf(self, d: f64) -> C {
+        if self.norm() == 0. {
+            return match d < 0. {
+                true => C {
+                    re: f64::INFINITY,
+                    im: 0.,
+                },
+                false => self,
+            };
+        }
+
+        let r = self.norm().sqrt().powf(d);
+        let theta = self.im.atan2(self.re) * d;
+
+        C {
+            re: r * theta.cos(),
+            im: r * theta.sin(),
+        }
+    }
+
+    pub fn exp(self) -> C {
+        let r = self.re.exp();
+
+        C {
+            re: r * self.im.cos(),
+            im: r * self.im.sin(),
+        }
+    }
+
+    /// Principal branch of the natural logarithm.
+    pub fn ln(self) -> C {
+        C {
+            re: self.norm().sqrt().ln(),
+            im: self.im.atan2(self.re),
+        }
+    }
+
+    pub fn sin(self) -> C {
+        C {
+            re: self.re.sin() * self.im.cosh(),
+            im: self.re.cos() * self.im.sinh(),
+        }
+    }
+
+    pub fn cos(self) -> C {
+        C {
+            re: self.re.cos() * self.im.cosh(),
+            im: -self.re.sin() * self.im.sinh(),
+        }
+    }
+
+    /// Raises `self` to the power `d`, using the exact integer path when possible.
+    pub fn pow(self, d: f64) -> C {
+        if d >= 0. && d.fract() == 0. && d <= u32::MAX as f64 {
+            self.powi(d as u32)
+        } else {
+            self.powf(d)
+        }
+    }
 }
 
 impl From<(f64, f64)> for C {
@@ -55,3 +140,30 @@ impl Add for C {
         }
     }
 }
+
+impl Sub for C {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        C {
+            re: self.re - rhs.re,
+            im: self.im - rhs.im,
+        }
+    }
+}
+
+// (a + ib) / (u + iw) = (a + ib) * (u - iw) / (u^2 + w^2)
+
+impl Div for C {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        let n = rhs.norm();
+        let p = self * rhs.conj();
+
+        C {
+            re: p.re / n,
+            im: p.im / n,
+        }
+    }
+}
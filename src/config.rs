@@ -0,0 +1,111 @@
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+use mandelbrot::{palette::Palette, CellMode, ColorMode, Ramp};
+use serde::Deserialize;
+
+use crate::{
+    animation::Easing,
+    bookmarks, cli,
+    error::Error,
+    keymap::{Action, Keymap, Keys},
+    Args,
+};
+
+/// Defaults of the settings, from the config file with the flags given on
+/// the command line put over them.
+pub struct Config {
+    /// Color mode to start in, or `None` to go by what the terminal supports.
+    pub color_mode: Option<ColorMode>,
+    pub cell_mode: CellMode,
+    /// Characters shading monochrome text.
+    pub ramp: Ramp,
+    /// Iteration limit, or `None` to follow the magnification.
+    pub iterations: Option<u32>,
+    /// Factor by which the zoom keys magnify the view.
+    pub zoom_step: f64,
+    /// Pace of transitions.
+    pub easing: Easing,
+    /// Palettes offered before the built-in ones.
+    pub palettes: Vec<Palette>,
+    pub keymap: Keymap,
+    /// Directory screenshots are saved into.
+    pub screenshot_dir: PathBuf,
+}
+
+/// What `config.toml` may set, all of it optional.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct File {
+    color_mode: Option<ColorMode>,
+    cell_mode: Option<CellMode>,
+    ramp: Option<String>,
+    iterations: Option<u32>,
+    zoom_step: Option<f64>,
+    easing: Option<Easing>,
+    /// Built-in palette names or palette files, relative to the config
+    /// directory.
+    palette: Vec<String>,
+    /// Keys of actions bound otherwise than by default.
+    keys: HashMap<Action, Keys>,
+    screenshot_dir: Option<PathBuf>,
+}
+
+/// Path of the config file, in the config directory.
+pub fn path() -> Option<PathBuf> {
+    bookmarks::config_dir().map(|dir| dir.join("config.toml"))
+}
+
+impl Config {
+    /// Reads the config file, if there is one, and puts the flags of `args`
+    /// over it.
+    pub fn load(args: &Args) -> Result<Config, Error> {
+        let Some(path) = path() else {
+            return Config::new(File::default(), args).map_err(|e| Error::Config(None, e));
+        };
+        let file = match fs::read_to_string(&path) {
+            Ok(source) => toml::from_str(&source).map_err(|e| e.to_string()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(File::default()),
+            Err(e) => Err(e.to_string()),
+        };
+        file.and_then(|file| Config::new(file, args))
+            .map_err(|e| Error::Config(Some(path), e))
+    }
+
+    fn new(file: File, args: &Args) -> Result<Config, String> {
+        let ramp = match (&args.ramp, file.ramp) {
+            (Some(ramp), _) => ramp.clone(),
+            (None, Some(ramp)) => cli::parse_ramp(&ramp)?,
+            (None, None) => Ramp::default(),
+        };
+        let zoom_step = match (args.zoom_step, file.zoom_step) {
+            (Some(step), _) => step,
+            (None, Some(step)) => cli::parse_zoom_step(&step.to_string())?,
+            (None, None) => cli::DEFAULT_ZOOM_STEP,
+        };
+        let palettes = match args.palette.is_empty() {
+            false => args.palette.clone(),
+            true => file
+                .palette
+                .iter()
+                .map(|palette| match bookmarks::config_dir() {
+                    Some(dir) if Palette::builtin(palette).is_none() => {
+                        cli::parse_palette(&dir.join(palette).to_string_lossy())
+                    }
+                    _ => cli::parse_palette(palette),
+                })
+                .collect::<Result<_, _>>()?,
+        };
+
+        Ok(Config {
+            color_mode: file.color_mode,
+            cell_mode: file.cell_mode.unwrap_or(CellMode::Full),
+            ramp,
+            iterations: args.iterations.or(file.iterations),
+            zoom_step,
+            easing: args.easing.or(file.easing).unwrap_or_default(),
+            palettes,
+            keymap: Keymap::new(&file.keys)?,
+            screenshot_dir: file.screenshot_dir.unwrap_or_else(|| PathBuf::from(".")),
+        })
+    }
+}
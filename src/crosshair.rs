@@ -0,0 +1,48 @@
+use mandelbrot::Viewport;
+use ratatui::buffer::Buffer;
+
+use crate::ui::put;
+
+/// Lines through the cell under the pointer, which follow it without
+/// another render, and the coordinates of the point there.
+pub struct Crosshair {
+    /// Cell under the pointer, once it is known.
+    pub pointer: Option<(u16, u16)>,
+}
+
+impl Crosshair {
+    /// Crosshair through the cell `pointer`, if it is known.
+    pub fn new(pointer: Option<(u16, u16)>) -> Self {
+        Crosshair { pointer }
+    }
+
+    /// Cell under the pointer, while it is over the view.
+    pub fn at(&self, viewport: &Viewport) -> Option<(u16, u16)> {
+        self.pointer.filter(|&at| on_screen(viewport, at))
+    }
+
+    /// Coordinates of the point under the pointer, for the status bar.
+    pub fn readout(&self, viewport: &Viewport) -> Option<String> {
+        let (term_x, term_y) = self.at(viewport)?;
+        let (re, im) = viewport.to_decimal(term_x, term_y);
+        Some(format!("pointer {}, {}", re, im))
+    }
+
+    /// Draws the lines through the cell under the pointer into `buffer`.
+    pub fn render(&self, viewport: &Viewport, buffer: &mut Buffer) {
+        let Some((term_x, term_y)) = self.at(viewport) else {
+            return;
+        };
+        for x in 1..=viewport.width {
+            put(buffer, (x, term_y), '─');
+        }
+        for y in 1..=viewport.height {
+            put(buffer, (term_x, y), '│');
+        }
+        put(buffer, (term_x, term_y), '┼');
+    }
+}
+
+pub fn on_screen(viewport: &Viewport, (term_x, term_y): (u16, u16)) -> bool {
+    (1..=viewport.width).contains(&term_x) && (1..=viewport.height).contains(&term_y)
+}
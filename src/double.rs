@@ -0,0 +1,220 @@
+//! Direct iteration in double-double arithmetic, where each number is an
+//! unevaluated sum of two f64 carrying about 106 bits, for deep views of the
+//! fractals perturbation doesn't cover. Several times slower than f64, but
+//! far quicker than arbitrary precision while the view is shallow enough.
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+use dashu_float::FBig;
+
+use crate::{fractal::track, precise, EscapeResult, Fractal, Params, Viewport, C};
+
+/// Cells smaller than this fraction of their distance from zero can't be
+/// resolved in double-double.
+const DEEP: f64 = 1e-28;
+
+/// Whether the cells of `viewport` are large enough to iterate in
+/// double-double.
+pub(crate) fn resolves(viewport: &Viewport) -> bool {
+    let (x, y) = viewport.center();
+    viewport.scale.abs() >= x.hypot(y).max(1.) * DEEP
+}
+
+/// `hi + lo`, with `|lo|` at most half an ulp of `hi`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Dd {
+    hi: f64,
+    lo: f64,
+}
+
+/// `a + b` and its rounding error.
+fn two_sum(a: f64, b: f64) -> Dd {
+    let hi = a + b;
+    let b_part = hi - a;
+    Dd {
+        hi,
+        lo: (a - (hi - b_part)) + (b - b_part),
+    }
+}
+
+/// Like [`two_sum`], for `|a| >= |b|`.
+fn quick_two_sum(a: f64, b: f64) -> Dd {
+    let hi = a + b;
+    Dd {
+        hi,
+        lo: b - (hi - a),
+    }
+}
+
+/// `a` split into halves of 26 bits, which multiply without rounding.
+fn split(a: f64) -> (f64, f64) {
+    let t = 134217729. * a;
+    let hi = t - (t - a);
+    (hi, a - hi)
+}
+
+/// `a * b` and its rounding error (Dekker's product).
+fn two_prod(a: f64, b: f64) -> Dd {
+    let hi = a * b;
+    let ((ah, al), (bh, bl)) = (split(a), split(b));
+    Dd {
+        hi,
+        lo: ((ah * bh - hi) + ah * bl + al * bh) + al * bl,
+    }
+}
+
+impl Dd {
+    fn from_f64(x: f64) -> Self {
+        Dd { hi: x, lo: 0. }
+    }
+
+    fn from_big(x: &FBig) -> Self {
+        let hi = x.to_f64().value();
+        let rest = x - FBig::try_from(hi).unwrap_or_default();
+        Dd {
+            hi,
+            lo: rest.to_f64().value(),
+        }
+    }
+
+    fn abs(self) -> Self {
+        if self.hi < 0. {
+            -self
+        } else {
+            self
+        }
+    }
+}
+
+impl Add for Dd {
+    type Output = Dd;
+
+    fn add(self, other: Dd) -> Dd {
+        let s = two_sum(self.hi, other.hi);
+        let t = two_sum(self.lo, other.lo);
+        let s = quick_two_sum(s.hi, s.lo + t.hi);
+        quick_two_sum(s.hi, s.lo + t.lo)
+    }
+}
+
+impl Sub for Dd {
+    type Output = Dd;
+
+    fn sub(self, other: Dd) -> Dd {
+        self + -other
+    }
+}
+
+impl Neg for Dd {
+    type Output = Dd;
+
+    fn neg(self) -> Dd {
+        Dd {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Mul for Dd {
+    type Output = Dd;
+
+    fn mul(self, other: Dd) -> Dd {
+        let p = two_prod(self.hi, other.hi);
+        quick_two_sum(p.hi, p.lo + (self.hi * other.lo + self.lo * other.hi))
+    }
+}
+
+/// `re + i im` in double-double.
+type DdC = (Dd, Dd);
+
+fn mul((a, b): DdC, (c, d): DdC) -> DdC {
+    (a * c - b * d, a * d + b * c)
+}
+
+fn approx((re, im): DdC) -> C {
+    C {
+        re: re.hi,
+        im: im.hi,
+    }
+}
+
+/// `z^d` for a positive integer `d`, by repeated squaring.
+fn powi(mut base: DdC, mut d: u32) -> DdC {
+    let mut result = (Dd::from_f64(1.), Dd::default());
+    while d > 0 {
+        if d & 1 == 1 {
+            result = mul(result, base);
+        }
+        d >>= 1;
+        if d > 0 {
+            base = mul(base, base);
+        }
+    }
+    result
+}
+
+/// Iterates the points of a deep view one by one in double-double.
+pub(crate) struct DoubleDouble {
+    origin: DdC,
+    /// Integer power of the iteration.
+    exponent: u32,
+}
+
+impl DoubleDouble {
+    /// Iterates the points of `viewport`, or returns `None` if the fractal
+    /// isn't one arbitrary precision [supports](precise::supports) either.
+    pub(crate) fn new(viewport: &Viewport, params: &Params) -> Option<Self> {
+        if !precise::supports(params) {
+            return None;
+        }
+
+        Some(DoubleDouble {
+            origin: (
+                Dd::from_big(&viewport.origin.re),
+                Dd::from_big(&viewport.origin.im),
+            ),
+            exponent: params.exponent as u32,
+        })
+    }
+
+    /// Iterates the point at `(x, y)` from the origin of the view.
+    pub(crate) fn iterate(&self, (x, y): (f64, f64), params: &Params) -> EscapeResult {
+        let point = (
+            self.origin.0 + Dd::from_f64(x),
+            self.origin.1 + Dd::from_f64(y),
+        );
+        track(approx(point), params, |visit| {
+            self.escape(point, params, visit)
+        })
+    }
+
+    fn escape(&self, point: DdC, params: &Params, visit: &mut dyn FnMut(C)) -> Option<(u32, C)> {
+        let (mut z, k) = match params.fractal {
+            Fractal::Julia(k) => (point, (Dd::from_f64(k.re), Dd::from_f64(k.im))),
+            _ => ((Dd::default(), Dd::default()), point),
+        };
+        let mut i = 0;
+
+        loop {
+            let zf = approx(z);
+            if zf.norm() > params.cutoff {
+                return Some((i, zf));
+            }
+
+            if i > params.max_iterations {
+                return None;
+            }
+
+            let base = match params.fractal {
+                Fractal::BurningShip => (z.0.abs(), z.1.abs()),
+                Fractal::Tricorn => (z.0, -z.1),
+                _ => z,
+            };
+            let p = powi(base, self.exponent);
+            z = (p.0 + k.0, p.1 + k.1);
+            visit(approx(z));
+            i += 1;
+        }
+    }
+}
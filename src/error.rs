@@ -0,0 +1,52 @@
+use std::{error, fmt, io, path::PathBuf};
+
+/// What stops the program, described for the user rather than with a
+/// backtrace.
+#[derive(Debug)]
+pub enum Error {
+    /// Standard input or output isn't a terminal, so there is nothing to
+    /// draw on or take input from.
+    NotATerminal,
+    /// Setting up, reading from or drawing on the terminal failed.
+    Terminal(io::Error),
+    /// An image couldn't be written to the path.
+    Export(PathBuf, image::ImageError),
+    /// The config file, at the path if there is one, couldn't be read or
+    /// has invalid settings.
+    Config(Option<PathBuf>, String),
+    /// The session file couldn't be read or restored.
+    Session(PathBuf, String),
+    /// The bookmark or session file named couldn't be found or read.
+    View(String, String),
+    /// The job file couldn't be read, or some of its jobs failed.
+    Jobs(PathBuf, String),
+    /// The tour file couldn't be read or has invalid keyframes.
+    Tour(PathBuf, String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotATerminal => write!(
+                f,
+                "stdin or stdout is not a terminal; use `mandelbrot render` to export an image instead"
+            ),
+            Error::Terminal(e) => write!(f, "terminal error: {}", e),
+            Error::Export(path, e) => write!(f, "could not export {}: {}", path.display(), e),
+            Error::Config(Some(path), e) => write!(f, "invalid config {}: {}", path.display(), e),
+            Error::Config(None, e) => write!(f, "invalid settings: {}", e),
+            Error::Session(path, e) => write!(f, "could not open {}: {}", path.display(), e),
+            Error::View(name, e) => write!(f, "no view `{}`: {}", name, e),
+            Error::Jobs(path, e) => write!(f, "jobs of {}: {}", path.display(), e),
+            Error::Tour(path, e) => write!(f, "invalid tour {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Terminal(e)
+    }
+}
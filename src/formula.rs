@@ -0,0 +1,346 @@
+//! Parser and evaluator for user-defined iteration formulas such as
+//! `z^2 + c` or `z^3 + c*z`.
+//!
+//! Formulas are built from the variables `z` and `c`, real numbers, the
+//! imaginary unit `i`, the operators `+ - * / ^`, parentheses and the
+//! functions `conj`, `abs`, `exp`, `ln`, `sin` and `cos`.
+
+use std::{error::Error, fmt};
+
+use crate::complex::C;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// Byte offset into the formula at which parsing failed.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Conj,
+    Abs,
+    Exp,
+    Ln,
+    Sin,
+    Cos,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        match name {
+            "conj" => Some(Function::Conj),
+            "abs" => Some(Function::Abs),
+            "exp" => Some(Function::Exp),
+            "ln" => Some(Function::Ln),
+            "sin" => Some(Function::Sin),
+            "cos" => Some(Function::Cos),
+            _ => None,
+        }
+    }
+
+    fn apply(self, z: C) -> C {
+        match self {
+            Function::Conj => z.conj(),
+            Function::Abs => C {
+                re: z.norm().sqrt(),
+                im: 0.,
+            },
+            Function::Exp => z.exp(),
+            Function::Ln => z.ln(),
+            Function::Sin => z.sin(),
+            Function::Cos => z.cos(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Z,
+    C,
+    Const(C),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, z: C, c: C) -> C {
+        match self {
+            Expr::Z => z,
+            Expr::C => c,
+            Expr::Const(k) => *k,
+            Expr::Neg(a) => C { re: 0., im: 0. } - a.eval(z, c),
+            Expr::Add(a, b) => a.eval(z, c) + b.eval(z, c),
+            Expr::Sub(a, b) => a.eval(z, c) - b.eval(z, c),
+            Expr::Mul(a, b) => a.eval(z, c) * b.eval(z, c),
+            Expr::Div(a, b) => a.eval(z, c) / b.eval(z, c),
+            Expr::Pow(a, b) => {
+                let (base, exponent) = (a.eval(z, c), b.eval(z, c));
+                if exponent.im == 0. {
+                    base.pow(exponent.re)
+                } else if base.norm() == 0. {
+                    base
+                } else {
+                    (exponent * base.ln()).exp()
+                }
+            }
+            Expr::Call(f, a) => f.apply(a.eval(z, c)),
+        }
+    }
+}
+
+/// A parsed iteration formula computing the next `z` from `z` and `c`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    source: String,
+    expr: Expr,
+}
+
+impl Formula {
+    pub fn parse(source: &str) -> Result<Formula, ParseError> {
+        let mut parser = Parser {
+            source,
+            pos: 0,
+            nesting: 0,
+        };
+        let (expr, _) = parser.expr()?;
+
+        parser.skip_whitespace();
+        if parser.pos < source.len() {
+            return Err(parser.error("unexpected input"));
+        }
+
+        Ok(Formula {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    pub fn eval(&self, z: C, c: C) -> C {
+        self.expr.eval(z, c)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Deepest formulas parse to, in nested operations. Parsing, evaluating
+/// and dropping formulas recurse through them, so deeper ones would
+/// overflow the stack.
+const MAX_DEPTH: usize = 256;
+
+/// An expression and the depth of its tree.
+type Node = (Expr, usize);
+
+/// Recursive descent parser over the grammar
+///
+/// ```text
+/// expr   = term (("+" | "-") term)*
+/// term   = unary (("*" | "/") unary)*
+/// unary  = "-" unary | power
+/// power  = atom ("^" unary)?
+/// atom   = number | "z" | "c" | "i" | name "(" expr ")" | "(" expr ")"
+/// ```
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+    /// Calls of [`Parser::unary`] being parsed, through which every nested
+    /// expression goes.
+    nesting: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            position: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.source[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn too_deep(&self) -> ParseError {
+        self.error(&format!("formula nested more than {} deep", MAX_DEPTH))
+    }
+
+    /// The operation `op` of one operand.
+    fn unary_node(
+        &self,
+        op: impl FnOnce(Box<Expr>) -> Expr,
+        (a, depth): Node,
+    ) -> Result<Node, ParseError> {
+        match depth + 1 {
+            depth if depth > MAX_DEPTH => Err(self.too_deep()),
+            depth => Ok((op(Box::new(a)), depth)),
+        }
+    }
+
+    /// The operation `op` of two operands.
+    fn binary_node(
+        &self,
+        op: fn(Box<Expr>, Box<Expr>) -> Expr,
+        (a, a_depth): Node,
+        (b, b_depth): Node,
+    ) -> Result<Node, ParseError> {
+        match a_depth.max(b_depth) + 1 {
+            depth if depth > MAX_DEPTH => Err(self.too_deep()),
+            depth => Ok((op(Box::new(a), Box::new(b)), depth)),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Node, ParseError> {
+        let mut lhs = self.term()?;
+
+        loop {
+            if self.eat('+') {
+                let rhs = self.term()?;
+                lhs = self.binary_node(Expr::Add, lhs, rhs)?;
+            } else if self.eat('-') {
+                let rhs = self.term()?;
+                lhs = self.binary_node(Expr::Sub, lhs, rhs)?;
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Node, ParseError> {
+        let mut lhs = self.unary()?;
+
+        loop {
+            if self.eat('*') {
+                let rhs = self.unary()?;
+                lhs = self.binary_node(Expr::Mul, lhs, rhs)?;
+            } else if self.eat('/') {
+                let rhs = self.unary()?;
+                lhs = self.binary_node(Expr::Div, lhs, rhs)?;
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, ParseError> {
+        if self.nesting == MAX_DEPTH {
+            return Err(self.too_deep());
+        }
+        self.nesting += 1;
+        let node = match self.eat('-') {
+            true => self.unary().and_then(|a| self.unary_node(Expr::Neg, a)),
+            false => self.power(),
+        };
+        self.nesting -= 1;
+        node
+    }
+
+    fn power(&mut self) -> Result<Node, ParseError> {
+        let base = self.atom()?;
+
+        if self.eat('^') {
+            let exponent = self.unary()?;
+            self.binary_node(Expr::Pow, base, exponent)
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node, ParseError> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected `)`"));
+                }
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => self.name(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of formula")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Node, ParseError> {
+        let start = self.pos;
+        let rest = &self.source[start..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+
+        match rest[..len].parse::<f64>() {
+            Ok(re) => {
+                self.pos += len;
+                Ok((Expr::Const(C { re, im: 0. }), 1))
+            }
+            Err(_) => Err(self.error("invalid number")),
+        }
+    }
+
+    fn name(&mut self) -> Result<Node, ParseError> {
+        let start = self.pos;
+        let rest = &self.source[start..];
+        let len = rest
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        let name = &rest[..len];
+        self.pos += len;
+
+        match name {
+            "z" => Ok((Expr::Z, 1)),
+            "c" => Ok((Expr::C, 1)),
+            "i" => Ok((Expr::Const(C { re: 0., im: 1. }), 1)),
+            _ => {
+                let function = Function::from_name(name).ok_or(ParseError {
+                    position: start,
+                    message: format!("unknown name `{}`", name),
+                })?;
+                if !self.eat('(') {
+                    return Err(self.error("expected `(`"));
+                }
+                let arg = self.expr()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected `)`"));
+                }
+                self.unary_node(|a| Expr::Call(function, a), arg)
+            }
+        }
+    }
+}
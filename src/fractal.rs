@@ -0,0 +1,566 @@
+use std::{fmt, mem, sync::Arc};
+
+use crate::{complex::C, formula::Formula, Bounds};
+
+/// Parameters of the escape-time iteration.
+#[derive(Debug, Clone)]
+pub struct Params {
+    pub max_iterations: u32,
+    /// Squared magnitude of `z` above which a point counts as escaped.
+    pub cutoff: f64,
+    pub fractal: Fractal,
+    /// Power `d` of the iteration `z = z^d + c`; 2 gives the classic sets.
+    pub exponent: f64,
+    /// Shape to which orbits record their closest approach, for orbit-trap coloring.
+    pub trap: Option<Trap>,
+    /// Whether to track the derivative of orbits to estimate their distance to the set.
+    pub distance_estimate: bool,
+    /// Samples along each axis averaged into every cell or pixel, for
+    /// antialiasing; 1 takes a single one at its center.
+    pub supersampling: u32,
+    /// Whether only the points of grids on edges between very different
+    /// escape times are supersampled.
+    pub adaptive_supersampling: bool,
+    /// Precision points are iterated in, or `None` to pick the cheapest
+    /// that resolves the cells of each view.
+    pub precision: Option<Precision>,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            max_iterations: 900,
+            cutoff: 10.,
+            fractal: Fractal::Mandelbrot,
+            exponent: 2.,
+            trap: None,
+            distance_estimate: false,
+            supersampling: 1,
+            adaptive_supersampling: false,
+            precision: None,
+        }
+    }
+}
+
+impl Params {
+    /// Whether `self` and `other` iterate every point the same way, up to
+    /// their iteration limits.
+    pub fn same_orbits(&self, other: &Params) -> bool {
+        let fractal = match (&self.fractal, &other.fractal) {
+            (Fractal::Julia(a), Fractal::Julia(b)) => a == b,
+            (Fractal::Formula(a), Fractal::Formula(b)) => Arc::ptr_eq(a, b),
+            (Fractal::Plugin(a), Fractal::Plugin(b)) => Arc::ptr_eq(a, b),
+            (a, b) => mem::discriminant(a) == mem::discriminant(b),
+        };
+        fractal
+            && self.cutoff == other.cutoff
+            && self.exponent == other.exponent
+            && self.trap == other.trap
+            && self.distance_estimate == other.distance_estimate
+            && self.supersampling == other.supersampling
+            && self.adaptive_supersampling == other.adaptive_supersampling
+            && self.precision == other.precision
+    }
+}
+
+/// Arithmetic points are iterated in, from the quickest to the one that
+/// resolves the deepest views.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// Twice as many points at once as in f64, which resolves views whose
+    /// points are at least about `1e-5` apart.
+    F32,
+    F64,
+    /// f64 differences from a reference orbit in arbitrary precision, for
+    /// the quadratic Mandelbrot and Julia sets.
+    Perturbed,
+    /// Pairs of f64, with about 106 bits.
+    DoubleDouble,
+    Arbitrary,
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Precision::F32 => "f32",
+            Precision::F64 => "f64",
+            Precision::Perturbed => "perturbed",
+            Precision::DoubleDouble => "double-double",
+            Precision::Arbitrary => "arbitrary",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Iteration limit of zoom-adaptive views at the default magnification.
+const BASE_ITERATIONS: f64 = 256.;
+/// Iterations added by each tenfold magnification of zoom-adaptive views.
+const ITERATIONS_PER_DECADE: f64 = 512.;
+
+/// Iteration limit that resolves the boundary of a view magnified
+/// `magnification` times from the default one. Deeper views need more
+/// iterations, growing with the logarithm of their magnification.
+pub fn zoom_iterations(magnification: f64) -> u32 {
+    let decades = magnification.log10().max(0.);
+    (BASE_ITERATIONS + ITERATIONS_PER_DECADE * decades).min(u32::MAX as f64) as u32
+}
+
+#[derive(Debug, Clone)]
+pub enum Fractal {
+    Mandelbrot,
+    /// The Julia set of the given constant `c`.
+    Julia(C),
+    /// `z = (|Re z| + i|Im z|)^2 + c`
+    BurningShip,
+    /// The Mandelbar set, `z = conj(z)^2 + c`.
+    Tricorn,
+    /// Newton's method for `z^3 - 1`, started at each point of the plane.
+    Newton,
+    /// A user-defined iteration `z = f(z, c)`, started from `z = c`.
+    Formula(Arc<Formula>),
+    /// An iteration the library doesn't define, plugged in by its user.
+    Plugin(Arc<dyn Iteration>),
+}
+
+/// An escape-time iteration `z = step(z, c)`, which the built-in fractals
+/// but Newton's method, formulas and [`Fractal::Plugin`] are all given by.
+/// Their orbits run through the same escape loop, orbit traps and coloring.
+pub trait Iteration: fmt::Debug + Send + Sync {
+    /// First iterate of the orbit of the point `c`, which formulas start
+    /// from.
+    fn start(&self, c: C) -> C {
+        c
+    }
+
+    /// Iterate following `z` on the orbit of the point `c`.
+    fn step(&self, z: C, c: C) -> C;
+
+    /// Bounds framing the interesting part of the fractal.
+    fn default_bounds(&self) -> Bounds {
+        ((-3., 4.), (-2., 4.))
+    }
+
+    /// Iterates the point `c` through the shared escape loop, at most
+    /// `max_iter` times, with the other parameters left at their defaults.
+    fn iterate(&self, c: C, max_iter: u32) -> EscapeResult {
+        let params = Params {
+            max_iterations: max_iter,
+            ..Params::default()
+        };
+        track(c, &params, |visit| escape_with(self, c, &params, visit))
+    }
+}
+
+impl Iteration for Formula {
+    fn step(&self, z: C, c: C) -> C {
+        self.eval(z, c)
+    }
+}
+
+/// The Mandelbrot set of `z = z^d + c`, with the exponent `d`.
+#[derive(Debug)]
+struct Multibrot(f64);
+
+impl Iteration for Multibrot {
+    fn start(&self, c: C) -> C {
+        critical_start(c, self.0)
+    }
+
+    fn step(&self, z: C, c: C) -> C {
+        power(z, self.0) + c
+    }
+}
+
+/// The Julia set of `z = z^d + k`, with the constant `k` and exponent `d`.
+#[derive(Debug)]
+struct Julia(C, f64);
+
+impl Iteration for Julia {
+    fn step(&self, z: C, _: C) -> C {
+        power(z, self.1) + self.0
+    }
+}
+
+/// `z = (|Re z| + i|Im z|)^d + c`, with the exponent `d`.
+#[derive(Debug)]
+struct BurningShip(f64);
+
+impl Iteration for BurningShip {
+    fn start(&self, c: C) -> C {
+        critical_start(c, self.0)
+    }
+
+    fn step(&self, z: C, c: C) -> C {
+        let z = C {
+            re: z.re.abs(),
+            im: z.im.abs(),
+        };
+        power(z, self.0) + c
+    }
+}
+
+/// `z = conj(z)^d + c`, with the exponent `d`.
+#[derive(Debug)]
+struct Tricorn(f64);
+
+impl Iteration for Tricorn {
+    fn start(&self, c: C) -> C {
+        critical_start(c, self.0)
+    }
+
+    fn step(&self, z: C, c: C) -> C {
+        power(z.conj(), self.0) + c
+    }
+}
+
+/// First iterate of the Mandelbrot-like sets of exponent `d`: the critical
+/// point 0, but for negative exponents, which take it straight to infinity,
+/// the point `c` itself.
+fn critical_start(c: C, d: f64) -> C {
+    match d < 0. {
+        true => c,
+        false => C { re: 0., im: 0. },
+    }
+}
+
+/// An orbit trap, measuring how close the iterates of an orbit come to a shape.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trap {
+    Point(C),
+    /// A line through the origin at the given angle in radians.
+    Line(f64),
+    /// A circle of the given radius around the origin.
+    Circle(f64),
+}
+
+impl Trap {
+    /// Distance from `z` to the trap.
+    pub fn distance(&self, z: C) -> f64 {
+        match *self {
+            Trap::Point(p) => (z - p).norm().sqrt(),
+            Trap::Line(angle) => (z.im * angle.cos() - z.re * angle.sin()).abs(),
+            Trap::Circle(radius) => (z.norm().sqrt() - radius).abs(),
+        }
+    }
+}
+
+/// What the iteration of a single point produced.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EscapeResult {
+    /// Iteration at which the orbit escaped, or for Newton fractals converged
+    /// to a root; `None` if it did neither within `max_iterations`.
+    pub iterations: Option<u32>,
+    /// Index of the root a Newton iteration converged to.
+    pub root: Option<u8>,
+    /// Continuous escape value `n + 1 - log(log|z|) / log d`, for coloring
+    /// without bands. Equals `iterations` when there is no escape to smooth.
+    pub smooth: f64,
+    /// Smallest distance of the orbit to `Params::trap`, if one is set.
+    pub trap: Option<f64>,
+    /// Estimated distance of an escaping point to the set, in the units of
+    /// the plane, if `Params::distance_estimate` is set.
+    pub distance: Option<f64>,
+}
+
+impl Fractal {
+    /// Constant of the Julia set shown when none is given.
+    pub const DEFAULT_JULIA: C = C {
+        re: -0.8,
+        im: 0.156,
+    };
+
+    /// Bounds framing the interesting part of the fractal.
+    pub fn default_bounds(&self) -> Bounds {
+        match self {
+            Fractal::Mandelbrot => ((-2.25, 3.), (-1.25, 2.5)),
+            Fractal::Julia(_) => ((-2., 4.), (-2., 4.)),
+            Fractal::BurningShip => ((-2.25, 3.5), (-2.25, 3.5)),
+            Fractal::Tricorn => ((-2.3, 4.), (-2., 4.)),
+            Fractal::Newton => ((-2., 4.), (-2., 4.)),
+            Fractal::Formula(formula) => formula.default_bounds(),
+            Fractal::Plugin(iteration) => iteration.default_bounds(),
+        }
+    }
+}
+
+/// Mean of the samples taken over one cell or pixel: escaped if most of them
+/// escaped, with the means of what the samples on that side recorded.
+pub(crate) fn average(samples: &[EscapeResult]) -> EscapeResult {
+    let escaped = samples.iter().filter(|r| r.iterations.is_some()).count();
+    let majority: Vec<&EscapeResult> = samples
+        .iter()
+        .filter(|r| r.iterations.is_some() == (escaped * 2 > samples.len()))
+        .collect();
+    let Some(first) = majority.first() else {
+        return EscapeResult::default();
+    };
+
+    let mean = |values: Vec<f64>| {
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+    };
+    let iterations: Vec<f64> = majority
+        .iter()
+        .filter_map(|r| r.iterations)
+        .map(f64::from)
+        .collect();
+    EscapeResult {
+        iterations: mean(iterations).map(|i| i.round() as u32),
+        root: first.root,
+        smooth: mean(majority.iter().map(|r| r.smooth).collect()).unwrap_or_default(),
+        trap: mean(majority.iter().filter_map(|r| r.trap).collect()),
+        distance: mean(majority.iter().filter_map(|r| r.distance).collect()),
+    }
+}
+
+/// Returns the iteration at which the point `c` of the plane escapes, or
+/// `None` if it stays bounded for `max_iterations`.
+pub fn check_convergence(c: C, params: &Params) -> Option<u32> {
+    iterate(c, params).iterations
+}
+
+/// Iterates the point `c` of the plane under the selected fractal.
+pub fn iterate(c: C, params: &Params) -> EscapeResult {
+    if let Fractal::Newton = params.fractal {
+        return newton(c, params);
+    }
+    // Orbit traps still need the orbits of points inside the set.
+    if params.trap.is_none() && in_main_bulbs(c, params) {
+        return EscapeResult::default();
+    }
+
+    track(c, params, |visit| escape(c, params, visit))
+}
+
+/// Runs the escape-time iteration `orbit` of the point `c`, following the
+/// iterates it passes on to record what `params` asks for besides the
+/// escape time.
+pub(crate) fn track(
+    c: C,
+    params: &Params,
+    orbit: impl FnOnce(&mut dyn FnMut(C)) -> Option<(u32, C)>,
+) -> EscapeResult {
+    let mut closest = f64::INFINITY;
+    let mut derivative = match params.distance_estimate {
+        true => Derivative::start(c, params),
+        false => None,
+    };
+    let escaped = orbit(&mut |z| {
+        if let Some(trap) = &params.trap {
+            closest = closest.min(trap.distance(z));
+        }
+        if let Some(derivative) = &mut derivative {
+            derivative.step(z, params.exponent);
+        }
+    });
+    let trap = params.trap.map(|_| closest);
+
+    match escaped {
+        Some((i, z)) => EscapeResult {
+            iterations: Some(i),
+            root: None,
+            smooth: smooth_iterations(i, z, params),
+            trap,
+            distance: derivative.map(|derivative| derivative.distance(z)),
+        },
+        None => EscapeResult {
+            trap,
+            ..EscapeResult::default()
+        },
+    }
+}
+
+/// Whether `c` lies in the main cardioid or the period-2 bulb of the
+/// classic Mandelbrot set, where orbits never escape.
+pub(crate) fn in_main_bulbs(c: C, params: &Params) -> bool {
+    if !matches!(params.fractal, Fractal::Mandelbrot) || params.exponent != 2. {
+        return false;
+    }
+
+    let x = c.re - 0.25;
+    let y2 = c.im * c.im;
+    let q = x * x + y2;
+    q * (q + x) <= y2 / 4. || (c.re + 1.) * (c.re + 1.) + y2 <= 1. / 16.
+}
+
+/// Runs the escape-time iteration of the point `c`, passing every iterate
+/// of its orbit to `visit`. Newton fractals have no escaping orbits and
+/// always return `None`.
+pub fn orbit(c: C, params: &Params, visit: impl FnMut(C)) -> Option<u32> {
+    escape(c, params, visit).map(|(i, _)| i)
+}
+
+fn escape(c: C, params: &Params, visit: impl FnMut(C)) -> Option<(u32, C)> {
+    let d = params.exponent;
+
+    match &params.fractal {
+        Fractal::Mandelbrot => escape_with(&Multibrot(d), c, params, visit),
+        Fractal::Julia(k) => escape_with(&Julia(*k, d), c, params, visit),
+        Fractal::BurningShip => escape_with(&BurningShip(d), c, params, visit),
+        Fractal::Tricorn => escape_with(&Tricorn(d), c, params, visit),
+        Fractal::Newton => None,
+        Fractal::Formula(formula) => escape_with(formula.as_ref(), c, params, visit),
+        Fractal::Plugin(iteration) => escape_with(iteration.as_ref(), c, params, visit),
+    }
+}
+
+/// Runs the escape-time loop over the orbit `iteration` gives the point `c`.
+/// Generic so the steps of the built-in fractals are inlined into it.
+fn escape_with<I: Iteration + ?Sized>(
+    iteration: &I,
+    c: C,
+    params: &Params,
+    visit: impl FnMut(C),
+) -> Option<(u32, C)> {
+    escape_time(iteration.start(c), params, |z| iteration.step(z, c), visit)
+}
+
+/// Derivative `dz/dc` of an orbit, advanced alongside it from the iterates
+/// passed to `visit`.
+struct Derivative {
+    /// Iterate preceding the one passed to the next `step`.
+    prev: C,
+    dz: C,
+    /// `dc/dc` for the Mandelbrot-like sets, where `c` is added every step.
+    offset: C,
+}
+
+impl Derivative {
+    fn start(c: C, params: &Params) -> Option<Self> {
+        let zero = C { re: 0., im: 0. };
+        let one = C { re: 1., im: 0. };
+
+        match params.fractal {
+            // The Burning Ship and Tricorn steps aren't holomorphic, but their
+            // derivatives have the magnitude of the Mandelbrot one.
+            Fractal::Mandelbrot | Fractal::BurningShip | Fractal::Tricorn => Some(Derivative {
+                prev: critical_start(c, params.exponent),
+                dz: match params.exponent < 0. {
+                    true => one,
+                    false => zero,
+                },
+                offset: one,
+            }),
+            Fractal::Julia(_) => Some(Derivative {
+                prev: c,
+                dz: one,
+                offset: zero,
+            }),
+            Fractal::Newton | Fractal::Formula(_) | Fractal::Plugin(_) => None,
+        }
+    }
+
+    fn step(&mut self, z: C, d: f64) {
+        let d_c = C { re: d, im: 0. };
+        self.dz = d_c * power(self.prev, d - 1.) * self.dz + self.offset;
+        self.prev = z;
+    }
+
+    /// Distance estimate `|z| log|z| / |dz|` of the escaped iterate `z`.
+    fn distance(&self, z: C) -> f64 {
+        let r = z.norm().sqrt();
+        r * r.ln() / self.dz.norm().sqrt()
+    }
+}
+
+/// Squared distance below which an iterate counts as a return to an earlier one.
+pub(crate) const PERIOD_TOLERANCE: f64 = 1e-24;
+
+/// Iterates `z = step(z)` from `z0` until `z` escapes, returning the
+/// iteration count and the first escaped `z`.
+///
+/// Orbits that fall into a cycle never escape, so they are given up on as
+/// soon as one is found, comparing each iterate to one saved at growing
+/// powers of two (Brent's method).
+fn escape_time(
+    z0: C,
+    params: &Params,
+    step: impl Fn(C) -> C,
+    mut visit: impl FnMut(C),
+) -> Option<(u32, C)> {
+    let mut i = 0;
+    let mut z = z0;
+    let (mut saved, mut period, mut limit) = (z0, 0, 1);
+
+    loop {
+        if z.norm() > params.cutoff {
+            return Some((i, z));
+        }
+
+        if i > params.max_iterations {
+            return None;
+        }
+
+        z = step(z);
+        visit(z);
+        i += 1;
+
+        if (z - saved).norm() < PERIOD_TOLERANCE {
+            return None;
+        }
+        period += 1;
+        if period == limit {
+            (saved, period, limit) = (z, 0, limit * 2);
+        }
+    }
+}
+
+pub(crate) fn smooth_iterations(i: u32, z: C, params: &Params) -> f64 {
+    let d = match params.fractal {
+        Fractal::Formula(_) | Fractal::Plugin(_) => 2.,
+        _ if params.exponent > 1. => params.exponent,
+        _ => 2.,
+    };
+    let log_z = z.norm().ln() / 2.;
+
+    if log_z > 0. {
+        (i as f64 + 1. - log_z.ln() / d.ln()).max(0.)
+    } else {
+        i as f64
+    }
+}
+
+fn power(z: C, d: f64) -> C {
+    if d == 2. {
+        z * z
+    } else {
+        z.pow(d)
+    }
+}
+
+const NEWTON_ROOTS: [C; 3] = [
+    C { re: 1., im: 0. },
+    C {
+        re: -0.5,
+        im: 0.8660254037844386,
+    },
+    C {
+        re: -0.5,
+        im: -0.8660254037844386,
+    },
+];
+const NEWTON_TOLERANCE: f64 = 1e-12;
+
+fn newton(mut z: C, params: &Params) -> EscapeResult {
+    let one = C { re: 1., im: 0. };
+    let three = C { re: 3., im: 0. };
+
+    for i in 0..=params.max_iterations {
+        if let Some(root) = NEWTON_ROOTS
+            .iter()
+            .position(|&root| (z - root).norm() < NEWTON_TOLERANCE)
+        {
+            return EscapeResult {
+                iterations: Some(i),
+                root: Some(root as u8),
+                smooth: i as f64,
+                trap: None,
+                distance: None,
+            };
+        }
+
+        let z2 = z * z;
+        z = z - (z2 * z - one) / (three * z2);
+    }
+
+    EscapeResult::default()
+}
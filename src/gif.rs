@@ -0,0 +1,195 @@
+//! Animated GIF encoding of rendered frames, each quantized to a palette of
+//! its own.
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+/// Colors of a GIF palette.
+const COLORS: usize = 256;
+/// Bits of the codes of the image data before any are added.
+const MIN_CODE_SIZE: u8 = 8;
+const MAX_CODE_SIZE: u8 = 12;
+
+/// Writes the frames of an animation that loops forever.
+pub struct Encoder<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Writes the header of a `width * height` animation to `out`.
+    pub fn new(mut out: W, width: u16, height: u16) -> io::Result<Self> {
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // No global palette, 8 bits per primary color.
+        out.write_all(&[0x70, 0, 0])?;
+        out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+        Ok(Encoder { out, width, height })
+    }
+
+    /// Appends a row-major frame, shown for `delay` hundredths of a second.
+    pub fn add_frame(&mut self, pixels: &[[u8; 3]], delay: u16) -> io::Result<()> {
+        assert_eq!(pixels.len(), self.width as usize * self.height as usize);
+        let (palette, indices) = quantize(pixels);
+
+        // Graphic control: leave the frame in place, no transparency.
+        self.out.write_all(&[0x21, 0xf9, 4, 0x04])?;
+        self.out.write_all(&delay.to_le_bytes())?;
+        self.out.write_all(&[0, 0])?;
+
+        self.out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        // A local palette of 256 colors.
+        self.out.write_all(&[0x87])?;
+        for i in 0..COLORS {
+            self.out
+                .write_all(&palette.get(i).copied().unwrap_or_default())?;
+        }
+
+        self.out.write_all(&[MIN_CODE_SIZE])?;
+        for block in lzw(&indices).chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0])
+    }
+
+    /// Ends the animation, returning what it was written to.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&[0x3b])?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Colors with 5 bits per primary, which the palette is picked among.
+fn bucket([r, g, b]: [u8; 3]) -> usize {
+    (r as usize >> 3) << 10 | (g as usize >> 3) << 5 | b as usize >> 3
+}
+
+/// Picks the most common colors of `pixels`, returning them and the index
+/// of the closest one to each pixel.
+fn quantize(pixels: &[[u8; 3]]) -> (Vec<[u8; 3]>, Vec<u8>) {
+    // Count and sum of the colors falling into each bucket.
+    let mut buckets = vec![(0u32, [0u32; 3]); 1 << 15];
+    for &pixel in pixels {
+        let (count, sum) = &mut buckets[bucket(pixel)];
+        *count += 1;
+        for (sum, c) in sum.iter_mut().zip(pixel) {
+            *sum += c as u32;
+        }
+    }
+
+    let mut used: Vec<usize> = (0..buckets.len()).filter(|&i| buckets[i].0 > 0).collect();
+    used.sort_by_key(|&i| std::cmp::Reverse(buckets[i].0));
+    let palette: Vec<[u8; 3]> = used
+        .iter()
+        .take(COLORS)
+        .map(|&i| {
+            let (count, sum) = buckets[i];
+            sum.map(|sum| (sum / count) as u8)
+        })
+        .collect();
+
+    let mut closest = HashMap::new();
+    let indices = pixels
+        .iter()
+        .map(|&pixel| {
+            let key = bucket(pixel);
+            *closest.entry(key).or_insert_with(|| {
+                let (_, sum) = buckets[key];
+                let average = sum.map(|sum| sum / buckets[key].0);
+                let distance = |color: &[u8; 3]| -> u32 {
+                    color
+                        .iter()
+                        .zip(average)
+                        .map(|(&c, a)| (c as i32 - a as i32).pow(2) as u32)
+                        .sum()
+                };
+                (0..palette.len())
+                    .min_by_key(|&i| distance(&palette[i]))
+                    .unwrap_or(0) as u8
+            })
+        })
+        .collect();
+    (palette, indices)
+}
+
+/// Packs codes of growing width into bytes, least significant bit first.
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    buffer: u32,
+    length: u8,
+}
+
+impl Bits {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.length;
+        self.length += size;
+        while self.length >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.length -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.length > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// LZW-compresses palette indices as GIF image data.
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut bits = Bits::default();
+    let mut codes = HashMap::new();
+    let mut next = end + 1;
+    let mut size = MIN_CODE_SIZE + 1;
+    // Widens the codes once the next one to be added needs more bits, in
+    // step with the decoder.
+    let grow = |size: &mut u8, next: u16| {
+        if next >= 1 << *size && *size < MAX_CODE_SIZE {
+            *size += 1;
+        }
+    };
+
+    bits.write(clear, size);
+    let Some((&first, rest)) = indices.split_first() else {
+        bits.write(end, size);
+        return bits.finish();
+    };
+    let mut prefix = first as u16;
+    for &index in rest {
+        if let Some(&code) = codes.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+
+        bits.write(prefix, size);
+        grow(&mut size, next);
+        if next < 1 << MAX_CODE_SIZE {
+            codes.insert((prefix, index), next);
+            next += 1;
+        } else {
+            bits.write(clear, size);
+            codes.clear();
+            next = end + 1;
+            size = MIN_CODE_SIZE + 1;
+        }
+        prefix = index as u16;
+    }
+    bits.write(prefix, size);
+    grow(&mut size, next);
+    bits.write(end, size);
+    bits.finish()
+}
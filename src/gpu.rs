@@ -0,0 +1,290 @@
+//! Escape-time iteration on the GPU, in a wgpu compute shader that writes
+//! back the escape time and escaped iterate of every point of a grid, from
+//! which the smooth values are worked out as on the CPU.
+//!
+//! Shaders iterate in f32, so only views whose points f32 still tells apart
+//! are iterated there, and only the quadratic sets without orbit traps or
+//! distance estimates. Everything else, and every view when there is no
+//! adapter, stays on the CPU.
+
+use std::sync::{mpsc, OnceLock};
+
+use crate::{fractal::smooth_iterations, EscapeResult, Fractal, Params, C};
+
+const SHADER: &str = r#"
+struct Grid {
+    start: vec2<f32>,
+    spacing: vec2<f32>,
+    julia: vec2<f32>,
+    cols: u32,
+    rows: u32,
+    max_iterations: u32,
+    cutoff: f32,
+    fractal: u32,
+    first_row: u32,
+}
+
+struct Sample {
+    z: vec2<f32>,
+    iterations: u32,
+    unused: u32,
+}
+
+const NONE: u32 = 0xffffffffu;
+const PERIOD_TOLERANCE: f32 = 1e-14;
+
+@group(0) @binding(0) var<uniform> grid: Grid;
+@group(0) @binding(1) var<storage, read_write> samples: array<Sample>;
+
+fn in_main_bulbs(c: vec2<f32>) -> bool {
+    let x = c.x - 0.25;
+    let y2 = c.y * c.y;
+    let q = x * x + y2;
+    return q * (q + x) <= y2 / 4.0 || (c.x + 1.0) * (c.x + 1.0) + y2 <= 1.0 / 16.0;
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= grid.cols || id.y >= grid.rows {
+        return;
+    }
+    let c = grid.start + vec2<f32>(f32(id.x), f32(grid.first_row + id.y)) * grid.spacing;
+    var z = vec2<f32>(0.0, 0.0);
+    var k = c;
+    if grid.fractal == 1u {
+        z = c;
+        k = grid.julia;
+    }
+
+    var sample = Sample(vec2<f32>(0.0, 0.0), NONE, 0u);
+    let index = id.y * grid.cols + id.x;
+    if grid.fractal == 0u && in_main_bulbs(c) {
+        samples[index] = sample;
+        return;
+    }
+
+    var saved = z;
+    var period = 0u;
+    var limit = 1u;
+    for (var i = 0u; ; i++) {
+        if dot(z, z) > grid.cutoff {
+            sample = Sample(z, i, 0u);
+            break;
+        }
+        if i > grid.max_iterations {
+            break;
+        }
+
+        var x = z;
+        if grid.fractal == 2u {
+            x = abs(z);
+        } else if grid.fractal == 3u {
+            x.y = -z.y;
+        }
+        z = vec2<f32>(x.x * x.x - x.y * x.y, 2.0 * x.x * x.y) + k;
+
+        let d = z - saved;
+        if dot(d, d) < PERIOD_TOLERANCE {
+            break;
+        }
+        period++;
+        if period == limit {
+            saved = z;
+            period = 0u;
+            limit *= 2u;
+        }
+    }
+    samples[index] = sample;
+}
+"#;
+
+/// Bytes of a `Sample` of the shader.
+const SAMPLE_SIZE: u64 = 16;
+/// Largest buffer of samples read back at once; taller grids are iterated
+/// in bands of rows.
+const BAND_SIZE: u64 = 64 << 20;
+/// Smallest spacing of points, relative to their magnitude, that f32 still
+/// resolves.
+const MIN_SPACING: f64 = 1e-5;
+
+/// A device with the iteration pipeline loaded on it.
+struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl Gpu {
+    fn new() -> Option<Gpu> {
+        let instance =
+            wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle_from_env());
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .ok()?;
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())).ok()?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("escape time"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("escape time"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Some(Gpu {
+            device,
+            queue,
+            pipeline,
+        })
+    }
+
+    /// Iterates the rows `first_row..first_row + rows` of the grid, whose
+    /// other fields `uniform` holds.
+    fn band(&self, uniform: &[u8], cols: usize, first_row: usize, rows: usize) -> Option<Vec<u8>> {
+        let mut uniform = uniform.to_vec();
+        uniform.extend_from_slice(&(first_row as u32).to_le_bytes());
+        uniform.resize(uniform.len().next_multiple_of(16), 0);
+        let size = (cols * rows) as u64 * SAMPLE_SIZE;
+
+        let buffer = |usage, size| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let grid = buffer(
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            uniform.len() as u64,
+        );
+        let samples = buffer(
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            size,
+        );
+        let readback = buffer(
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            size,
+        );
+        self.queue.write_buffer(&grid, 0, &uniform);
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: grid.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: samples.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups((cols as u32).div_ceil(8), (rows as u32).div_ceil(8), 1);
+        }
+        encoder.copy_buffer_to_buffer(&samples, 0, &readback, 0, size);
+        self.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        readback.map_async(wgpu::MapMode::Read, .., move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::wait_indefinitely()).ok()?;
+        receiver.recv().ok()?.ok()?;
+        let bytes = readback.get_mapped_range(..).ok()?.to_vec();
+        Some(bytes)
+    }
+}
+
+fn gpu() -> Option<&'static Gpu> {
+    static GPU: OnceLock<Option<Gpu>> = OnceLock::new();
+    GPU.get_or_init(Gpu::new).as_ref()
+}
+
+/// Code of the fractal in the shader, if it has one.
+fn fractal_code(params: &Params) -> Option<u32> {
+    let single = params.supersampling <= 1;
+    if params.exponent != 2. || params.trap.is_some() || params.distance_estimate || !single {
+        return None;
+    }
+    match params.fractal {
+        Fractal::Mandelbrot => Some(0),
+        Fractal::Julia(_) => Some(1),
+        Fractal::BurningShip => Some(2),
+        Fractal::Tricorn => Some(3),
+        _ => None,
+    }
+}
+
+/// Whether the grid of points `start + (i, j) * spacing` is iterated on the
+/// GPU.
+pub(crate) fn supports(start: (f64, f64), spacing: (f64, f64), params: &Params) -> bool {
+    let magnitude = start.0.abs().max(start.1.abs()).max(1.);
+    let resolved = spacing.0.abs().min(spacing.1.abs()) / magnitude > MIN_SPACING;
+    resolved && fractal_code(params).is_some() && gpu().is_some()
+}
+
+/// Escape results of the `cols * rows` grid of points `start + (i, j) *
+/// spacing` of the plane, row-major, or `None` if the GPU can't iterate it.
+pub(crate) fn iterate_grid(
+    start: (f64, f64),
+    spacing: (f64, f64),
+    (cols, rows): (usize, usize),
+    params: &Params,
+) -> Option<Vec<EscapeResult>> {
+    if !supports(start, spacing, params) || cols == 0 || rows == 0 {
+        return None;
+    }
+    let gpu = gpu()?;
+
+    let julia = match params.fractal {
+        Fractal::Julia(k) => k,
+        _ => C { re: 0., im: 0. },
+    };
+    let floats = [start.0, start.1, spacing.0, spacing.1, julia.re, julia.im];
+    let mut uniform: Vec<u8> = floats
+        .iter()
+        .flat_map(|&v| (v as f32).to_le_bytes())
+        .collect();
+    uniform.extend_from_slice(&(cols as u32).to_le_bytes());
+    uniform.extend_from_slice(&(rows as u32).to_le_bytes());
+    uniform.extend_from_slice(&params.max_iterations.to_le_bytes());
+    uniform.extend_from_slice(&(params.cutoff as f32).to_le_bytes());
+    uniform.extend_from_slice(&fractal_code(params)?.to_le_bytes());
+
+    let band_rows = (BAND_SIZE / (cols as u64 * SAMPLE_SIZE)).max(1) as usize;
+    let mut results = Vec::with_capacity(cols * rows);
+    for first_row in (0..rows).step_by(band_rows) {
+        let bytes = gpu.band(&uniform, cols, first_row, band_rows.min(rows - first_row))?;
+        for sample in bytes.chunks_exact(SAMPLE_SIZE as usize) {
+            let word = |i: usize| u32::from_le_bytes(sample[i * 4..i * 4 + 4].try_into().unwrap());
+            let z = C {
+                re: f32::from_bits(word(0)) as f64,
+                im: f32::from_bits(word(1)) as f64,
+            };
+            results.push(match word(2) {
+                u32::MAX => EscapeResult::default(),
+                i => EscapeResult {
+                    iterations: Some(i),
+                    smooth: smooth_iterations(i, z, params),
+                    ..EscapeResult::default()
+                },
+            });
+        }
+    }
+    Some(results)
+}
@@ -0,0 +1,61 @@
+//! Raw dumps of the iteration counts and smooth escape values of an image,
+//! for analysis outside of the renderer.
+//!
+//! Each pixel is a little-endian `i32` iteration count, `-1` for points that
+//! never escaped, followed by an `f64` smooth escape value, NaN for those
+//! points.
+
+use crate::EscapeResult;
+
+/// First bytes of a flat dump.
+const MAGIC: &[u8; 4] = b"MBIT";
+
+/// Appends the record of each of `results` to `data`.
+fn push_records(data: &mut Vec<u8>, results: &[EscapeResult]) {
+    for result in results {
+        let (iterations, smooth) = match result.iterations {
+            Some(n) => (n.min(i32::MAX as u32) as i32, result.smooth),
+            None => (-1, f64::NAN),
+        };
+        data.extend(iterations.to_le_bytes());
+        data.extend(smooth.to_le_bytes());
+    }
+}
+
+/// Encodes a row-major `width * height` image of escape results as a NumPy
+/// `.npy` file: an array of that shape with the fields `iterations` and
+/// `smooth`.
+pub fn encode_npy(width: usize, height: usize, results: &[EscapeResult]) -> Vec<u8> {
+    let mut header = format!(
+        "{{'descr': [('iterations', '<i4'), ('smooth', '<f8')], 'fortran_order': False, 'shape': ({}, {}), }}",
+        height, width
+    );
+    // The header ends in a newline and pads the data to 64 bytes, after the
+    // magic, the version and its own length.
+    let unpadded = 6 + 2 + 2 + header.len() + 1;
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+    header.push('\n');
+
+    let mut data = b"\x93NUMPY\x01\x00".to_vec();
+    data.extend((header.len() as u16).to_le_bytes());
+    data.extend(header.as_bytes());
+    push_records(&mut data, results);
+    data
+}
+
+/// Encodes a row-major `width * height` image of escape results as a flat
+/// dump: `MBIT`, then the width, height and `max_iterations` as
+/// little-endian `u32`s, then the pixels.
+pub fn encode_raw(
+    width: usize,
+    height: usize,
+    results: &[EscapeResult],
+    max_iterations: u32,
+) -> Vec<u8> {
+    let mut data = MAGIC.to_vec();
+    for n in [width as u32, height as u32, max_iterations] {
+        data.extend(n.to_le_bytes());
+    }
+    push_records(&mut data, results);
+    data
+}
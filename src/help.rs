@@ -0,0 +1,135 @@
+use ratatui::{text::Line, Frame};
+
+use crate::{
+    keymap::{sequence_name, Action, Keymap},
+    ui::{centered, dialog},
+};
+
+/// Mouse controls and what they do, listed before the keys.
+const MOUSE: &[(&str, &str)] = &[
+    ("left click", "zoom in on the point"),
+    ("left drag", "pan the view"),
+    ("right click", "zoom out from the point"),
+    ("right drag", "zoom into the selected box"),
+    ("wheel", "zoom in or out at the pointer"),
+];
+
+/// Actions listed together, separated by `/`, and what they do, in the
+/// order they are listed.
+const KEYS: &[(&[Action], &str)] = &[
+    (&[Action::ZoomIn, Action::ZoomOut], "zoom in / out"),
+    (
+        &[
+            Action::PanLeft,
+            Action::PanRight,
+            Action::PanUp,
+            Action::PanDown,
+        ],
+        "pan",
+    ),
+    (
+        &[Action::RotateLeft, Action::RotateRight],
+        "turn the view counterclockwise / clockwise",
+    ),
+    (&[Action::ResetRotation], "straighten the view"),
+    (&[Action::Reset], "reset the view"),
+    (&[Action::Undo], "undo a move"),
+    (&[Action::Redo], "redo a move"),
+    (&[Action::GoTo], "go to a location"),
+    (&[Action::CopyLocation], "copy the location"),
+    (&[Action::CopyLink], "copy a link to the view"),
+    (&[Action::Command], "type a command, such as :goto RE IM"),
+    (
+        &[Action::SaveBookmark, Action::OpenBookmark],
+        "save / open a bookmark",
+    ),
+    (
+        &[Action::SaveSession, Action::OpenSession],
+        "save / open the session",
+    ),
+    (
+        &[Action::AddKeyframe, Action::EditTour],
+        "add a tour keyframe / edit the tour",
+    ),
+    (&[Action::NextFractal], "next fractal"),
+    (
+        &[Action::ToggleJulia],
+        "open the Julia set of the point under the pointer / go back",
+    ),
+    (
+        &[Action::LowerExponent, Action::RaiseExponent],
+        "lower / raise the exponent",
+    ),
+    (
+        &[
+            Action::JuliaLeft,
+            Action::JuliaRight,
+            Action::JuliaUp,
+            Action::JuliaDown,
+        ],
+        "move the Julia constant",
+    ),
+    (
+        &[Action::HalveIterations, Action::DoubleIterations],
+        "halve / double the iterations",
+    ),
+    (
+        &[Action::ToggleAutoIterations],
+        "toggle zoom-adaptive iterations",
+    ),
+    (&[Action::NextColoring], "next coloring"),
+    (&[Action::NextPalette], "next palette"),
+    (&[Action::ToggleCycling], "cycle the palette"),
+    (&[Action::ToggleColor], "next color mode"),
+    (&[Action::NextCellMode], "next cell mode"),
+    (&[Action::NextBackend], "next graphics backend"),
+    (&[Action::ToggleBuddhabrot], "toggle the Buddhabrot"),
+    (&[Action::Export], "export an image"),
+    (&[Action::Screenshot], "save a screenshot"),
+    (&[Action::ToggleMinimap], "toggle the minimap"),
+    (&[Action::ToggleCrosshair], "toggle the crosshair"),
+    (&[Action::ToggleOrbit], "toggle tracing orbits on click"),
+    (&[Action::TogglePanel], "toggle the side panel"),
+    (&[Action::Help], "show this help"),
+    (&[Action::Quit], "quit"),
+];
+
+/// Controls and what they do, with the keys `keymap` binds. Actions with
+/// no keys are left out.
+fn controls(keymap: &Keymap) -> Vec<(String, &'static str)> {
+    let keys = KEYS.iter().filter_map(|&(actions, what)| {
+        let bound = actions.iter().map(|&action| {
+            let keys: Vec<_> = keymap
+                .keys(action)
+                .iter()
+                .map(|k| sequence_name(k))
+                .collect();
+            keys.join(" ")
+        });
+        let bound: Vec<_> = bound.collect();
+        let any = bound.iter().any(|keys| !keys.is_empty());
+        any.then(|| (bound.join(" / "), what))
+    });
+    MOUSE
+        .iter()
+        .map(|&(control, what)| (control.to_string(), what))
+        .chain(keys)
+        .chain([("count, as 5h".to_string(), "repeat the key that follows")])
+        .collect()
+}
+
+/// Draws the list of controls in a dialog over the middle of `frame`,
+/// leaving out what doesn't fit.
+pub fn draw(frame: &mut Frame, keymap: &Keymap) {
+    let controls = controls(keymap);
+    let keys = controls.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+    let actions = controls.iter().map(|(_, a)| a.len()).max().unwrap_or(0);
+    let lines = controls
+        .iter()
+        .map(|(key, action)| Line::raw(format!(" {:<keys$}  {}", key, action)))
+        .collect();
+
+    let (width, height) = (keys + actions + 5, controls.len() + 2);
+    let area = centered(frame.area(), width as u16, height as u16);
+    dialog(frame, area, "", lines);
+}
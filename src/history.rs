@@ -0,0 +1,50 @@
+use crate::Location;
+
+/// Most views kept to go back to.
+const LIMIT: usize = 1000;
+
+/// Previously visited views, for undo and redo.
+pub struct History {
+    current: Location,
+    back: Vec<Location>,
+    forward: Vec<Location>,
+}
+
+impl History {
+    pub fn new(current: Location) -> Self {
+        History {
+            current,
+            back: Vec::new(),
+            forward: Vec::new(),
+        }
+    }
+
+    /// Records `location` as the current view if it changed, dropping the views
+    /// that could be redone.
+    pub fn visit(&mut self, location: Location) {
+        if location == self.current {
+            return;
+        }
+
+        if self.back.len() == LIMIT {
+            self.back.remove(0);
+        }
+        self.back
+            .push(std::mem::replace(&mut self.current, location));
+        self.forward.clear();
+    }
+
+    pub fn undo(&mut self) -> Option<Location> {
+        let location = self.back.pop()?;
+        self.forward
+            .push(std::mem::replace(&mut self.current, location.clone()));
+        Some(location)
+    }
+
+    pub fn redo(&mut self) -> Option<Location> {
+        let location = self.forward.pop()?;
+        self.back
+            .push(std::mem::replace(&mut self.current, location.clone()));
+        Some(location)
+    }
+}
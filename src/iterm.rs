@@ -0,0 +1,18 @@
+//! Encoder for iTerm2 inline images (`OSC 1337 File=`).
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::png;
+
+/// Encodes a row-major `width * height` RGB image as an iTerm2 inline image.
+pub fn encode(width: usize, height: usize, pixels: &[[u8; 3]]) -> String {
+    let data = png::encode(width, height, pixels);
+
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={}px;height={}px;preserveAspectRatio=0:{}\x07",
+        data.len(),
+        width,
+        height,
+        STANDARD.encode(&data)
+    )
+}
@@ -0,0 +1,171 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use mandelbrot::{palette::Palette, Viewport};
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    cli::{self, Args},
+    error::Error,
+    export_image,
+    link::{self, Link},
+    magnification, Settings,
+};
+
+/// An image of a job file as written, such as
+///
+/// ```toml
+/// [[job]]
+/// output = "seahorse.png"
+/// center = "-0.7436,0.1318"
+/// zoom = 2000
+/// size = "2560x1440"
+/// palette = "fire"
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    output: PathBuf,
+    /// View to start from, as any the command line opens.
+    view: Option<String>,
+    /// Decimal coordinates, as RE,IM.
+    center: Option<String>,
+    zoom: Option<f64>,
+    iterations: Option<u32>,
+    size: Option<String>,
+    palette: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobFile {
+    #[serde(default, rename = "job")]
+    jobs: Vec<Entry>,
+}
+
+/// An image to render, with what it changes of the view the command line
+/// gives.
+pub struct Job {
+    output: PathBuf,
+    view: Option<Link>,
+    center: Option<(String, String)>,
+    zoom: Option<f64>,
+    iterations: Option<u32>,
+    size: Option<(u32, u32)>,
+    palette: Option<Palette>,
+}
+
+impl Entry {
+    fn parse(self) -> Result<Job, String> {
+        let center = self
+            .center
+            .map(|center| {
+                let (re, im) = center
+                    .split_once(',')
+                    .ok_or_else(|| format!("expected a center of RE,IM, got `{}`", center))?;
+                Ok::<_, String>((re.trim().to_string(), im.trim().to_string()))
+            })
+            .transpose()?;
+        if self.zoom.is_some_and(|zoom| zoom <= 0.) {
+            return Err("zoom must be positive".to_string());
+        }
+        if self.iterations == Some(0) {
+            return Err("iterations must be positive".to_string());
+        }
+        Ok(Job {
+            output: self.output,
+            view: self.view.as_deref().map(cli::parse_view).transpose()?,
+            center,
+            zoom: self.zoom,
+            iterations: self.iterations,
+            size: self.size.as_deref().map(cli::parse_size).transpose()?,
+            palette: self
+                .palette
+                .as_deref()
+                .map(cli::parse_palette)
+                .transpose()?,
+        })
+    }
+}
+
+/// Reads the `[[job]]` tables of a job file.
+pub fn load(path: &Path) -> Result<Vec<Job>, Error> {
+    let error = |e: String| Error::Jobs(path.to_path_buf(), e);
+    let source = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    let file: JobFile = toml::from_str(&source).map_err(|e| error(e.to_string()))?;
+    file.jobs
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| entry.parse().map_err(|e| format!("job {}: {}", i + 1, e)))
+        .collect::<Result<_, _>>()
+        .map_err(error)
+}
+
+impl Job {
+    /// Renders the job from the view and settings the command line gives.
+    fn render(&self, viewport: &Viewport, settings: &Settings, args: &Args) -> Result<(), String> {
+        let mut viewport = viewport.clone();
+        let mut settings = settings.clone();
+        if let Some(link) = &self.view {
+            link.apply(&mut viewport, &mut settings);
+        }
+        if self.center.is_some() || self.zoom.is_some() {
+            let (re, im) = self
+                .center
+                .clone()
+                .unwrap_or_else(|| viewport.center_decimal());
+            let zoom = self
+                .zoom
+                .unwrap_or_else(|| magnification(&viewport, &settings.params));
+            link::place(&mut viewport, &settings, (&re, &im), zoom)
+                .ok_or_else(|| format!("invalid center {},{}", re, im))?;
+        }
+        match self.iterations {
+            Some(iterations) => {
+                settings.params.max_iterations = iterations;
+                settings.auto_iterations = false;
+            }
+            None => settings.adapt_iterations(&viewport),
+        }
+        if let Some(palette) = &self.palette {
+            settings.style.palette = palette.clone();
+        }
+        let size = self.size.unwrap_or_else(|| args.size());
+        export_image(&viewport, &settings, &self.output, Some(size), args)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Renders every job, several at once, reporting each as it is written or
+/// fails.
+pub fn render(
+    path: &Path,
+    jobs: &[Job],
+    viewport: &Viewport,
+    settings: &Settings,
+    args: &Args,
+) -> Result<(), Error> {
+    let failed = jobs
+        .par_iter()
+        .filter(|job| match job.render(viewport, settings, args) {
+            Ok(()) => {
+                eprintln!("wrote {}", job.output.display());
+                false
+            }
+            Err(e) => {
+                eprintln!("mandelbrot: {}", e);
+                true
+            }
+        })
+        .count();
+    match failed {
+        0 => Ok(()),
+        n => Err(Error::Jobs(
+            path.to_path_buf(),
+            format!("{} of {} jobs failed", n, jobs.len()),
+        )),
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::terminal::Key;
+
+/// What a key can be bound to, named in the `[keys]` table of the config
+/// file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    Reset,
+    ZoomIn,
+    ZoomOut,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    RotateLeft,
+    RotateRight,
+    ResetRotation,
+    Undo,
+    Redo,
+    GoTo,
+    CopyLocation,
+    CopyLink,
+    Command,
+    SaveBookmark,
+    OpenBookmark,
+    SaveSession,
+    OpenSession,
+    AddKeyframe,
+    EditTour,
+    NextFractal,
+    ToggleJulia,
+    LowerExponent,
+    RaiseExponent,
+    JuliaLeft,
+    JuliaRight,
+    JuliaUp,
+    JuliaDown,
+    HalveIterations,
+    DoubleIterations,
+    ToggleAutoIterations,
+    NextColoring,
+    NextPalette,
+    ToggleCycling,
+    ToggleColor,
+    NextCellMode,
+    NextBackend,
+    ToggleBuddhabrot,
+    Export,
+    Screenshot,
+    ToggleMinimap,
+    ToggleCrosshair,
+    ToggleOrbit,
+    TogglePanel,
+    Help,
+}
+
+/// Keys of each action unless the config file binds it, as they are
+/// written there.
+const DEFAULTS: &[(Action, &[&str])] = &[
+    (Action::Quit, &["q"]),
+    (Action::Reset, &["r", "Home", "g g"]),
+    (Action::ZoomIn, &["+", "=", "z"]),
+    (Action::ZoomOut, &["-", "x"]),
+    (Action::PanLeft, &["Left", "h"]),
+    (Action::PanRight, &["Right", "l"]),
+    (Action::PanUp, &["Up", "k"]),
+    (Action::PanDown, &["Down", "j"]),
+    (Action::RotateLeft, &["<"]),
+    (Action::RotateRight, &[">"]),
+    (Action::ResetRotation, &["g r"]),
+    (Action::Undo, &["u", "Backspace"]),
+    (Action::Redo, &["Ctrl-r"]),
+    (Action::GoTo, &["G"]),
+    (Action::CopyLocation, &["y"]),
+    (Action::CopyLink, &["Y"]),
+    (Action::Command, &[":"]),
+    (Action::SaveBookmark, &["b"]),
+    (Action::OpenBookmark, &["'"]),
+    (Action::SaveSession, &["Ctrl-s"]),
+    (Action::OpenSession, &["Ctrl-o"]),
+    (Action::AddKeyframe, &["K"]),
+    (Action::EditTour, &["T"]),
+    (Action::NextFractal, &["f"]),
+    (Action::ToggleJulia, &["J"]),
+    (Action::LowerExponent, &["e"]),
+    (Action::RaiseExponent, &["E"]),
+    (Action::JuliaLeft, &["A"]),
+    (Action::JuliaRight, &["D"]),
+    (Action::JuliaUp, &["W"]),
+    (Action::JuliaDown, &["S"]),
+    (Action::HalveIterations, &["["]),
+    (Action::DoubleIterations, &["]"]),
+    (Action::ToggleAutoIterations, &["i"]),
+    (Action::NextColoring, &["H"]),
+    (Action::NextPalette, &["P"]),
+    (Action::ToggleCycling, &["C"]),
+    (Action::ToggleColor, &["c"]),
+    (Action::NextCellMode, &["v"]),
+    (Action::NextBackend, &["g b"]),
+    (Action::ToggleBuddhabrot, &["B"]),
+    (Action::Export, &["s"]),
+    (Action::Screenshot, &["p"]),
+    (Action::ToggleMinimap, &["m"]),
+    (Action::ToggleCrosshair, &["*"]),
+    (Action::ToggleOrbit, &["o"]),
+    (Action::TogglePanel, &["Tab"]),
+    (Action::Help, &["?"]),
+];
+
+/// Largest count that can be typed before a key.
+const MAX_COUNT: u32 = 999;
+
+/// Keys given for an action in the config file: one, or a list of them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Keys pressed one after another, such as `g g`.
+type Sequence = Vec<Key>;
+
+/// Key sequences bound to each action, in the order of [`DEFAULTS`].
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<Sequence>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let parse = |keys: &[&str]| keys.iter().map(|k| parse_sequence(k).unwrap()).collect();
+        Keymap {
+            bindings: DEFAULTS
+                .iter()
+                .map(|&(action, keys)| (action, parse(keys)))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// The default keymap with the actions in `keys` bound to those keys
+    /// instead. Fails if a sequence ends up bound to two actions, or starts
+    /// another one and so would never be finished.
+    pub fn new(keys: &HashMap<Action, Keys>) -> Result<Keymap, String> {
+        let mut keymap = Keymap::default();
+        for (action, bound) in &mut keymap.bindings {
+            *bound = match keys.get(action) {
+                Some(Keys::One(keys)) => vec![parse_sequence(keys)?],
+                Some(Keys::Many(keys)) => keys
+                    .iter()
+                    .map(|keys| parse_sequence(keys))
+                    .collect::<Result<_, _>>()?,
+                None => continue,
+            };
+        }
+
+        let written = |keys: &[Key]| {
+            let names: Vec<_> = keys.iter().map(|&key| key_name(key)).collect();
+            names.join(" ")
+        };
+        let all: Vec<_> = keymap
+            .bindings
+            .iter()
+            .flat_map(|(action, sequences)| sequences.iter().map(move |s| (*action, s)))
+            .collect();
+        for (i, &(action, sequence)) in all.iter().enumerate() {
+            for &(other, longer) in &all[i + 1..] {
+                let (short, long) = match sequence.len() <= longer.len() {
+                    true => (sequence, longer),
+                    false => (longer, sequence),
+                };
+                if action != other && long.starts_with(short) {
+                    return Err(format!(
+                        "`{}` of {} conflicts with `{}` of {}",
+                        written(sequence),
+                        name(action),
+                        written(longer),
+                        name(other)
+                    ));
+                }
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// What the keys typed so far do: an action, nothing yet if they start a
+    /// longer sequence, or `None` if nothing is bound to them.
+    fn lookup(&self, keys: &[Key]) -> Option<Option<Action>> {
+        let mut prefix = false;
+        for (action, sequences) in &self.bindings {
+            for sequence in sequences {
+                if sequence == keys {
+                    return Some(Some(*action));
+                }
+                prefix |= sequence.starts_with(keys);
+            }
+        }
+        prefix.then_some(None)
+    }
+
+    /// Sequences bound to `action`.
+    pub fn keys(&self, action: Action) -> &[Sequence] {
+        self.bindings
+            .iter()
+            .find(|&&(a, _)| a == action)
+            .map_or(&[], |(_, keys)| keys)
+    }
+
+    /// Hint naming the first keys bound to `action`, to follow a message, as
+    /// in ", press b to add one", or nothing if no keys are.
+    pub fn hint(&self, action: Action, to: &str) -> String {
+        match self.keys(action).first() {
+            Some(keys) => format!(", press {} {}", sequence_name(keys), to),
+            None => String::new(),
+        }
+    }
+}
+
+/// Keys typed towards an action, vim-style: an optional count, such as the
+/// `5` of `5h`, then a key sequence.
+#[derive(Default)]
+pub struct Pending {
+    count: Option<u32>,
+    keys: Sequence,
+}
+
+impl Pending {
+    /// Adds `key` to those typed, returning the action they make up and how
+    /// many times to repeat it. Keys bound to nothing are dropped with the
+    /// count before them.
+    pub fn push(&mut self, key: Key, keymap: &Keymap) -> Option<(Action, u32)> {
+        // Digits not bound to anything make up the count, which can't start
+        // with 0.
+        let digit = match key {
+            Key::Char(c) if self.keys.is_empty() && keymap.lookup(&[key]).is_none() => {
+                c.to_digit(10).filter(|&d| d > 0 || self.count.is_some())
+            }
+            _ => None,
+        };
+        if let Some(digit) = digit {
+            let count = self.count.unwrap_or(0) * 10 + digit;
+            self.count = Some(count.min(MAX_COUNT));
+            return None;
+        }
+
+        self.keys.push(key);
+        match keymap.lookup(&self.keys) {
+            Some(None) => None,
+            found => {
+                let count = self.count.take().unwrap_or(1);
+                self.keys.clear();
+                found.flatten().map(|action| (action, count))
+            }
+        }
+    }
+}
+
+/// Name of `action` as in the config file.
+fn name(action: Action) -> String {
+    let debug = format!("{:?}", action);
+    let mut name = String::new();
+    for c in debug.chars() {
+        if c.is_ascii_uppercase() && !name.is_empty() {
+            name.push('-');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+/// Named keys, as written in the config file and shown in the help.
+const NAMED: &[(&str, Key)] = &[
+    ("Left", Key::Left),
+    ("Right", Key::Right),
+    ("Up", Key::Up),
+    ("Down", Key::Down),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("Backspace", Key::Backspace),
+    ("Delete", Key::Delete),
+    ("Insert", Key::Insert),
+    ("Tab", Key::Char('\t')),
+    ("Space", Key::Char(' ')),
+    ("Esc", Key::Esc),
+];
+
+/// Parses a key as a character, `Ctrl-C` or `Alt-C` for a character `C`,
+/// a name such as `Left` or `PageUp`, or `F1` to `F12`.
+fn parse_key(s: &str) -> Result<Key, String> {
+    let single = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    let lower = s.to_ascii_lowercase();
+    if let Some(c) = single(s) {
+        return Ok(Key::Char(c));
+    }
+    if let Some(c) = lower.strip_prefix("ctrl-").and_then(single) {
+        return Ok(Key::Ctrl(c));
+    }
+    if let Some(c) = s
+        .get(4..)
+        .filter(|_| lower.starts_with("alt-"))
+        .and_then(single)
+    {
+        return Ok(Key::Alt(c));
+    }
+    if let Some(&(_, key)) = NAMED.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)) {
+        return Ok(key);
+    }
+    match lower.strip_prefix('f').map(str::parse::<u8>) {
+        Some(Ok(n @ 1..=12)) => Ok(Key::F(n)),
+        _ => Err(format!("unknown key `{}`", s)),
+    }
+}
+
+/// Parses keys separated by spaces, pressed one after another.
+pub fn parse_sequence(s: &str) -> Result<Sequence, String> {
+    match s {
+        " " => Ok(vec![Key::Char(' ')]),
+        _ if s.trim().is_empty() => Err("empty key sequence".to_string()),
+        _ => s.split_whitespace().map(parse_key).collect(),
+    }
+}
+
+/// `sequence` as shown in the help: the names of its keys, run together if
+/// they are all characters.
+pub fn sequence_name(sequence: &[Key]) -> String {
+    let names: Vec<_> = sequence.iter().map(|&key| key_name(key)).collect();
+    match sequence.iter().all(|key| matches!(key, Key::Char(_))) {
+        true => names.concat(),
+        false => names.join(" "),
+    }
+}
+
+/// `key` as it is written in the config file.
+fn key_name(key: Key) -> String {
+    if let Some((name, _)) = NAMED.iter().find(|&&(_, k)| k == key) {
+        return name.to_string();
+    }
+    match key {
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl-{}", c.to_ascii_uppercase()),
+        Key::Alt(c) => format!("Alt-{}", c),
+        Key::F(n) => format!("F{}", n),
+        key => format!("{:?}", key),
+    }
+}
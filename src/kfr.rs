@@ -0,0 +1,84 @@
+use std::{fs, path::Path};
+
+use mandelbrot::{Fractal, Origin};
+
+use crate::{
+    cli::{self, FractalKind},
+    link::Link,
+};
+
+/// Height of the plane Kalles Fraktaler shows at a zoom of 1.
+const KF_HEIGHT: f64 = 4.;
+
+/// Reads a Kalles Fraktaler location file, of `Key: value` lines such as
+/// `Re: -1.768...`, `Zoom: 1.5E100` and `Iterations: 20000`.
+pub fn load(path: &Path) -> Result<Link, String> {
+    fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|source| parse(&source))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Parses the fields of a location file, of which `Re`, `Im` and `Zoom` are
+/// required and those without a counterpart are skipped.
+pub fn parse(source: &str) -> Result<Link, String> {
+    let field = |key: &str| {
+        source.lines().find_map(|line| {
+            let (k, value) = line.split_once(':')?;
+            k.trim().eq_ignore_ascii_case(key).then(|| value.trim())
+        })
+    };
+    let number = |key: &str| -> Result<Option<f64>, String> {
+        field(key)
+            .map(|value| {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|v| v.is_finite())
+                    .ok_or_else(|| format!("{} must be a number, got `{}`", key, value))
+            })
+            .transpose()
+    };
+
+    let (Some(re), Some(im)) = (field("Re"), field("Im")) else {
+        return Err("no Re and Im of the center".to_string());
+    };
+    if Origin::parse(re, im).is_none() {
+        return Err(format!("invalid center {},{}", re, im));
+    }
+    let mut link = Link::new((re.to_string(), im.to_string()));
+
+    link.fractal = match number("FractalType")?.unwrap_or(0.) as u32 {
+        0 => FractalKind::Mandelbrot,
+        1 => FractalKind::BurningShip,
+        4 => FractalKind::Tricorn,
+        n => return Err(format!("fractal type {} isn't supported", n)),
+    };
+    // Kalles Fraktaler measures zoom against a view 4 high, the fractals
+    // here against their own default views.
+    let fractal = match link.fractal {
+        FractalKind::BurningShip => Fractal::BurningShip,
+        FractalKind::Tricorn => Fractal::Tricorn,
+        _ => Fractal::Mandelbrot,
+    };
+    let (_, (_, height)) = fractal.default_bounds();
+    link.zoom = match number("Zoom") {
+        Ok(Some(zoom)) if zoom > 0. => zoom * height / KF_HEIGHT,
+        Ok(None) => return Err("no Zoom".to_string()),
+        // Zooms past f64 overflow, and are deeper than views go anyway.
+        _ => {
+            return Err(format!(
+                "unsupported zoom `{}`",
+                field("Zoom").unwrap_or("")
+            ))
+        }
+    };
+    link.iterations = match number("Iterations")? {
+        Some(n) if n >= 1. => Some(n.min(u32::MAX as f64) as u32),
+        Some(_) => return Err("Iterations must be positive".to_string()),
+        None => None,
+    };
+    link.exponent = number("Power")?.map(cli::check_exponent).transpose()?;
+    link.rotation = number("Rotate")?.unwrap_or(0.);
+    Ok(link)
+}
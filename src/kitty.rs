@@ -0,0 +1,38 @@
+//! Encoder for the kitty terminal graphics protocol.
+
+use std::fmt::Write;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+const CHUNK_SIZE: usize = 4096;
+
+/// Encodes a row-major `width * height` RGB image as a sequence of kitty
+/// graphics commands that replace any previously displayed image.
+pub fn encode(width: usize, height: usize, pixels: &[[u8; 3]]) -> String {
+    let data = STANDARD.encode(pixels.concat());
+    // `q=2` suppresses the terminal's responses, which would otherwise show
+    // up on stdin as key events.
+    let mut out = String::from("\x1b_Ga=d,q=2\x1b\\");
+
+    let chunks: Vec<&str> = data
+        .as_bytes()
+        .chunks(CHUNK_SIZE)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap())
+        .collect();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        if i == 0 {
+            write!(
+                out,
+                "\x1b_Ga=T,f=24,s={},v={},q=2,m={};{}\x1b\\",
+                width, height, more, chunk
+            )
+            .unwrap();
+        } else {
+            write!(out, "\x1b_Gm={};{}\x1b\\", more, chunk).unwrap();
+        }
+    }
+
+    out
+}
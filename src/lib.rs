@@ -0,0 +1,821 @@
+pub mod buddhabrot;
+mod color;
+pub mod complex;
+mod double;
+pub mod formula;
+mod fractal;
+pub mod gif;
+#[cfg(feature = "gpu")]
+mod gpu;
+pub mod grid;
+pub mod history;
+pub mod iterm;
+pub mod kitty;
+pub mod output;
+pub mod palette;
+mod perturbation;
+pub mod png;
+pub mod pnm;
+mod precise;
+pub mod simd;
+pub mod sixel;
+mod subdivide;
+pub mod svg;
+pub mod tiles;
+mod viewport;
+
+use std::{
+    ops::Range,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use color::{CellWriter, Shader};
+use double::DoubleDouble;
+use fractal::average;
+use perturbation::Reference;
+use precise::Precise;
+use rayon::prelude::*;
+use tiles::TileCache;
+
+pub use color::{pixel_color, Cell, CellMode, Color, ColorMap, ColorMode, Coloring, Ramp, Style};
+pub use complex::C;
+pub use fractal::{
+    check_convergence, iterate, orbit, zoom_iterations, EscapeResult, Fractal, Iteration, Params,
+    Precision, Trap,
+};
+pub use viewport::{precision, Bounds, Location, Origin, Viewport};
+
+/// Precision the points of `viewport` are iterated in: the one `params`
+/// asks for, or else the cheapest that resolves its cells. Deep views are
+/// perturbed where the fractal allows, and otherwise iterated in
+/// double-double until they are too deep for it.
+pub fn active_precision(viewport: &Viewport, params: &Params) -> Precision {
+    if let Some(precision) = params.precision {
+        return precision;
+    }
+
+    match viewport.is_deep() {
+        false if viewport.is_shallow() => Precision::F32,
+        false => Precision::F64,
+        true if perturbation::supports(params) => Precision::Perturbed,
+        true if !precise::supports(params) => Precision::F64,
+        true if double::resolves(viewport) => Precision::DoubleDouble,
+        true => Precision::Arbitrary,
+    }
+}
+
+/// How the points of a view are iterated.
+enum Kernel<'a> {
+    /// In f32 or f64 coordinates, which resolve all but deep views.
+    Direct(Precision),
+    Perturbed(Reference),
+    DoubleDouble(DoubleDouble),
+    Precise(Precise<'a>),
+}
+
+/// Iterates the points of a view in its [`active_precision`].
+pub(crate) struct Sampler<'a> {
+    viewport: &'a Viewport,
+    params: &'a Params,
+    kernel: Kernel<'a>,
+}
+
+impl<'a> Sampler<'a> {
+    fn new(viewport: &'a Viewport, params: &'a Params) -> Self {
+        let kernel = match active_precision(viewport, params) {
+            precision @ (Precision::F32 | Precision::F64) => Some(Kernel::Direct(precision)),
+            // A reference that escapes right away leaves nothing to perturb.
+            Precision::Perturbed => Reference::new(viewport, params)
+                .map(Kernel::Perturbed)
+                .or_else(|| Precise::new(viewport, params).map(Kernel::Precise)),
+            Precision::DoubleDouble => {
+                DoubleDouble::new(viewport, params).map(Kernel::DoubleDouble)
+            }
+            Precision::Arbitrary => Precise::new(viewport, params).map(Kernel::Precise),
+        };
+
+        Sampler {
+            viewport,
+            params,
+            kernel: kernel.unwrap_or(Kernel::Direct(Precision::F64)),
+        }
+    }
+
+    /// Iterates the points at fractional cell positions, each the center of
+    /// an `sx * sy` part of a cell.
+    fn sample_all(&self, positions: &[(f64, f64)], (sx, sy): (f64, f64)) -> Vec<EscapeResult> {
+        let points: Vec<(f64, f64)> = positions
+            .iter()
+            .map(|&(term_x, term_y)| self.viewport.offset(term_x, term_y))
+            .collect();
+        self.at_all(&points, self.plane_spacing((sx, sy)))
+    }
+
+    /// Size in the plane of an `sx * sy` part of a cell.
+    fn plane_spacing(&self, (sx, sy): (f64, f64)) -> (f64, f64) {
+        let (width, height) = self.viewport.cell_size();
+        (sx * width, sy * height)
+    }
+
+    /// Escape results of the `cols * rows` grid of points at the centers of
+    /// the `sx * sy` parts `(i, first_row + j)` of cells, row-major, iterated
+    /// on the GPU if it can. Returns `None` once `cancel` is set.
+    fn sample_grid(
+        &self,
+        (cols, rows): (usize, usize),
+        first_row: usize,
+        (sx, sy): (f64, f64),
+        cancel: &AtomicBool,
+    ) -> Option<Vec<EscapeResult>> {
+        // The GPU grid runs along the axes of the plane.
+        #[cfg(feature = "gpu")]
+        if let (Kernel::Direct(_), 0.) = (&self.kernel, self.viewport.rotation) {
+            let start = self
+                .viewport
+                .sample(0.5 * sx, (first_row as f64 + 0.5) * sy);
+            let (x, y) = self
+                .viewport
+                .sample(1.5 * sx, (first_row as f64 + 1.5) * sy);
+            let spacing = (x - start.0, y - start.1);
+            if let Some(grid) = gpu::iterate_grid(start, spacing, (cols, rows), self.params) {
+                return Some(grid);
+            }
+        }
+
+        let point = |i: usize, j: usize| {
+            let (x, y) = ((i as f64 + 0.5) * sx, ((first_row + j) as f64 + 0.5) * sy);
+            self.viewport.offset(x, y)
+        };
+        self.at_grid((cols, rows), point, self.plane_spacing((sx, sy)), cancel)
+    }
+
+    /// Escape results of the `cols * rows` grid of points `point(i, j)`,
+    /// `spacing` apart, row-major. Supersampling covers all of them, or only
+    /// those on edges when it is adaptive. Returns `None` once `cancel` is
+    /// set.
+    pub(crate) fn at_grid(
+        &self,
+        (cols, rows): (usize, usize),
+        point: impl Fn(usize, usize) -> (f64, f64) + Sync,
+        spacing: (f64, f64),
+        cancel: &AtomicBool,
+    ) -> Option<Vec<EscapeResult>> {
+        let points = |indices: &[(usize, usize)]| -> Vec<(f64, f64)> {
+            indices.iter().map(|&(i, j)| point(i, j)).collect()
+        };
+        let adaptive = self.params.adaptive_supersampling && self.params.supersampling > 1;
+        if !adaptive {
+            return subdivide::sample_grid((cols, rows), cancel, |indices| {
+                self.at_all(&points(indices), spacing)
+            });
+        }
+
+        let mut grid = subdivide::sample_grid((cols, rows), cancel, |indices| {
+            self.iterate_all(&points(indices))
+        })?;
+        let edges = subdivide::edges(&grid, (cols, rows));
+        let refined: Vec<EscapeResult> = edges
+            .par_chunks(subdivide::BLOCK)
+            .flat_map_iter(|edges| self.at_all(&points(edges), spacing))
+            .collect();
+        for (&(i, j), result) in edges.iter().zip(refined) {
+            grid[j * cols + i] = result;
+        }
+        Some(grid)
+    }
+
+    /// Iterates the points at `points` from the origin of the view, each
+    /// averaged over the `spacing` sized area around it when supersampling.
+    fn at_all(&self, points: &[(f64, f64)], spacing: (f64, f64)) -> Vec<EscapeResult> {
+        let n = self.params.supersampling.max(1) as usize;
+        if n == 1 {
+            return self.iterate_all(points);
+        }
+
+        let offset = |k: usize, s: f64| ((k as f64 + 0.5) / n as f64 - 0.5) * s;
+        let subsamples: Vec<(f64, f64)> = points
+            .iter()
+            .flat_map(|&(x, y)| {
+                (0..n * n)
+                    .map(move |k| (x + offset(k % n, spacing.0), y + offset(k / n, spacing.1)))
+            })
+            .collect();
+        self.iterate_all(&subsamples)
+            .chunks(n * n)
+            .map(average)
+            .collect()
+    }
+
+    /// Iterates the points at `points` from the origin of the view, several
+    /// at once where the kernel allows.
+    fn iterate_all(&self, points: &[(f64, f64)]) -> Vec<EscapeResult> {
+        match &self.kernel {
+            Kernel::Direct(precision) => {
+                let (re, im) = self.viewport.origin.approx();
+                let points: Vec<C> = points
+                    .iter()
+                    .map(|&(x, y)| C {
+                        re: re + x,
+                        im: im + y,
+                    })
+                    .collect();
+                simd::iterate_all(&points, self.params, *precision)
+            }
+            _ => points.iter().map(|&point| self.at(point)).collect(),
+        }
+    }
+
+    /// Iterates the point at `(x, y)` from the origin of the view.
+    fn at(&self, (x, y): (f64, f64)) -> EscapeResult {
+        match &self.kernel {
+            Kernel::Direct(_) => {
+                let (re, im) = self.viewport.origin.approx();
+                iterate(
+                    C {
+                        re: re + x,
+                        im: im + y,
+                    },
+                    self.params,
+                )
+            }
+            Kernel::Perturbed(reference) => reference.iterate((x, y), self.params),
+            Kernel::DoubleDouble(double) => double.iterate((x, y), self.params),
+            Kernel::Precise(precise) => precise.iterate((x, y), self.params),
+        }
+    }
+}
+
+impl CellMode {
+    /// Offsets of the samples of a cell, at the centers of the parts of it
+    /// they stand for, in the order `CellWriter` expects them.
+    fn offsets(self) -> &'static [(f64, f64)] {
+        match self {
+            CellMode::Full | CellMode::Background => &[(0.5, 0.5)],
+            CellMode::HalfBlock => &[(0.5, 0.25), (0.5, 0.75)],
+            CellMode::Braille => &[
+                (0.25, 0.125),
+                (0.75, 0.125),
+                (0.25, 0.375),
+                (0.75, 0.375),
+                (0.25, 0.625),
+                (0.75, 0.625),
+                (0.25, 0.875),
+                (0.75, 0.875),
+            ],
+        }
+    }
+
+    /// Number of samples of a cell along each axis.
+    pub(crate) fn subdivision(self) -> (usize, usize) {
+        match self {
+            CellMode::Full | CellMode::Background => (1, 1),
+            CellMode::HalfBlock => (1, 2),
+            CellMode::Braille => (2, 4),
+        }
+    }
+}
+
+/// Samples of the cells at `positions`, one after the other.
+fn sample_cells(
+    sampler: &Sampler,
+    positions: &[(usize, usize)],
+    cells: CellMode,
+) -> Vec<EscapeResult> {
+    let points: Vec<(f64, f64)> = positions
+        .iter()
+        .flat_map(|&(term_x, term_y)| {
+            let (x, y) = (term_x as f64, term_y as f64);
+            cells.offsets().iter().map(move |(dx, dy)| (x + dx, y + dy))
+        })
+        .collect();
+    let (kx, ky) = cells.subdivision();
+    sampler.sample_all(&points, (1. / kx as f64, 1. / ky as f64))
+}
+
+/// Samples every `step`-th position of a `width * height` grid along both
+/// axes and fills the `step * step` block starting there with its samples,
+/// row-major. `sample` is passed the positions of a row at once and returns
+/// the same number of samples for each. Returns `None` once `cancel` is set.
+fn sample_blocks(
+    (width, height): (usize, usize),
+    step: usize,
+    cancel: &AtomicBool,
+    sample: impl Fn(&[(usize, usize)]) -> Vec<EscapeResult> + Sync,
+) -> Option<Vec<EscapeResult>> {
+    let step = step.max(1);
+    let rows: Vec<Vec<EscapeResult>> = (0..height)
+        .into_par_iter()
+        .step_by(step)
+        .map(|y| {
+            (!cancel.load(Ordering::Relaxed)).then(|| {
+                let positions: Vec<(usize, usize)> =
+                    (0..width).step_by(step).map(|x| (x, y)).collect();
+                let samples = sample(&positions);
+                let n = samples.len() / positions.len().max(1);
+
+                let mut row = Vec::with_capacity(width * n);
+                for (&(x, _), block) in positions.iter().zip(samples.chunks(n.max(1))) {
+                    for _ in 0..step.min(width - x) {
+                        row.extend_from_slice(block);
+                    }
+                }
+                row
+            })
+        })
+        .collect::<Option<_>>()?;
+
+    Some(
+        rows.iter()
+            .enumerate()
+            .flat_map(|(i, row)| std::iter::repeat_n(row, step.min(height - i * step)))
+            .flatten()
+            .copied()
+            .collect(),
+    )
+}
+
+/// Colors the cells of `samples`, drawn as `cells` does.
+fn color_cells<'a>(
+    samples: &[EscapeResult],
+    mode: ColorMode,
+    cells: CellMode,
+    shader: &'a Shader,
+) -> CellWriter<'a> {
+    let mut row = CellWriter::new(mode, shader);
+
+    for cell in samples.chunks(cells.offsets().len()) {
+        match cells {
+            CellMode::Full => row.push(cell[0]),
+            CellMode::Background => row.push_background(cell[0]),
+            CellMode::HalfBlock => row.push_half(cell[0], cell[1]),
+            CellMode::Braille => row.push_braille(std::array::from_fn(|dy| {
+                std::array::from_fn(|dx| cell[dy * 2 + dx])
+            })),
+        }
+    }
+
+    row
+}
+
+/// Escape results of a rendered frame, kept so it can be recolored without
+/// iterating again.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Samples of each cell, or each pixel for images, row-major.
+    samples: Vec<EscapeResult>,
+    cells: CellMode,
+    pub width: usize,
+    pub height: usize,
+    /// Distance in the plane between neighbouring samples.
+    spacing: f64,
+    /// Iteration limit the samples were iterated with.
+    max_iterations: u32,
+}
+
+impl Frame {
+    /// Samples every cell of `viewport`, with as many samples per cell as `cells` packs.
+    pub fn from_cells(viewport: &Viewport, params: &Params, cells: CellMode) -> Frame {
+        Frame::try_from_cells(viewport, params, cells, 1, &AtomicBool::new(false)).unwrap()
+    }
+
+    /// Like [`Frame::from_cells`], but only samples every `step`-th cell
+    /// along both axes, filling the blocks in between, and gives up and
+    /// returns `None` once `cancel` is set.
+    pub fn try_from_cells(
+        viewport: &Viewport,
+        params: &Params,
+        cells: CellMode,
+        step: usize,
+        cancel: &AtomicBool,
+    ) -> Option<Frame> {
+        if step > 1 {
+            let sampler = Sampler::new(viewport, params);
+            let size = (viewport.width as usize, viewport.height as usize);
+            let samples = sample_blocks(size, step, cancel, |positions| {
+                sample_cells(&sampler, positions, cells)
+            })?;
+            return Some(Frame::of_cells(viewport, params, cells, samples));
+        }
+
+        Frame::try_from_rows(viewport, params, cells, 0..viewport.height, None, cancel)
+    }
+
+    /// Like [`Frame::from_cells`], but takes the samples `cache` holds from
+    /// earlier frames at the same scale, snapping them to its grid, and
+    /// gives up and returns `None` once `cancel` is set.
+    pub fn try_from_cells_cached(
+        viewport: &Viewport,
+        params: &Params,
+        cells: CellMode,
+        cache: &mut TileCache,
+        cancel: &AtomicBool,
+    ) -> Option<Frame> {
+        let rows = 0..viewport.height;
+        Frame::try_from_rows(viewport, params, cells, rows, Some(cache), cancel)
+    }
+
+    /// The frame of the band of `rows` of the cells of `viewport`, taking
+    /// the samples `cache` holds if one is given. Frames of neighbouring
+    /// bands are joined with [`Frame::extend`]. Returns `None` once `cancel`
+    /// is set.
+    pub fn try_from_rows(
+        viewport: &Viewport,
+        params: &Params,
+        cells: CellMode,
+        rows: Range<u16>,
+        cache: Option<&mut TileCache>,
+        cancel: &AtomicBool,
+    ) -> Option<Frame> {
+        let (kx, ky) = cells.subdivision();
+        let ((x_min, width), (y_min, height)) = viewport.bounds();
+        let first_row = rows.start as usize * ky;
+        let size = (viewport.width as usize * kx, rows.len() * ky);
+        let sampler = Sampler::new(viewport, params);
+
+        // The cache's grid runs along the axes of the plane.
+        let grid = match cache.filter(|_| viewport.rotation == 0.) {
+            Some(cache) => {
+                let spacing = (
+                    width / size.0 as f64,
+                    height / (viewport.height as usize * ky) as f64,
+                );
+                let start = (x_min, y_min + first_row as f64 * spacing.1);
+                cache.sample(&sampler, start, spacing, size, cancel)?
+            }
+            None => {
+                let step = (1. / kx as f64, 1. / ky as f64);
+                sampler.sample_grid(size, first_row, step, cancel)?
+            }
+        };
+        Some(Frame::of_grid(viewport, params, cells, rows.len(), &grid))
+    }
+
+    /// Frame of `rows` rows of the samples of `cells` taken from a row-major
+    /// grid with `cells.subdivision()` points per cell along each axis.
+    fn of_grid(
+        viewport: &Viewport,
+        params: &Params,
+        cells: CellMode,
+        rows: usize,
+        grid: &[EscapeResult],
+    ) -> Frame {
+        let (kx, ky) = cells.subdivision();
+        let cols = viewport.width as usize * kx;
+
+        let mut samples = Vec::with_capacity(grid.len());
+        for y in 0..rows {
+            for x in 0..viewport.width as usize {
+                samples.extend(cells.offsets().iter().map(|(dx, dy)| {
+                    let (i, j) = (
+                        x * kx + (dx * kx as f64) as usize,
+                        y * ky + (dy * ky as f64) as usize,
+                    );
+                    grid[j * cols + i]
+                }));
+            }
+        }
+        Frame {
+            height: rows,
+            ..Frame::of_cells(viewport, params, cells, samples)
+        }
+    }
+
+    /// Appends the rows of `below`, a frame of the same width and cells
+    /// sampled right below the view of this one.
+    pub fn extend(&mut self, below: Frame) {
+        self.samples.extend(below.samples);
+        self.height += below.height;
+    }
+
+    fn of_cells(
+        viewport: &Viewport,
+        params: &Params,
+        cells: CellMode,
+        samples: Vec<EscapeResult>,
+    ) -> Frame {
+        let spacing = viewport.scale / cells.subdivision().0 as f64;
+
+        Frame {
+            samples,
+            cells,
+            width: viewport.width as usize,
+            height: viewport.height as usize,
+            spacing,
+            max_iterations: params.max_iterations,
+        }
+    }
+
+    /// Samples every pixel of a `width * height` image of `viewport`.
+    pub fn from_pixels(viewport: &Viewport, params: &Params, width: u32, height: u32) -> Frame {
+        Frame::try_from_pixels(viewport, params, width, height, 1, &AtomicBool::new(false)).unwrap()
+    }
+
+    /// Like [`Frame::from_pixels`], but only samples every `step`-th pixel
+    /// along both axes, filling the blocks in between, and gives up and
+    /// returns `None` once `cancel` is set.
+    pub fn try_from_pixels(
+        viewport: &Viewport,
+        params: &Params,
+        width: u32,
+        height: u32,
+        step: usize,
+        cancel: &AtomicBool,
+    ) -> Option<Frame> {
+        Some(Frame {
+            samples: sample_pixels(viewport, params, width, height, step, cancel)?,
+            cells: CellMode::Full,
+            width: width as usize,
+            height: height as usize,
+            spacing: viewport.extent().0 / width as f64,
+            max_iterations: params.max_iterations,
+        })
+    }
+
+    /// The frame of the band of `rows` of the pixels of a `width * height`
+    /// image of `viewport`, for rendering images too large to hold at once.
+    pub fn from_pixel_rows(
+        viewport: &Viewport,
+        params: &Params,
+        (width, height): (u32, u32),
+        rows: Range<u32>,
+    ) -> Frame {
+        let step = (
+            viewport.width as f64 / width as f64,
+            viewport.height as f64 / height as f64,
+        );
+        let size = (width as usize, rows.len());
+        let sampler = Sampler::new(viewport, params);
+        let cancel = AtomicBool::new(false);
+        Frame {
+            samples: sampler
+                .sample_grid(size, rows.start as usize, step, &cancel)
+                .unwrap(),
+            cells: CellMode::Full,
+            width: width as usize,
+            height: rows.len(),
+            spacing: viewport.extent().0 / width as f64,
+            max_iterations: params.max_iterations,
+        }
+    }
+
+    /// Like [`Frame::from_pixels`], but takes the samples `cache` holds from
+    /// earlier frames at the same scale, snapping them to its grid, and
+    /// gives up and returns `None` once `cancel` is set.
+    pub fn try_from_pixels_cached(
+        viewport: &Viewport,
+        params: &Params,
+        width: u32,
+        height: u32,
+        cache: &mut TileCache,
+        cancel: &AtomicBool,
+    ) -> Option<Frame> {
+        let rows = 0..height;
+        Frame::try_from_pixel_rows(viewport, params, (width, height), rows, Some(cache), cancel)
+    }
+
+    /// The frame of the band of `rows` of the pixels of a `width * height`
+    /// image of `viewport`, like [`Frame::try_from_rows`] for cells.
+    pub fn try_from_pixel_rows(
+        viewport: &Viewport,
+        params: &Params,
+        (width, height): (u32, u32),
+        rows: Range<u32>,
+        cache: Option<&mut TileCache>,
+        cancel: &AtomicBool,
+    ) -> Option<Frame> {
+        let ((x_min, w), (y_min, h)) = viewport.bounds();
+        let spacing = (w / width as f64, h / height as f64);
+        let size = (width as usize, rows.len());
+        let sampler = Sampler::new(viewport, params);
+
+        let samples = match cache.filter(|_| viewport.rotation == 0.) {
+            Some(cache) => {
+                let start = (x_min, y_min + rows.start as f64 * spacing.1);
+                cache.sample(&sampler, start, spacing, size, cancel)?
+            }
+            None => {
+                let step = (
+                    viewport.width as f64 / width as f64,
+                    viewport.height as f64 / height as f64,
+                );
+                sampler.sample_grid(size, rows.start as usize, step, cancel)?
+            }
+        };
+        Some(Frame {
+            samples,
+            cells: CellMode::Full,
+            width: width as usize,
+            height: rows.len(),
+            spacing: spacing.0,
+            max_iterations: params.max_iterations,
+        })
+    }
+
+    /// The frame of `viewport`, which is this frame's view panned by `dx`
+    /// and `dy` cells: samples still in view are moved over and only the
+    /// cells coming into view are iterated. Only for frames sampled from
+    /// cells. Returns `None` once `cancel` is set.
+    pub fn try_shifted(
+        &self,
+        viewport: &Viewport,
+        params: &Params,
+        (dx, dy): (isize, isize),
+        cancel: &AtomicBool,
+    ) -> Option<Frame> {
+        let sampler = Sampler::new(viewport, params);
+        let n = self.cells.offsets().len();
+        let (width, height) = (self.width as isize, self.height as isize);
+
+        let rows: Vec<Vec<EscapeResult>> = (0..self.height)
+            .into_par_iter()
+            .map(|y| {
+                (!cancel.load(Ordering::Relaxed)).then(|| {
+                    let mut row = Vec::with_capacity(self.width * n);
+                    for x in 0..self.width {
+                        let (old_x, old_y) = (x as isize + dx, y as isize + dy);
+                        if (0..width).contains(&old_x) && (0..height).contains(&old_y) {
+                            let i = (old_y * width + old_x) as usize * n;
+                            row.extend_from_slice(&self.samples[i..i + n]);
+                        } else {
+                            row.extend(sample_cells(&sampler, &[(x, y)], self.cells));
+                        }
+                    }
+                    row
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Frame {
+            samples: rows.concat(),
+            cells: self.cells,
+            width: self.width,
+            height: self.height,
+            spacing: self.spacing,
+            max_iterations: self.max_iterations,
+        })
+    }
+
+    fn shader<'a>(&self, style: &'a Style) -> Shader<'a> {
+        Shader::new(
+            style,
+            self.spacing,
+            self.max_iterations,
+            self.samples.iter(),
+        )
+    }
+
+    /// Colors the frame into a string of `width * height` cells.
+    pub fn to_buffer(&self, mode: ColorMode, style: &Style) -> String {
+        self.to_rows(mode, style).concat()
+    }
+
+    /// Colors the frame into a string of `width` cells per row.
+    pub fn to_rows(&self, mode: ColorMode, style: &Style) -> Vec<String> {
+        self.rows_as(self.cells, mode, style)
+    }
+
+    /// Like [`Frame::to_rows`], but draws the cells as `cells` does, which
+    /// must sample them as the frame's own mode does.
+    pub(crate) fn rows_as(&self, cells: CellMode, mode: ColorMode, style: &Style) -> Vec<String> {
+        let shader = self.shader(style);
+        let row_len = self.width * cells.offsets().len();
+
+        self.samples
+            .par_chunks(row_len.max(1))
+            .map(|samples| color_cells(samples, mode, cells, &shader).finish())
+            .collect()
+    }
+
+    /// How many samples the frame has of each cell, and where.
+    pub fn cells(&self) -> CellMode {
+        self.cells
+    }
+
+    /// Colors the cells of a text frame, row-major, for drawing them on
+    /// screen.
+    pub fn to_cells(&self, mode: ColorMode, style: &Style) -> Vec<Cell> {
+        let shader = self.shader(style);
+        let row_len = self.width * self.cells.offsets().len();
+
+        self.samples
+            .par_chunks(row_len.max(1))
+            .flat_map_iter(|samples| color_cells(samples, mode, self.cells, &shader).into_cells())
+            .collect()
+    }
+
+    /// Colors each of the `cells` of a text frame on its own, for redrawing
+    /// them without the rest of the frame. The cells must be in the frame.
+    pub fn cells_at(&self, cells: &[(usize, usize)], mode: ColorMode, style: &Style) -> Vec<Cell> {
+        let shader = self.shader(style);
+        let n = self.cells.offsets().len();
+
+        cells
+            .par_iter()
+            .map(|&(x, y)| {
+                let i = (y * self.width + x) * n;
+                let cell = color_cells(&self.samples[i..i + n], mode, self.cells, &shader);
+                cell.into_cells()[0]
+            })
+            .collect()
+    }
+
+    /// Colors the frame into a row-major RGB image, one pixel per sample.
+    pub fn to_pixels(&self, style: &Style) -> Vec<[u8; 3]> {
+        let shader = self.shader(style);
+
+        self.samples.par_iter().map(|&r| shader.pixel(r)).collect()
+    }
+
+    /// Like [`Frame::to_pixels`], but colors the frame as a part of `whole`,
+    /// ranking values among its samples for histogram coloring.
+    pub fn to_pixels_as(&self, style: &Style, whole: &Frame) -> Vec<[u8; 3]> {
+        let shader = Shader::new(
+            style,
+            self.spacing,
+            self.max_iterations,
+            whole.samples.iter(),
+        );
+
+        self.samples.par_iter().map(|&r| shader.pixel(r)).collect()
+    }
+}
+
+/// Renders `viewport` into a string of `width * height` cells in the given modes.
+pub fn render_to_buffer(
+    viewport: &Viewport,
+    params: &Params,
+    mode: ColorMode,
+    cells: CellMode,
+    style: &Style,
+) -> String {
+    Frame::from_cells(viewport, params, cells).to_buffer(mode, style)
+}
+
+fn sample_pixels(
+    viewport: &Viewport,
+    params: &Params,
+    width: u32,
+    height: u32,
+    step: usize,
+    cancel: &AtomicBool,
+) -> Option<Vec<EscapeResult>> {
+    let (sx, sy) = (
+        viewport.width as f64 / width as f64,
+        viewport.height as f64 / height as f64,
+    );
+
+    let sampler = Sampler::new(viewport, params);
+    let size = (width as usize, height as usize);
+    if step == 1 {
+        return sampler.sample_grid(size, 0, (sx, sy), cancel);
+    }
+    sample_blocks(size, step, cancel, |pixels| {
+        let positions: Vec<(f64, f64)> = pixels
+            .iter()
+            .map(|&(px, py)| ((px as f64 + 0.5) * sx, (py as f64 + 0.5) * sy))
+            .collect();
+        sampler.sample_all(&positions, (sx, sy))
+    })
+}
+
+/// Whether full-resolution frames of `viewport` are iterated on the GPU,
+/// which is quicker than piecing them together from cached tiles.
+pub fn gpu_iterates(viewport: &Viewport, params: &Params) -> bool {
+    #[cfg(feature = "gpu")]
+    {
+        let start = viewport.sample(0., 0.);
+        let spacing = viewport.scale;
+        let direct = matches!(
+            active_precision(viewport, params),
+            Precision::F32 | Precision::F64
+        );
+        direct && viewport.rotation == 0. && gpu::supports(start, (spacing, spacing), params)
+    }
+    #[cfg(not(feature = "gpu"))]
+    {
+        let _ = (viewport, params);
+        false
+    }
+}
+
+/// Computes the escape result of every pixel of a `width * height` image of
+/// `viewport`, row-major.
+pub fn render_iterations(
+    viewport: &Viewport,
+    params: &Params,
+    width: u32,
+    height: u32,
+) -> Vec<EscapeResult> {
+    sample_pixels(viewport, params, width, height, 1, &AtomicBool::new(false)).unwrap()
+}
+
+/// Renders `viewport` into a row-major `width * height` RGB image.
+pub fn render_to_pixels(
+    viewport: &Viewport,
+    params: &Params,
+    style: &Style,
+    width: u32,
+    height: u32,
+) -> Vec<[u8; 3]> {
+    Frame::from_pixels(viewport, params, width, height).to_pixels(style)
+}
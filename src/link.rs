@@ -0,0 +1,275 @@
+use std::{fmt::Write, sync::Arc};
+
+use clap::ValueEnum;
+use mandelbrot::{
+    formula::Formula, palette::Palette, precision, Fractal, Location, Origin, Viewport, C,
+};
+
+use crate::{cli, cli::FractalKind, magnification, Settings};
+
+/// What deep links start with.
+const SCHEME: &str = "mandel:?";
+
+/// A view shared as a deep link such as
+/// `mandel:?re=-0.743&im=0.131&zoom=1e9&iter=5000&frac=burning-ship`.
+#[derive(Clone)]
+pub struct Link {
+    /// Decimal coordinates of the center of the view.
+    pub center: (String, String),
+    /// Magnification over the default view of the fractal.
+    pub zoom: f64,
+    /// Iteration limit, or `None` to follow the magnification.
+    pub iterations: Option<u32>,
+    pub fractal: FractalKind,
+    /// Formula iterated instead of `fractal`.
+    pub formula: Option<Arc<Formula>>,
+    pub julia: Option<C>,
+    pub exponent: Option<f64>,
+    /// Angle the view is turned by, in degrees.
+    pub rotation: f64,
+    /// Palette to color with, which links themselves don't carry.
+    pub palette: Option<Palette>,
+}
+
+/// Whether `s` is a deep link rather than a location of another form.
+pub fn is_link(s: &str) -> bool {
+    s.trim().starts_with(SCHEME)
+}
+
+/// Parses a deep link, of which `re` and `im` are required.
+pub fn parse(s: &str) -> Result<Link, String> {
+    let query = s
+        .trim()
+        .strip_prefix(SCHEME)
+        .ok_or_else(|| format!("expected a link starting with `{}`", SCHEME))?;
+    let (mut re, mut im) = (None, None);
+    let mut link = Link::new(Default::default());
+    let number = |key: &str, value: &str| {
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| format!("`{}` must be a number, got `{}`", key, value))
+    };
+
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = unescape(value)?;
+        match key {
+            "re" => re = Some(value),
+            "im" => im = Some(value),
+            "zoom" => match number(key, &value)? {
+                zoom if zoom > 0. => link.zoom = zoom,
+                _ => return Err("`zoom` must be positive".to_string()),
+            },
+            "iter" => {
+                link.iterations = match value.as_str() {
+                    "auto" => None,
+                    n => Some(
+                        n.parse::<u32>()
+                            .ok()
+                            .filter(|&n| n > 0)
+                            .ok_or_else(|| format!("invalid iteration limit `{}`", n))?,
+                    ),
+                }
+            }
+            "frac" => {
+                link.fractal = FractalKind::from_str(&value, false)
+                    .map_err(|_| format!("unknown fractal `{}`", value))?
+            }
+            "formula" => {
+                let formula =
+                    Formula::parse(&value).map_err(|e| format!("formula `{}`: {}", value, e))?;
+                link.formula = Some(Arc::new(formula));
+            }
+            "julia" => {
+                let (re, im) = value
+                    .split_once(',')
+                    .ok_or_else(|| format!("expected `julia=RE,IM`, got `{}`", value))?;
+                link.julia = Some(C {
+                    re: number(key, re)?,
+                    im: number(key, im)?,
+                });
+            }
+            "exp" => link.exponent = Some(cli::check_exponent(number(key, &value)?)?),
+            "rot" => link.rotation = number(key, &value)?,
+            _ => return Err(format!("unknown link field `{}`", key)),
+        }
+    }
+
+    let (Some(re), Some(im)) = (re, im) else {
+        return Err("links need `re` and `im`".to_string());
+    };
+    if Origin::parse(&re, &im).is_none() {
+        return Err(format!("invalid center {},{}", re, im));
+    }
+    link.center = (re, im);
+    Ok(link)
+}
+
+/// Fraction of a cell the center of links is given to, finer than the
+/// pixels of any export.
+pub const RESOLUTION: f64 = 1e-4;
+
+/// The deep link of the view.
+pub fn encode(viewport: &Viewport, settings: &Settings) -> String {
+    let (re, im) = viewport.center_decimal_to(viewport.scale * RESOLUTION);
+    let params = &settings.params;
+    let mut link = format!(
+        "{}re={}&im={}&zoom={:e}",
+        SCHEME,
+        re,
+        im,
+        magnification(viewport, params)
+    );
+    match settings.auto_iterations {
+        true => link.push_str("&iter=auto"),
+        false => write!(link, "&iter={}", params.max_iterations).unwrap(),
+    }
+    let fractal = match &params.fractal {
+        Fractal::Julia(c) => {
+            write!(link, "&julia={},{}", c.re, c.im).unwrap();
+            FractalKind::Julia
+        }
+        Fractal::BurningShip => FractalKind::BurningShip,
+        Fractal::Tricorn => FractalKind::Tricorn,
+        Fractal::Newton => FractalKind::Newton,
+        Fractal::Formula(formula) => {
+            write!(link, "&formula={}", escape(formula.source())).unwrap();
+            FractalKind::Mandelbrot
+        }
+        _ => FractalKind::Mandelbrot,
+    };
+    if let Some(name) = fractal.to_possible_value() {
+        write!(link, "&frac={}", name.get_name()).unwrap();
+    }
+    if params.exponent != 2. {
+        write!(link, "&exp={}", params.exponent).unwrap();
+    }
+    if viewport.rotation != 0. {
+        write!(link, "&rot={}", viewport.rotation.to_degrees()).unwrap();
+    }
+    link
+}
+
+impl Link {
+    /// The default view of the Mandelbrot set moved to `center`, which must
+    /// be decimal coordinates.
+    pub fn new(center: (String, String)) -> Link {
+        Link {
+            center,
+            zoom: 1.,
+            iterations: None,
+            fractal: FractalKind::Mandelbrot,
+            formula: None,
+            julia: None,
+            exponent: None,
+            rotation: 0.,
+            palette: None,
+        }
+    }
+
+    /// Shows the view the link is of, keeping the settings it doesn't give.
+    pub fn apply(&self, viewport: &mut Viewport, settings: &mut Settings) {
+        if let Some(julia) = self.julia {
+            settings.julia_c = julia;
+        }
+        settings.params.fractal = match (&self.formula, self.fractal) {
+            (Some(formula), _) => Fractal::Formula(formula.clone()),
+            (None, FractalKind::Mandelbrot) => Fractal::Mandelbrot,
+            (None, FractalKind::Julia) => Fractal::Julia(settings.julia_c),
+            (None, FractalKind::BurningShip) => Fractal::BurningShip,
+            (None, FractalKind::Tricorn) => Fractal::Tricorn,
+            (None, FractalKind::Newton) => Fractal::Newton,
+        };
+        if self.formula.is_some() {
+            settings.formula = self.formula.clone();
+        }
+        if let Some(exponent) = self.exponent {
+            settings.params.exponent = exponent;
+        }
+        if let Some(iterations) = self.iterations {
+            settings.params.max_iterations = iterations;
+        }
+        settings.auto_iterations = self.iterations.is_none();
+        if let Some(palette) = &self.palette {
+            let palettes = &mut settings.palettes;
+            settings.palette = match palettes.iter().position(|p| p.name == palette.name) {
+                Some(index) => {
+                    palettes[index] = palette.clone();
+                    index
+                }
+                None => {
+                    palettes.push(palette.clone());
+                    palettes.len() - 1
+                }
+            };
+            settings.style.palette = palette.clone();
+        }
+
+        let (re, im) = &self.center;
+        place(viewport, settings, (re, im), self.zoom).expect("center was parsed");
+        viewport.rotation = self.rotation.to_radians();
+        settings.adapt_iterations(viewport);
+    }
+}
+
+/// Centers the view on decimal coordinates, magnified `zoom` times over the
+/// default view of the fractal, or returns `None` if they don't parse.
+pub fn place(
+    viewport: &mut Viewport,
+    settings: &Settings,
+    (re, im): (&str, &str),
+    zoom: f64,
+) -> Option<()> {
+    // The center becomes the origin, so it keeps all of its digits.
+    let default = settings.params.fractal.default_bounds();
+    let bounds = cli::view_bounds(default, Some((0., 0.)), zoom);
+    let ((_, width), _) = bounds;
+    let origin = Origin::parse_to(re, im, precision(width))?;
+    viewport.set_location(Location::new(origin, bounds, viewport.rotation));
+    Some(())
+}
+
+/// Characters of fields that links hold as they are.
+fn unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~,".contains(c)
+}
+
+/// Percent-encodes the characters of `s` that would end or garble a field.
+fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match unreserved(c) {
+            true => escaped.push(c),
+            false => {
+                let mut bytes = [0; 4];
+                for byte in c.encode_utf8(&mut bytes).bytes() {
+                    write!(escaped, "%{:02X}", byte).unwrap();
+                }
+            }
+        }
+    }
+    escaped
+}
+
+/// Decodes the percent-encoded bytes of a field.
+fn unescape(s: &str) -> Result<String, String> {
+    let mut bytes = Vec::new();
+    let mut rest = s.bytes();
+    while let Some(byte) = rest.next() {
+        match byte {
+            b'%' => {
+                let hex: Vec<u8> = rest.by_ref().take(2).collect();
+                let byte = std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .filter(|_| hex.len() == 2)
+                    .ok_or_else(|| format!("invalid escape in `{}`", s))?;
+                bytes.push(byte);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| format!("invalid text in `{}`", s))
+}
@@ -1,153 +1,2044 @@
-mod complex;
+mod animation;
+mod bookmarks;
+mod canvas;
+mod cli;
+mod command;
+mod config;
+mod crosshair;
+mod error;
+mod help;
+mod jobs;
+mod keymap;
+mod kfr;
+mod link;
+mod minimap;
+mod movie;
+mod orbit;
+mod panel;
+mod par;
+mod prompt;
+mod render;
+mod session;
+mod terminal;
+mod tiled;
+mod timing;
+mod tour;
+mod ui;
+mod upr;
 
-use std::io::{self, Write};
+use std::{
+    env, fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use complex::C;
-use termion::{
-    event::{Event, Key, MouseEvent},
-    input::{MouseTerminal, TermRead},
-    raw::IntoRawMode,
+use animation::{Easing, Morph, Zoom};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bookmarks::Bookmark;
+use canvas::Canvas;
+use clap::Parser;
+use cli::{Args, Command, FractalKind, ImageFormat};
+use config::Config;
+use crosshair::{on_screen, Crosshair};
+use error::Error;
+use image::ImageError;
+use keymap::{Action, Keymap, Pending};
+use mandelbrot::{
+    active_precision, buddhabrot, formula::Formula, grid, history::History, palette::Palette, png,
+    pnm, render_iterations, render_to_pixels, svg, zoom_iterations, Bounds, CellMode, ColorMode,
+    Coloring, Fractal, Frame, Location, Params, Style, Trap, Viewport, C,
 };
+use minimap::Minimap;
+use orbit::Orbit;
+use panel::Panel;
+use ratatui::{buffer::Buffer, layout::Rect, style::Stylize, text::Line, widgets::Paragraph};
+use render::{Picture, Rendered, Renderer};
+use serde::{Deserialize, Serialize};
+use session::Session;
+use terminal::{Event, Events, Key, MouseButton, MouseEvent, Terminal};
+use timing::FrameTimes;
+use tour::{Keyframe, Playback, Step, Tour};
+use ui::{centered, dialog, Ui};
+
+fn render_pixels(
+    viewport: &Viewport,
+    settings: &Settings,
+    width: u32,
+    height: u32,
+) -> Vec<[u8; 3]> {
+    if settings.buddhabrot {
+        let samples = width as u64 * height as u64 * settings.samples;
+        buddhabrot::render_to_pixels(viewport, &settings.params, width, height, samples)
+    } else {
+        render_to_pixels(viewport, &settings.params, &settings.style, width, height)
+    }
+}
+
+/// Path in `dir` of a screenshot of the view, named after where it looks,
+/// how far it is magnified and when it was taken.
+fn screenshot_path(dir: &Path, viewport: &Viewport, settings: &Settings, args: &Args) -> PathBuf {
+    // Deep views have long coordinates, which names can't hold in full.
+    let (re, im) = viewport.center_decimal();
+    let short = |x: String| x.chars().take(40).collect::<String>();
+    let sign = if im.starts_with('-') { "" } else { "+" };
+    let format = args.format.unwrap_or(ImageFormat::Png);
+    dir.join(format!(
+        "mandelbrot_{}{}{}i_{:.3e}x_{}.{}",
+        short(re),
+        sign,
+        short(im),
+        magnification(viewport, &settings.params),
+        utc_timestamp(),
+        format.extension()
+    ))
+}
+
+/// The current time in UTC, as `YYYYMMDD-hhmmss`.
+fn utc_timestamp() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, time) = (seconds / 86400, seconds % 86400);
+    // Civil date of a day count, counting in 400-year eras from March 1st
+    // of year 0 so leap days fall at the end of each year.
+    let days = days as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let m = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * m + 2) / 5 + 1;
+    let month = if m < 10 { m + 3 } else { m - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Opens `path` to write an export to, or stdout if it is `-` and not a
+/// terminal.
+fn create_output(path: &Path) -> Result<Box<dyn Write>, Error> {
+    let error = |e: io::Error| Error::Export(path.to_path_buf(), ImageError::IoError(e));
+    if path != Path::new("-") {
+        return Ok(Box::new(io::BufWriter::new(
+            fs::File::create(path).map_err(error)?,
+        )));
+    }
+    match io::stdout().is_terminal() {
+        true => Err(error(io::Error::other(
+            "not writing an image to a terminal",
+        ))),
+        false => Ok(Box::new(io::stdout().lock())),
+    }
+}
+
+/// Renders the view at `size`, or by default the cells on screen for text,
+/// and writes it to `path`, or to stdout if it is `-`, in the format of
+/// `--format` or the one its extension names.
+fn export_image(
+    viewport: &Viewport,
+    settings: &Settings,
+    path: &Path,
+    size: Option<(u32, u32)>,
+    args: &Args,
+) -> Result<(), Error> {
+    let format = ImageFormat::of(path, args.format);
+    let (width, height) = match (size, format) {
+        (Some(size), _) => size,
+        (None, ImageFormat::Txt) => (viewport.width as u32, viewport.height as u32),
+        (None, _) => cli::DEFAULT_SIZE,
+    };
+    // Text is as many cells as a view has.
+    if matches!(format, ImageFormat::Txt) && width.max(height) > u16::MAX as u32 {
+        return Err(Error::Export(
+            path.to_path_buf(),
+            ImageError::IoError(io::Error::other("text is at most 65535 cells a side")),
+        ));
+    }
+    let pixel_count = width as u64 * height as u64;
+    let banded = matches!(format, ImageFormat::Png | ImageFormat::Ppm);
+    if args.tiled || pixel_count > tiled::MAX_WHOLE_PIXELS && banded {
+        return tiled::export(viewport, settings, path, (width, height), format);
+    }
+    let mut out = create_output(path)?;
+    let pixels = || {
+        let viewport = viewport.with_aspect(width, height);
+        render_pixels(&viewport, settings, width, height)
+    };
+    let results = || {
+        let viewport = viewport.with_aspect(width, height);
+        render_iterations(&viewport, &settings.params, width, height)
+    };
+    let (w, h) = (width as usize, height as usize);
+    let max_iterations = settings.params.max_iterations;
+    let data = match format {
+        ImageFormat::Png => png::encode(w, h, &pixels()),
+        ImageFormat::Ppm => pnm::encode_ppm(w, h, &pixels()),
+        ImageFormat::Pgm => pnm::encode_pgm(w, h, &results(), max_iterations),
+        ImageFormat::Svg => {
+            let levels = svg::levels(max_iterations, args.contours as usize);
+            svg::encode(w, h, &results(), &levels).into_bytes()
+        }
+        ImageFormat::Npy => grid::encode_npy(w, h, &results()),
+        ImageFormat::Raw => grid::encode_raw(w, h, &results(), max_iterations),
+        ImageFormat::Txt => {
+            let mut cells = viewport.clone();
+            cells.resize(width as u16, height as u16);
+            let frame = Frame::from_cells(&cells, &settings.params, settings.cells);
+            let rows = frame.to_rows(ColorMode::Ascii, &settings.style);
+            rows.iter()
+                .flat_map(|row| [row.as_bytes(), b"\n"])
+                .flatten()
+                .copied()
+                .collect()
+        }
+    };
+    out.write_all(&data)
+        .and_then(|()| out.flush())
+        .map_err(|e| Error::Export(path.to_path_buf(), ImageError::IoError(e)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Backend {
+    Text,
+    Sixel,
+    Kitty,
+    Iterm,
+}
+
+#[derive(Debug, Clone)]
+struct Settings {
+    mode: ColorMode,
+    cells: CellMode,
+    style: Style,
+    /// Whether the palette offset is advanced on a timer.
+    cycling: bool,
+    /// Palettes from the command line followed by the built-in ones.
+    palettes: Vec<Palette>,
+    /// Index of the palette in `style`.
+    palette: usize,
+    /// Trap put into `params` while orbit-trap coloring is selected.
+    trap: Trap,
+    backend: Backend,
+    params: Params,
+    /// Whether the iteration limit in `params` follows the magnification of
+    /// the view, rather than being set by hand.
+    auto_iterations: bool,
+    /// Constant used when switching to the Julia set, kept while the
+    /// Mandelbrot set is shown.
+    julia_c: C,
+    /// User-defined formula from the command line, if any.
+    formula: Option<Arc<Formula>>,
+    /// Factor by which the zoom keys magnify the view.
+    zoom_step: f64,
+    /// Pace of transitions, animations and tours.
+    easing: Easing,
+    buddhabrot: bool,
+    /// Buddhabrot starting points per pixel or cell.
+    samples: u64,
+}
+
+const JULIA_STEP: f64 = 0.01;
+/// Degrees each press of the rotation keys turns the view by.
+const ROTATION_STEP: f64 = 15.;
+/// Iteration limit of the intermediate frames of zoom transitions.
+const COARSE_ITERATIONS: u32 = 200;
+
+impl Settings {
+    fn next_fractal(&mut self) -> &Fractal {
+        self.params.fractal = match (&self.params.fractal, &self.formula) {
+            (Fractal::Mandelbrot, _) => Fractal::Julia(self.julia_c),
+            (Fractal::Julia(_), _) => Fractal::BurningShip,
+            (Fractal::BurningShip, _) => Fractal::Tricorn,
+            (Fractal::Tricorn, _) => Fractal::Newton,
+            (Fractal::Newton, Some(formula)) => Fractal::Formula(formula.clone()),
+            (Fractal::Newton, None) | (Fractal::Formula(_) | Fractal::Plugin(_), _) => {
+                Fractal::Mandelbrot
+            }
+        };
+        &self.params.fractal
+    }
+
+    fn next_palette(&mut self) {
+        self.palette = (self.palette + 1) % self.palettes.len();
+        self.style.palette = self.palettes[self.palette].clone();
+    }
+
+    fn next_coloring(&mut self) {
+        self.set_coloring(match self.style.coloring {
+            Coloring::Cyclic => Coloring::Histogram,
+            Coloring::Histogram => Coloring::OrbitTrap,
+            Coloring::OrbitTrap => Coloring::Distance,
+            Coloring::Distance => Coloring::Cyclic,
+        });
+    }
+
+    /// Colors with `coloring`, iterating what it needs besides escape times.
+    fn set_coloring(&mut self, coloring: Coloring) {
+        self.style.coloring = coloring;
+        self.params.trap = (coloring == Coloring::OrbitTrap).then_some(self.trap);
+        self.params.distance_estimate = coloring == Coloring::Distance;
+    }
+
+    /// Copy with a capped iteration limit and no supersampling, for quick
+    /// intermediate frames.
+    fn coarse(&self) -> Settings {
+        let mut coarse = self.clone();
+        coarse.params.max_iterations = coarse.params.max_iterations.min(COARSE_ITERATIONS);
+        coarse.params.supersampling = 1;
+        coarse
+    }
+
+    fn scale_iterations(&mut self, factor: f64) {
+        let iterations = self.params.max_iterations as f64 * factor;
+        self.params.max_iterations = iterations.round().clamp(1., u32::MAX as f64) as u32;
+        self.auto_iterations = false;
+    }
+
+    /// Sets the iteration limit for the magnification of `viewport`, unless
+    /// it was set by hand.
+    fn adapt_iterations(&mut self, viewport: &Viewport) {
+        if self.auto_iterations {
+            self.params.max_iterations = zoom_iterations(magnification(viewport, &self.params));
+        }
+    }
+
+    /// Changes the exponent by `delta`, stepping over 1, unless that takes
+    /// it out of range.
+    fn change_exponent(&mut self, delta: f64) -> Result<(), String> {
+        let d = match self.params.exponent + delta {
+            1. => 1. + delta.signum(),
+            d => d,
+        };
+        self.params.exponent = cli::check_exponent(d)?;
+        Ok(())
+    }
+
+    fn nudge_julia(&mut self, re: f64, im: f64) {
+        self.julia_c = self.julia_c + C::from((re, im));
+        if let Fractal::Julia(_) = self.params.fractal {
+            self.params.fractal = Fractal::Julia(self.julia_c);
+        }
+    }
+}
+
+/// What is drawn around and over the canvas: the status bar, the side
+/// panel if it is shown, and the minimap, the crosshair, traced orbits and
+/// the box being selected if there are any.
+struct Overlays {
+    status: String,
+    /// Message the status bar shows instead until the next input.
+    message: Option<String>,
+    /// How far the render is, once it has run long enough to show it.
+    progress: Option<f64>,
+    /// Cell the pointer was last reported over, which is also where the
+    /// crosshair starts out.
+    pointer: Option<(u16, u16)>,
+    minimap: Option<Minimap>,
+    crosshair: Option<Crosshair>,
+    /// Orbit traced by clicks, which zoom when it is `None`.
+    orbit: Option<Orbit>,
+    /// Corners of the box selected with the right button held.
+    selection: Option<((u16, u16), (u16, u16))>,
+    /// Whether the side panel is shown.
+    panel: bool,
+}
+
+impl Overlays {
+    /// Draws the overlays into `buffer` over the view, the crosshair only
+    /// if the canvas shows `text`.
+    fn render(
+        &mut self,
+        buffer: &mut Buffer,
+        viewport: &Viewport,
+        settings: &Settings,
+        text: bool,
+    ) {
+        if let (Some(crosshair), true) = (&self.crosshair, text) {
+            crosshair.render(viewport, buffer);
+        }
+        if let Some(orbit) = &mut self.orbit {
+            orbit.render(viewport, &settings.params, buffer);
+        }
+        if let Some(minimap) = &mut self.minimap {
+            minimap.render(viewport, settings, buffer);
+        }
+        if let Some((from, to)) = self.selection {
+            render_selection(buffer, viewport, from, to);
+        }
+    }
+
+    /// What the status bar shows: a message, how far the render is, or the
+    /// status of the view.
+    fn status_bar(&self, viewport: &Viewport) -> String {
+        if let Some(message) = &self.message {
+            return message.clone();
+        }
+        if let Some(done) = self.progress {
+            return progress_line(done);
+        }
+        let mut status = self.status.clone();
+        if let Some(readout) = self.crosshair.as_ref().and_then(|c| c.readout(viewport)) {
+            status = format!("{}  {}", readout, status);
+        }
+        if let Some(summary) = self.orbit.as_ref().and_then(Orbit::summary) {
+            status = format!("{}  {}", summary, status);
+        }
+        status
+    }
+}
+
+/// Draws the view: the canvas with the overlays over it, the side panel if
+/// it is shown, and the status bar.
+fn draw(
+    ui: &mut Ui,
+    canvas: &mut Canvas,
+    overlays: &mut Overlays,
+    viewport: &Viewport,
+    settings: &Settings,
+    bookmarks: &[Bookmark],
+    keymap: &Keymap,
+) -> io::Result<()> {
+    if ui.overdrawn() {
+        canvas.redraw();
+    }
+    if let Some(image) = canvas.take_image() {
+        ui.draw_image(image)?;
+    }
+    ui.draw(|frame| {
+        let (view, panel, status) = ui::layout(frame.area(), overlays.panel);
+        frame.render_widget(&*canvas, view);
+        overlays.render(frame.buffer_mut(), viewport, settings, canvas.is_text());
+        if let Some(area) = panel {
+            let panel = Panel {
+                viewport,
+                settings,
+                bookmarks,
+                keymap,
+            };
+            frame.render_widget(panel, area);
+        }
+        frame.render_widget(Paragraph::new(overlays.status_bar(viewport)), status);
+    })
+}
+
+/// How long a render runs before its progress is shown on the status bar.
+const PROGRESS_DELAY: Duration = Duration::from_millis(200);
+
+/// Status bar of a render `done` of the way through.
+fn progress_line(done: f64) -> String {
+    const WIDTH: usize = 20;
+    let filled = ((done * WIDTH as f64) as usize).min(WIDTH);
+    format!(
+        "rendering [{}{}] {:.0}%",
+        "#".repeat(filled),
+        " ".repeat(WIDTH - filled),
+        done * 100.
+    )
+}
+
+/// Magnification of `viewport` over the default view of the fractal, along
+/// the axis it is magnified more on, since the default bounds only fill the
+/// screen along one of them.
+fn magnification(viewport: &Viewport, params: &Params) -> f64 {
+    let ((_, width), (_, height)) = params.fractal.default_bounds();
+    let (w, h) = viewport.extent();
+    (width / w).max(height / h)
+}
+
+/// Describes the view on screen: where it is, its iteration limit, the
+/// precision it is iterated in and how long it took to render, as `timing`
+/// tells.
+fn status_line(viewport: &Viewport, settings: &Settings, timing: &str) -> String {
+    let (re, im) = viewport.center_decimal();
+    let (width, _) = viewport.extent();
+    let zoom = magnification(viewport, &settings.params);
+    let auto = if settings.auto_iterations {
+        " (auto)"
+    } else {
+        ""
+    };
+
+    let precision = active_precision(viewport, &settings.params);
+    let turned = match viewport.rotation {
+        0. => String::new(),
+        angle => format!("  turned {:.0}°", angle.to_degrees()),
+    };
+
+    format!(
+        "center {},{}  width {:.3e}{}  zoom {:.3e}x  {} iterations{}  {}  {}",
+        re, im, width, turned, zoom, settings.params.max_iterations, auto, precision, timing
+    )
+}
+
+/// Where the view is, in a line the go-to prompt reads back.
+fn location_line(viewport: &Viewport, settings: &Settings) -> String {
+    let (re, im) = viewport.center_decimal();
+    let zoom = magnification(viewport, &settings.params);
+    format!(
+        "{},{} @ {:.6e}; {} iterations",
+        re, im, zoom, settings.params.max_iterations
+    )
+}
+
+/// Outlines the box of cells between `from` and `to` into `buffer`, as
+/// much of it as is over the view.
+fn render_selection(buffer: &mut Buffer, viewport: &Viewport, from: (u16, u16), to: (u16, u16)) {
+    let clamp = |(x, y): (u16, u16)| (x.clamp(1, viewport.width), y.clamp(1, viewport.height));
+    let (from, to) = (clamp(from), clamp(to));
+    let (x0, x1) = (from.0.min(to.0), from.0.max(to.0));
+    let (y0, y1) = (from.1.min(to.1), from.1.max(to.1));
+    for x in x0 + 1..x1 {
+        ui::put(buffer, (x, y0), '─');
+        ui::put(buffer, (x, y1), '─');
+    }
+    for y in y0 + 1..y1 {
+        ui::put(buffer, (x0, y), '│');
+        ui::put(buffer, (x1, y), '│');
+    }
+    ui::put(buffer, (x0, y0), '┌');
+    ui::put(buffer, (x1, y0), '┐');
+    ui::put(buffer, (x0, y1), '└');
+    ui::put(buffer, (x1, y1), '┘');
+}
+
+fn supports_truecolor() -> bool {
+    env::var("COLORTERM").is_ok_and(|v| v == "truecolor" || v == "24bit")
+}
+
+fn supports_256_color() -> bool {
+    env::var("COLORTERM").is_ok_and(|v| !v.is_empty())
+        || env::var("TERM").is_ok_and(|v| v.contains("256color"))
+}
+
+fn detect_color_mode() -> ColorMode {
+    if supports_truecolor() {
+        ColorMode::TrueColor
+    } else {
+        ColorMode::Ascii
+    }
+}
+
+fn next_color_mode(mode: ColorMode) -> ColorMode {
+    match mode {
+        ColorMode::Ascii if supports_256_color() => ColorMode::Ansi256,
+        ColorMode::Ascii | ColorMode::Ansi256 if supports_truecolor() => ColorMode::TrueColor,
+        _ => ColorMode::Ascii,
+    }
+}
+
+fn supports_sixel() -> bool {
+    env::var("TERM").is_ok_and(|v| v.starts_with("foot") || v.starts_with("mlterm"))
+}
+
+fn supports_kitty() -> bool {
+    env::var("TERM").is_ok_and(|v| v == "xterm-kitty")
+        || env::var("KITTY_WINDOW_ID").is_ok()
+        || env::var("TERM_PROGRAM").is_ok_and(|v| v == "WezTerm")
+}
+
+fn supports_iterm() -> bool {
+    env::var("TERM_PROGRAM").is_ok_and(|v| v == "iTerm.app")
+}
 
-// (a + ib) * (u + iw)
-// (a * u + i * a * w + i * b * u - b * w)
-// (a * u - b * w) + i (a * w + b * u)
+fn detect_backend() -> Backend {
+    if supports_iterm() {
+        Backend::Iterm
+    } else if supports_kitty() {
+        Backend::Kitty
+    } else if supports_sixel() {
+        Backend::Sixel
+    } else {
+        Backend::Text
+    }
+}
+
+fn next_backend(backend: Backend) -> Backend {
+    match backend {
+        Backend::Text => Backend::Sixel,
+        Backend::Sixel => Backend::Kitty,
+        Backend::Kitty => Backend::Iterm,
+        Backend::Iterm => Backend::Text,
+    }
+}
+
+fn next_cell_mode(cells: CellMode) -> CellMode {
+    match cells {
+        CellMode::Full => CellMode::Background,
+        CellMode::Background => CellMode::HalfBlock,
+        CellMode::HalfBlock => CellMode::Braille,
+        CellMode::Braille => CellMode::Full,
+    }
+}
+
+/// Zoom factor of a single scroll-wheel step, gentler than a click.
+const WHEEL_ZOOM: f64 = 0.8;
 
-fn convert_term_coords(
-    term_x: u16,
-    term_y: u16,
-    term_width: u16,
-    term_height: u16,
-    bounds: ((f64, f64), (f64, f64)),
-) -> (f64, f64) {
-    let (x_min, width) = bounds.0;
-    let (y_min, height) = bounds.1;
+/// Fraction of the view an arrow key pans by.
+const PAN_STEP: f64 = 0.1;
+
+/// How often the terminal size is checked while idle.
+const RESIZE_POLL: Duration = Duration::from_millis(250);
+
+const CYCLE_INTERVAL: Duration = Duration::from_millis(50);
+const CYCLE_STEP: f64 = 0.005;
+
+/// What wakes up the event loop.
+enum Message {
+    Input(io::Result<Event>),
+    Rendered(Rendered),
+    /// An export finished, with what to show about it.
+    Exported(String),
+}
+
+/// Reads terminal `events` on a separate thread, so the event loop can also
+/// wake up on a timer or when a render finishes.
+fn spawn_events(sender: Sender<Message>, events: Events) {
+    thread::spawn(move || {
+        for event in events {
+            if sender.send(Message::Input(event)).is_err() {
+                break;
+            }
+        }
+    });
+}
 
-    let x = (term_x as f64 / term_width as f64) * width + x_min;
-    let y = (term_y as f64 / term_height as f64) * height + y_min;
+/// Waits for the next terminal event, dropping finished renders and the
+/// messages of exports: the view is drawn again once the prompt that called
+/// this is done.
+fn next_input(messages: &Receiver<Message>) -> Option<Event> {
+    loop {
+        match messages.recv().ok()? {
+            Message::Input(event) => return event.ok(),
+            Message::Rendered(_) | Message::Exported(_) => (),
+        }
+    }
+}
 
-    return (x, y);
+/// Exports the view to `path` at `size` on a thread of its own, so the view
+/// stays live meanwhile, and sends back the message to show once it is
+/// done: `done` and the path, or what went wrong.
+fn spawn_export(
+    sender: Sender<Message>,
+    viewport: &Viewport,
+    settings: &Settings,
+    (path, size): (PathBuf, Option<(u32, u32)>),
+    args: &Args,
+    done: &'static str,
+) -> JoinHandle<()> {
+    let (viewport, settings, args) = (viewport.clone(), settings.clone(), args.clone());
+    thread::spawn(move || {
+        let message = match export_image(&viewport, &settings, &path, size, &args) {
+            Ok(()) => format!("{} {}", done, path.display()),
+            Err(e) => e.to_string(),
+        };
+        let _ = sender.send(Message::Exported(message));
+    })
 }
 
-fn draw_buffer(buffer: String) {
-    print!(
-        "{}{}{}",
-        termion::clear::All,
-        termion::cursor::Goto(1, 1),
-        buffer
+/// Prompts for a name and bookmarks the current view under it, returning a
+/// message to show.
+fn save_bookmark(
+    ui: &mut Ui,
+    messages: &Receiver<Message>,
+    viewport: &Viewport,
+    settings: &Settings,
+    bookmarks: &mut Vec<Bookmark>,
+) -> Option<String> {
+    let name = prompt::read_line(ui, || next_input(messages), "bookmark name: ", &[])?;
+    if name.is_empty() {
+        return None;
+    }
+
+    bookmarks::insert(
+        bookmarks,
+        Bookmark::new(
+            name.clone(),
+            viewport.location(),
+            settings.params.max_iterations,
+        ),
     );
+    Some(match bookmarks::save(bookmarks) {
+        Ok(()) => format!("saved bookmark `{}`", name),
+        Err(e) => format!("could not save bookmarks: {}", e),
+    })
+}
+
+/// Prompts for a file, `session::DEFAULT_PATH` if none is typed, or `None`
+/// if cancelled.
+fn session_path(ui: &mut Ui, messages: &Receiver<Message>, label: &str) -> Option<PathBuf> {
+    let path = prompt::read_line(
+        ui,
+        || next_input(messages),
+        &format!("{} [{}]: ", label, session::DEFAULT_PATH),
+        &[],
+    )?;
+    Some(PathBuf::from(match path.trim() {
+        "" => session::DEFAULT_PATH,
+        path => path,
+    }))
 }
 
-fn check_convergence(c: C) -> Option<u16> {
-    let max_iterations: u16 = 900;
-    let mut i = 0;
-    let mut z = C { im: 0., re: 0. };
-    let cutoff = 10.;
+/// Prompts for a file and saves the session to it, returning a message to
+/// show.
+fn save_session(
+    ui: &mut Ui,
+    messages: &Receiver<Message>,
+    viewport: &Viewport,
+    settings: &Settings,
+) -> Option<String> {
+    let path = session_path(ui, messages, "save session to")?;
+    Some(write_session(&path, viewport, settings))
+}
+
+/// Whether `path` is of Ultra Fractal parameters rather than a session.
+fn is_upr(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("upr"))
+}
+
+/// Saves the session to `path`, returning a message to show.
+fn write_session(path: &Path, viewport: &Viewport, settings: &Settings) -> String {
+    if is_upr(path) {
+        return match upr::save(path, viewport, settings) {
+            Ok(()) => format!("saved parameters to {}", path.display()),
+            Err(e) => format!("could not save {}: {}", path.display(), e),
+        };
+    }
+    match session::save(path, &Session::new(viewport, settings)) {
+        Ok(()) => format!("saved session to {}", path.display()),
+        Err(e) => format!("could not save {}: {}", path.display(), e),
+    }
+}
+
+/// Prompts for a file and restores the session saved in it, returning a
+/// message to show.
+fn open_session(
+    ui: &mut Ui,
+    messages: &Receiver<Message>,
+    viewport: &mut Viewport,
+    settings: &mut Settings,
+) -> Option<String> {
+    let path = session_path(ui, messages, "open session")?;
+    Some(read_session(&path, viewport, settings))
+}
 
+/// Restores the session saved in `path`, returning a message to show.
+fn read_session(path: &Path, viewport: &mut Viewport, settings: &mut Settings) -> String {
+    if is_upr(path) {
+        return match upr::load(path) {
+            Ok(link) => {
+                link.apply(viewport, settings);
+                format!("opened parameters {}", path.display())
+            }
+            Err(e) => format!("could not open {}", e),
+        };
+    }
+    let restored = session::load(path)
+        .map_err(|e| e.to_string())
+        .and_then(|session| session.restore(viewport, settings));
+    match restored {
+        Ok(()) => format!("opened session {}", path.display()),
+        Err(e) => format!("could not open {}: {}", path.display(), e),
+    }
+}
+
+/// Lists the bookmarks and prompts for one to open, by name or number.
+fn open_bookmark(
+    ui: &mut Ui,
+    messages: &Receiver<Message>,
+    bookmarks: &[Bookmark],
+    keymap: &Keymap,
+) -> Result<Option<Bookmark>, String> {
+    if bookmarks.is_empty() {
+        let hint = keymap.hint(Action::SaveBookmark, "to add one");
+        return Err(format!("no bookmarks saved yet{}", hint));
+    }
+
+    let listed: Vec<_> = bookmarks
+        .iter()
+        .enumerate()
+        .map(|(i, bookmark)| format!("{:>3} {}", i + 1, bookmark.name))
+        .collect();
+    let Some(key) = prompt::read_line(ui, || next_input(messages), "open bookmark: ", &listed)
+    else {
+        return Ok(None);
+    };
+    match bookmarks::find(bookmarks, key.trim()) {
+        Some(bookmark) => Ok(Some(bookmark.clone())),
+        None => Err(format!("no bookmark `{}`", key.trim())),
+    }
+}
+
+/// What the tour editor was closed to do.
+enum TourEdit {
+    Close,
+    /// Show the keyframe at the index.
+    Show(usize),
+    Play,
+}
+
+/// Lists the keyframes of the tour until Esc closes it, for selecting one
+/// with the arrow keys, moving it earlier or later with K and J, timing it
+/// with e, deleting it with d, showing it with Enter, playing the tour with
+/// p and saving it to `path` with s.
+fn edit_tour(
+    ui: &mut Ui,
+    messages: &Receiver<Message>,
+    keyframes: &mut Vec<Keyframe>,
+    path: &mut PathBuf,
+    keymap: &Keymap,
+) -> Result<TourEdit, String> {
+    if keyframes.is_empty() {
+        let hint = keymap.hint(Action::AddKeyframe, "to add the view as one");
+        return Err(format!("no keyframes yet{}", hint));
+    }
+
+    let base = ui.shown();
+    let mut selected: usize = 0;
+    let mut message: Option<String> = None;
     loop {
-        if z.norm() > cutoff {
-            return Some(i);
+        let total: f64 = keyframes
+            .iter()
+            .enumerate()
+            .map(|(i, k)| k.hold + if i > 0 { k.duration } else { 0. })
+            .sum();
+        let title = format!("tour of {} keyframes, {:.1} s", keyframes.len(), total);
+        let status = message.take().unwrap_or_else(|| {
+            "K/J move, e time, d delete, Enter show, p play, s save, Esc close".to_string()
+        });
+        // A failed draw comes up again at the next one of the event loop.
+        let _ = ui.draw_over(&base, |frame| {
+            // Rows left between the borders and the status line below.
+            let rows = (frame.area().height as usize).saturating_sub(4).max(1);
+            let first = (selected + 1).saturating_sub(rows);
+            let mut lines: Vec<_> = keyframes
+                .iter()
+                .enumerate()
+                .skip(first)
+                .take(rows)
+                .map(|(i, keyframe)| {
+                    let center: String = keyframe.center.chars().take(40).collect();
+                    let line = Line::raw(format!(
+                        "{:>3} {} @ {:.3e}  fly {} s  hold {} s",
+                        i + 1,
+                        center,
+                        keyframe.zoom,
+                        keyframe.duration,
+                        keyframe.hold
+                    ));
+                    match i == selected {
+                        true => line.reversed(),
+                        false => line,
+                    }
+                })
+                .collect();
+            lines.extend([Line::default(), Line::raw(status.as_str())]);
+
+            let width = lines.iter().map(Line::width).max().unwrap_or(0);
+            let width = width.max(title.len()) as u16 + 2;
+            let area = centered(frame.area(), width, lines.len() as u16 + 2);
+            dialog(frame, area, &title, lines);
+        });
+
+        let Some(Event::Key(key)) = next_input(messages) else {
+            continue;
+        };
+        match key {
+            Key::Esc | Key::Char('q') => return Ok(TourEdit::Close),
+            Key::Char('\n') => return Ok(TourEdit::Show(selected)),
+            Key::Char('p') => return Ok(TourEdit::Play),
+            Key::Up | Key::Char('k') => selected = selected.saturating_sub(1),
+            Key::Down | Key::Char('j') => selected = (selected + 1).min(keyframes.len() - 1),
+            Key::Char('K') if selected > 0 => {
+                keyframes.swap(selected, selected - 1);
+                selected -= 1;
+            }
+            Key::Char('J') if selected + 1 < keyframes.len() => {
+                keyframes.swap(selected, selected + 1);
+                selected += 1;
+            }
+            Key::Char('d') | Key::Delete => {
+                keyframes.remove(selected);
+                if keyframes.is_empty() {
+                    return Ok(TourEdit::Close);
+                }
+                selected = selected.min(keyframes.len() - 1);
+            }
+            Key::Char('e') => {
+                let label = format!(
+                    "seconds to fly to keyframe {} and to hold it: ",
+                    selected + 1
+                );
+                let Some(line) = prompt::read_line(ui, || next_input(messages), &label, &[]) else {
+                    continue;
+                };
+                let times: Vec<_> = line.split_whitespace().map(str::parse::<f64>).collect();
+                match times[..] {
+                    [Ok(duration), Ok(hold)] if duration >= 0. && hold >= 0. => {
+                        keyframes[selected].duration = duration;
+                        keyframes[selected].hold = hold;
+                    }
+                    _ => message = Some("expected two numbers of seconds, as FLY HOLD".to_string()),
+                }
+            }
+            Key::Char('s') => {
+                let label = format!("save tour to [{}]: ", path.display());
+                let Some(line) = prompt::read_line(ui, || next_input(messages), &label, &[]) else {
+                    continue;
+                };
+                if !line.trim().is_empty() {
+                    *path = PathBuf::from(line.trim());
+                }
+                message = Some(match tour::save(path, keyframes) {
+                    Ok(()) => format!("saved tour {}", path.display()),
+                    Err(e) => format!("could not save {}: {}", path.display(), e),
+                });
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Prompts for a location to move to, keeping the current magnification
+/// unless a zoom is given and the iteration limit unless one is given.
+fn go_to(
+    ui: &mut Ui,
+    messages: &Receiver<Message>,
+    viewport: &mut Viewport,
+    settings: &mut Settings,
+) -> Result<(), String> {
+    let Some(input) = prompt::read_line(
+        ui,
+        || next_input(messages),
+        "go to (RE IM [@ ZOOM] [; ITERATIONS], or a link): ",
+        &[],
+    ) else {
+        return Ok(());
+    };
+    if link::is_link(&input) {
+        link::parse(&input)?.apply(viewport, settings);
+        return Ok(());
+    }
+    let (center, zoom, iterations) = cli::parse_location(&input)?;
+
+    viewport.set_bounds(location_bounds(viewport, settings, center, zoom));
+    if let Some(iterations) = iterations {
+        settings.params.max_iterations = iterations;
+        settings.auto_iterations = false;
+    }
+    Ok(())
+}
+
+/// Bounds centered on `center`, magnified by `zoom` from the default view
+/// or, if none is given, as much as `viewport` is.
+fn location_bounds(
+    viewport: &Viewport,
+    settings: &Settings,
+    center: (f64, f64),
+    zoom: Option<f64>,
+) -> Bounds {
+    match zoom {
+        Some(zoom) => {
+            cli::view_bounds(settings.params.fractal.default_bounds(), Some(center), zoom)
         }
+        None => cli::view_bounds(viewport.bounds(), Some(center), 1.),
+    }
+}
 
-        if i > max_iterations {
-            return None;
+/// Carries out a command typed at the `:` prompt, returning a message to
+/// show.
+fn execute(
+    command: command::Command,
+    viewport: &mut Viewport,
+    settings: &mut Settings,
+    args: &Args,
+) -> Result<Option<String>, String> {
+    match command {
+        command::Command::GoTo(center, zoom, iterations) => {
+            viewport.set_bounds(location_bounds(viewport, settings, center, zoom));
+            if let Some(iterations) = iterations {
+                settings.params.max_iterations = iterations;
+                settings.auto_iterations = false;
+            }
+        }
+        command::Command::Link(link) => link.apply(viewport, settings),
+        command::Command::Zoom(zoom) => viewport.zoom(1. / zoom),
+        command::Command::Iterations(None) => settings.auto_iterations = true,
+        command::Command::Iterations(Some(iterations)) => {
+            settings.params.max_iterations = iterations;
+            settings.auto_iterations = false;
+        }
+        command::Command::Palette(name) => {
+            let index = match settings.palettes.iter().position(|p| p.name == name) {
+                Some(index) => index,
+                None => {
+                    settings.palettes.push(cli::parse_palette(&name)?);
+                    settings.palettes.len() - 1
+                }
+            };
+            settings.palette = index;
+            settings.style.palette = settings.palettes[index].clone();
+        }
+        command::Command::Coloring(kind) => settings.set_coloring(kind.into()),
+        command::Command::Fractal(kind) => {
+            settings.params.fractal = match kind {
+                FractalKind::Mandelbrot => Fractal::Mandelbrot,
+                FractalKind::Julia => Fractal::Julia(settings.julia_c),
+                FractalKind::BurningShip => Fractal::BurningShip,
+                FractalKind::Tricorn => Fractal::Tricorn,
+                FractalKind::Newton => Fractal::Newton,
+            };
+            viewport.set_bounds(settings.params.fractal.default_bounds());
+        }
+        command::Command::Julia(c) => {
+            settings.julia_c = c;
+            if !matches!(settings.params.fractal, Fractal::Julia(_)) {
+                viewport.set_bounds(Fractal::Julia(c).default_bounds());
+            }
+            settings.params.fractal = Fractal::Julia(c);
         }
+        command::Command::Exponent(exponent) => settings.params.exponent = exponent,
+        command::Command::Export(path, size) => {
+            export_image(viewport, settings, &path, size.or(args.size), args)
+                .map_err(|e| e.to_string())?;
+            return Ok(Some(format!("exported {}", path.display())));
+        }
+        command::Command::SaveSession(path) => {
+            let path = path.unwrap_or_else(|| session::DEFAULT_PATH.into());
+            return Ok(Some(write_session(&path, viewport, settings)));
+        }
+        command::Command::OpenSession(path) => {
+            let path = path.unwrap_or_else(|| session::DEFAULT_PATH.into());
+            return Ok(Some(read_session(&path, viewport, settings)));
+        }
+        // Quitting is up to the event loop.
+        command::Command::Quit => (),
+    }
+    Ok(None)
+}
+
+/// Size of the view in a terminal of `width * height` cells, above the
+/// status bar and beside the side panel if it is shown.
+fn view_size((width, height): (u16, u16), panel: bool) -> (u16, u16) {
+    let (view, _, _) = ui::layout(Rect::new(0, 0, width, height), panel);
+    (view.width, view.height.max(1))
+}
 
-        z = z * z + c;
-        i += 1;
+/// Adopts the current size of `terminal` for the view beside the side panel
+/// if it is shown, keeping the bounds in view, and returns whether it
+/// changed.
+fn resize(terminal: &dyn Terminal, panel: bool, viewport: &mut Viewport) -> bool {
+    match terminal.size().map(|size| view_size(size, panel)) {
+        Ok((width, height))
+            if width > 0 && (width, height) != (viewport.width, viewport.height) =>
+        {
+            viewport.resize(width, height);
+            true
+        }
+        _ => false,
     }
 }
 
-fn push_pixel(convergence_result: Option<u16>, buffer: &mut String) {
-    let c = match convergence_result {
-        None => "@",
-        Some(0..=100) => " ",
-        Some(101..=200) => ".",
-        Some(201..=300) => ":",
-        Some(301..=400) => "-",
-        Some(401..=500) => "=",
-        Some(501..=600) => "+",
-        Some(601..=700) => "*",
-        Some(701..=800) => "#",
-        Some(801..) => "%",
-    };
+/// Pixel size of the part of `terminal` the view takes up, if it tells its
+/// size in pixels.
+fn image_size(terminal: &dyn Terminal, viewport: &Viewport) -> Option<(u32, u32)> {
+    let (width, height) = terminal.size_pixels()?;
+    let (columns, rows) = terminal.size().ok()?;
+    Some((
+        width as u32 * viewport.width as u32 / columns.max(1) as u32,
+        height as u32 * viewport.height as u32 / rows.max(1) as u32,
+    ))
+}
+
+/// Whole cells, about a tenth of the view, that an arrow key pans by.
+fn pan_shift(action: Action, viewport: &Viewport) -> (isize, isize) {
+    let step = |cells: u16| (cells as f64 * PAN_STEP).round().max(1.) as isize;
+    let (x, y) = (step(viewport.width), step(viewport.height));
 
-    buffer.push_str(c)
+    match action {
+        Action::PanRight => (x, 0),
+        Action::PanLeft => (-x, 0),
+        Action::PanDown => (0, y),
+        Action::PanUp => (0, -y),
+        _ => (0, 0),
+    }
 }
 
-fn draw_mandelbrot(term_width: u16, term_height: u16, bounds: ((f64, f64), (f64, f64))) {
-    let mut buffer = String::new();
+/// Starts a transition from the `shown` bounds to those of `viewport`, or
+/// renders it right away if its frames are too slow to animate.
+fn animate_zoom(
+    animation: &mut Option<Zoom>,
+    renderer: &mut Renderer,
+    shown: Location,
+    viewport: &Viewport,
+    settings: &mut Settings,
+) {
+    settings.adapt_iterations(viewport);
+    *animation =
+        (!settings.buddhabrot).then(|| Zoom::new(shown, viewport.location(), settings.easing));
+    next_frame(animation, renderer, viewport, settings);
+}
 
-    for term_y in 0..term_height {
-        for term_x in 0..term_width {
-            let (x, y) = convert_term_coords(term_x, term_y, term_width, term_height, bounds);
-            let convergence_result = check_convergence(C::from((x, y)));
-            push_pixel(convergence_result, &mut buffer);
+/// Starts rendering the next frame of a running transition, ending it with
+/// a full render of `viewport`.
+fn next_frame(
+    animation: &mut Option<Zoom>,
+    renderer: &mut Renderer,
+    viewport: &Viewport,
+    settings: &Settings,
+) {
+    match animation {
+        Some(zoom) if !zoom.done() => {
+            let mut shown = viewport.clone();
+            shown.set_location(zoom.location());
+            renderer.start_once(&shown, &settings.coarse());
+        }
+        _ => {
+            *animation = None;
+            renderer.start(viewport, settings);
         }
     }
+}
 
-    draw_buffer(buffer);
+/// Shows the step of a tour due now, ending the tour once it is done.
+fn play(
+    playback: &mut Option<Playback>,
+    renderer: &mut Renderer,
+    viewport: &mut Viewport,
+    settings: &mut Settings,
+) {
+    let Some(tour) = playback else {
+        return;
+    };
+    match tour.step() {
+        Step::Fly(location) => {
+            viewport.set_location(location);
+            settings.adapt_iterations(viewport);
+            renderer.start_once(viewport, &settings.coarse());
+        }
+        Step::Arrive(location) => {
+            viewport.set_location(location);
+            settings.adapt_iterations(viewport);
+            renderer.start(viewport, settings);
+        }
+        Step::Hold => (),
+        Step::Done => *playback = None,
+    }
 }
 
-fn scale_origin(f: f64, x: f64, x0: f64) -> f64 {
-    x - f * x + f * x0
+/// Renders the Julia set of the constant a sweep is at now.
+fn sweep(
+    morph: &Option<Morph>,
+    renderer: &mut Renderer,
+    viewport: &Viewport,
+    settings: &mut Settings,
+) {
+    let Some(morph) = morph else {
+        return;
+    };
+    settings.julia_c = morph.constant();
+    settings.params.fractal = Fractal::Julia(settings.julia_c);
+    renderer.start_once(viewport, settings);
 }
 
-fn scale_bounds(
-    f: f64,
-    term_x: u16,
-    term_y: u16,
-    term_height: u16,
-    term_width: u16,
-    bounds: ((f64, f64), (f64, f64)),
-) -> ((f64, f64), (f64, f64)) {
-    let (x, y) = convert_term_coords(term_x, term_y, term_width, term_height, bounds);
-    let (x0_new, y0_new) = (
-        scale_origin(f, x, bounds.0 .0),
-        scale_origin(f, y, bounds.1 .0),
-    );
+#[allow(clippy::too_many_arguments)]
+fn handle_mouse_events(
+    ui: &mut Ui,
+    mut viewport: Viewport,
+    mut settings: Settings,
+    args: &Args,
+    config: &Config,
+    mut playback: Option<Playback>,
+    mut keyframes: Vec<Keyframe>,
+    mut morph: Option<Morph>,
+) -> Result<(), Error> {
+    ui.terminal().enter()?;
+    let (sender, messages) = mpsc::channel();
+    spawn_events(sender.clone(), ui.terminal().events());
+    let (exports, mut exporting) = (sender.clone(), Vec::new());
+    let mut renderer = Renderer::new(sender, args.interactive().cache_size << 20);
+    renderer.image_size = image_size(ui.terminal(), &viewport);
+    match (&playback, &morph) {
+        (Some(_), _) => play(&mut playback, &mut renderer, &mut viewport, &mut settings),
+        (_, Some(_)) => sweep(&morph, &mut renderer, &viewport, &mut settings),
+        (None, None) => renderer.start(&viewport, &settings),
+    }
+    // Escape results on screen, for recoloring without another render.
+    let mut frame = None;
+    let mut canvas = Canvas::new();
+    let mut overlays = Overlays {
+        status: String::new(),
+        message: None,
+        progress: None,
+        pointer: None,
+        minimap: None,
+        crosshair: None,
+        orbit: None,
+        selection: None,
+        panel: false,
+    };
+    // Button held down and the cell where it went down, until it is released.
+    let mut drag_start = None;
+    let mut history = History::new(viewport.location());
+    // Transition from the view on screen to `viewport`, if one is running.
+    let mut animation: Option<Zoom> = None;
+    // Mandelbrot view a Julia set was opened from, to go back to.
+    let mut julia_from: Option<Location> = None;
+    // Count and keys typed towards an action.
+    let mut keys = Pending::default();
+    let (mut bookmarks, mut message) = match bookmarks::load() {
+        Ok(bookmarks) => (bookmarks, None),
+        Err(e) => (Vec::new(), Some(format!("could not load bookmarks: {}", e))),
+    };
+    // Saving over a file that failed to load would lose its bookmarks.
+    let bookmarks_loaded = message.is_none();
+    let mut times = FrameTimes::new();
+    if let Some(path) = &args.interactive().frame_log {
+        if let Err(e) = times.log_to(path) {
+            message = Some(format!("could not open {}: {}", path.display(), e));
+        }
+    }
+    if playback.is_some() {
+        message = Some("playing the tour, press any key to take over".to_string());
+    } else if morph.is_some() {
+        message = Some("sweeping the Julia constant, press any key to take over".to_string());
+    }
+    let mut tour_path = match &args.command {
+        Some(Command::Tour { file, .. }) => file.clone(),
+        _ => PathBuf::from(tour::DEFAULT_PATH),
+    };
+    loop {
+        if let Some(message) = message.take() {
+            overlays.message = Some(message);
+            draw(
+                ui,
+                &mut canvas,
+                &mut overlays,
+                &viewport,
+                &settings,
+                &bookmarks,
+                &config.keymap,
+            )?;
+        }
+
+        let cycling = settings.cycling && frame.is_some() && !renderer.busy;
+        let timeout = match cycling || playback.is_some() || morph.is_some() {
+            true => CYCLE_INTERVAL,
+            false => RESIZE_POLL,
+        };
+        let evt = match messages.recv_timeout(timeout) {
+            Ok(Message::Input(evt)) => evt?,
+            Ok(Message::Exported(exported)) => {
+                message = Some(exported);
+                continue;
+            }
+            Ok(Message::Rendered(rendered)) => {
+                if renderer.accept(&rendered) {
+                    match rendered.progress {
+                        Some(done) => {
+                            if let (Some(row), Some(part)) = (rendered.band, &rendered.frame) {
+                                canvas.show_band(part, row as usize, &settings);
+                            }
+                            if rendered.elapsed > PROGRESS_DELAY {
+                                overlays.progress = Some(done);
+                            }
+                        }
+                        None => {
+                            let drawing = Instant::now();
+                            match rendered.picture {
+                                Picture::Image(image) => canvas.show_image(image),
+                                Picture::Cells(cells) => {
+                                    canvas.show_cells(cells, viewport.width as usize)
+                                }
+                                Picture::None => {
+                                    if let Some(frame) = &rendered.frame {
+                                        canvas.show(frame, &settings);
+                                    }
+                                }
+                            }
+                            overlays.progress = None;
+                            draw(
+                                ui,
+                                &mut canvas,
+                                &mut overlays,
+                                &viewport,
+                                &settings,
+                                &bookmarks,
+                                &config.keymap,
+                            )?;
+                            let timing = match rendered.last {
+                                true => {
+                                    if let Err(e) =
+                                        times.record(rendered.elapsed, drawing.elapsed())
+                                    {
+                                        message = Some(format!("could not log frame times: {}", e));
+                                    }
+                                    times.summary()
+                                }
+                                false => format!("{} ms", rendered.elapsed.as_millis()),
+                            };
+                            overlays.status = status_line(&viewport, &settings, &timing);
+                            frame = rendered.frame;
+                        }
+                    }
+                    if rendered.last && animation.is_some() {
+                        next_frame(&mut animation, &mut renderer, &viewport, &settings);
+                    } else if rendered.last {
+                        play(&mut playback, &mut renderer, &mut viewport, &mut settings);
+                        sweep(&morph, &mut renderer, &viewport, &mut settings);
+                    }
+                    draw(
+                        ui,
+                        &mut canvas,
+                        &mut overlays,
+                        &viewport,
+                        &settings,
+                        &bookmarks,
+                        &config.keymap,
+                    )?;
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if resize(ui.terminal(), overlays.panel, &mut viewport) {
+                    renderer.image_size = image_size(ui.terminal(), &viewport);
+                    animation = None;
+                    renderer.start(&viewport, &settings);
+                } else if (playback.is_some() || morph.is_some()) && !renderer.busy {
+                    play(&mut playback, &mut renderer, &mut viewport, &mut settings);
+                    sweep(&morph, &mut renderer, &viewport, &mut settings);
+                } else if cycling {
+                    settings.style.offset = (settings.style.offset + CYCLE_STEP).fract();
+                    if let Some(frame) = &frame {
+                        canvas.recolor(frame, &settings);
+                    }
+                    draw(
+                        ui,
+                        &mut canvas,
+                        &mut overlays,
+                        &viewport,
+                        &settings,
+                        &bookmarks,
+                        &config.keymap,
+                    )?;
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if let Event::Mouse(MouseEvent::Moved(term_x, term_y)) = evt {
+            overlays.pointer = Some((term_x, term_y));
+            if let Some(crosshair) = &mut overlays.crosshair {
+                crosshair.pointer = overlays.pointer;
+                draw(
+                    ui,
+                    &mut canvas,
+                    &mut overlays,
+                    &viewport,
+                    &settings,
+                    &bookmarks,
+                    &config.keymap,
+                )?;
+            }
+            continue;
+        }
+        // Clicks on the side panel or the status bar miss the view.
+        if let Event::Mouse(MouseEvent::Press(_, term_x, term_y)) = evt {
+            if !on_screen(&viewport, (term_x, term_y)) {
+                continue;
+            }
+        }
+        if matches!(evt, Event::Key(_) | Event::Mouse(MouseEvent::Press(..))) {
+            overlays.message = None;
+        }
+        if let Event::Mouse(
+            MouseEvent::Press(_, term_x, term_y)
+            | MouseEvent::Hold(term_x, term_y)
+            | MouseEvent::Release(term_x, term_y),
+        ) = evt
+        {
+            overlays.pointer = Some((term_x, term_y));
+        }
+        let (action, count) = match evt {
+            Event::Key(key) => match keys.push(key, &config.keymap) {
+                Some((action, count)) => (Some(action), count),
+                None => continue,
+            },
+            _ => (None, 1),
+        };
+        // Input takes the view over from a tour or sweep.
+        if playback.take().is_some() | morph.take().is_some() {
+            renderer.start(&viewport, &settings);
+        }
+        match (action, evt) {
+            (Some(Action::Quit), _) => break,
+            (Some(Action::Reset), _) => {
+                let shown = animation
+                    .as_ref()
+                    .map_or_else(|| viewport.location(), Zoom::location);
+
+                viewport.set_bounds(settings.params.fractal.default_bounds());
+                settings.auto_iterations = config.iterations.is_none();
+                if let Some(iterations) = config.iterations {
+                    settings.params.max_iterations = iterations;
+                }
 
-    ((x0_new, f * bounds.0 .1), (y0_new, f * bounds.1 .1))
-}
-
-fn handle_mouse_events(term_height: u16, term_width: u16, mut bounds: ((f64, f64), (f64, f64))) {
-    let stdin = io::stdin();
-    let mut stdout = MouseTerminal::from(io::stdout().into_raw_mode().unwrap());
-    for c in stdin.events() {
-        let evt = c.unwrap();
-        match evt {
-            Event::Key(Key::Char('q')) => break,
-            Event::Key(k) => {
-                match k {
-                    Key::Right => bounds.0 .0 += bounds.0 .1 * 0.1,
-                    Key::Left => bounds.0 .0 -= bounds.0 .1 * 0.1,
-                    Key::Down => bounds.1 .0 += bounds.1 .1 * 0.1,
-                    Key::Up => bounds.1 .0 -= bounds.1 .1 * 0.1,
+                animate_zoom(
+                    &mut animation,
+                    &mut renderer,
+                    shown,
+                    &viewport,
+                    &mut settings,
+                );
+            }
+            (Some(action @ (Action::ZoomIn | Action::ZoomOut)), _) => {
+                let f = match action {
+                    Action::ZoomOut => settings.zoom_step,
+                    _ => 1. / settings.zoom_step,
+                };
+                let f = f.powi(count as i32);
+                let shown = animation
+                    .as_ref()
+                    .map_or_else(|| viewport.location(), Zoom::location);
+
+                viewport.zoom(f);
+
+                animate_zoom(
+                    &mut animation,
+                    &mut renderer,
+                    shown,
+                    &viewport,
+                    &mut settings,
+                );
+            }
+            (
+                Some(
+                    action @ (Action::PanLeft | Action::PanRight | Action::PanUp | Action::PanDown),
+                ),
+                _,
+            ) => {
+                let shift = pan_shift(action, &viewport);
+                let shift = (shift.0 * count as isize, shift.1 * count as isize);
+                viewport.pan(
+                    shift.0 as f64 / viewport.width as f64,
+                    shift.1 as f64 / viewport.height as f64,
+                );
+
+                // A finished text frame of the previous view only needs the
+                // strip of cells coming into view.
+                let finished = !renderer.busy && animation.is_none();
+                match frame.clone() {
+                    Some(frame) if finished && settings.backend == Backend::Text => {
+                        renderer.start_shifted(&viewport, &settings, frame, shift)
+                    }
+                    _ => renderer.start(&viewport, &settings),
+                }
+                animation = None;
+            }
+            (Some(action), _) => {
+                // Counts repeat the actions it makes sense to.
+                let step = JULIA_STEP * count as f64;
+                match action {
+                    Action::Undo => {
+                        if let Some(location) = (0..count).filter_map(|_| history.undo()).last() {
+                            viewport.set_location(location)
+                        }
+                    }
+                    Action::Redo => {
+                        if let Some(location) = (0..count).filter_map(|_| history.redo()).last() {
+                            viewport.set_location(location)
+                        }
+                    }
+                    Action::SaveBookmark if bookmarks_loaded => {
+                        message = save_bookmark(ui, &messages, &viewport, &settings, &mut bookmarks)
+                    }
+                    Action::SaveBookmark => {
+                        message = Some("not saving over unreadable bookmarks".to_string())
+                    }
+                    Action::OpenBookmark => {
+                        match open_bookmark(ui, &messages, &bookmarks, &config.keymap) {
+                            Ok(Some(bookmark)) => match bookmark.location() {
+                                Some(location) => {
+                                    viewport.set_location(location);
+                                    settings.params.max_iterations = bookmark.iterations;
+                                    settings.auto_iterations = false;
+                                }
+                                None => {
+                                    message = Some("bookmark has a malformed origin".to_string())
+                                }
+                            },
+                            Ok(None) => (),
+                            Err(e) => message = Some(e),
+                        }
+                    }
+                    Action::AddKeyframe => {
+                        keyframes.push(Keyframe::of(&viewport, &settings));
+                        message = Some(format!("added keyframe {}", keyframes.len()));
+                    }
+                    Action::EditTour => {
+                        match edit_tour(
+                            ui,
+                            &messages,
+                            &mut keyframes,
+                            &mut tour_path,
+                            &config.keymap,
+                        ) {
+                            Ok(TourEdit::Close) => (),
+                            Ok(TourEdit::Show(i)) => {
+                                match keyframes[i].location(&viewport, &settings) {
+                                    Ok(location) => viewport.set_location(location),
+                                    Err(e) => message = Some(e),
+                                }
+                            }
+                            Ok(TourEdit::Play) => {
+                                match Tour::new(keyframes.clone(), &viewport, &settings) {
+                                    Ok(tour) => playback = Some(Playback::new(tour, false)),
+                                    Err(e) => message = Some(e),
+                                }
+                            }
+                            Err(e) => message = Some(e),
+                        }
+                    }
+                    Action::SaveSession => {
+                        message = save_session(ui, &messages, &viewport, &settings)
+                    }
+                    Action::OpenSession => {
+                        message = open_session(ui, &messages, &mut viewport, &mut settings)
+                    }
+                    Action::Command => {
+                        let line = prompt::read_line(ui, || next_input(&messages), ":", &[]);
+                        match line.as_deref().map(str::trim) {
+                            None | Some("") => (),
+                            Some(line) => match command::parse(line) {
+                                Ok(command::Command::Quit) => break,
+                                Ok(command) => {
+                                    message = execute(command, &mut viewport, &mut settings, args)
+                                        .unwrap_or_else(Some)
+                                }
+                                Err(e) => message = Some(e),
+                            },
+                        }
+                    }
+                    Action::GoTo => {
+                        if let Err(e) = go_to(ui, &messages, &mut viewport, &mut settings) {
+                            message = Some(e);
+                        }
+                    }
+                    Action::CopyLocation | Action::CopyLink => {
+                        let line = match action {
+                            Action::CopyLink => link::encode(&viewport, &settings),
+                            _ => location_line(&viewport, &settings),
+                        };
+                        // OSC 52 has the terminal put the text on the clipboard.
+                        let copy = format!("\x1b]52;c;{}\x07", STANDARD.encode(&line));
+                        ui.terminal().write_raw(&copy);
+                        message = Some(format!("copied {}", line));
+                    }
+                    // Any key dismisses the help.
+                    Action::Help => {
+                        let base = ui.shown();
+                        ui.draw_over(&base, |frame| help::draw(frame, &config.keymap))?;
+                        next_input(&messages);
+                    }
+                    Action::ToggleMinimap => {
+                        overlays.minimap = match overlays.minimap {
+                            Some(_) => None,
+                            None => Some(Minimap::new()),
+                        }
+                    }
+                    Action::ToggleCrosshair => {
+                        overlays.crosshair = match overlays.crosshair {
+                            Some(_) => None,
+                            None => Some(Crosshair::new(overlays.pointer)),
+                        }
+                    }
+                    Action::ToggleOrbit => {
+                        overlays.orbit = match overlays.orbit {
+                            Some(_) => None,
+                            None => Some(Orbit::new()),
+                        }
+                    }
+                    Action::TogglePanel => {
+                        overlays.panel = !overlays.panel;
+                        if resize(ui.terminal(), overlays.panel, &mut viewport) {
+                            renderer.image_size = image_size(ui.terminal(), &viewport);
+                        }
+                    }
+                    Action::ToggleColor => settings.mode = next_color_mode(settings.mode),
+                    Action::NextCellMode => settings.cells = next_cell_mode(settings.cells),
+                    Action::NextColoring => (0..count).for_each(|_| settings.next_coloring()),
+                    Action::NextPalette => (0..count).for_each(|_| settings.next_palette()),
+                    Action::ToggleCycling => settings.cycling = !settings.cycling,
+                    Action::NextBackend => settings.backend = next_backend(settings.backend),
+                    Action::Export => {
+                        let path = args.output();
+                        message = Some(format!("exporting {}", path.display()));
+                        let export = (path, args.size);
+                        let sender = exports.clone();
+                        exporting.push(spawn_export(
+                            sender, &viewport, &settings, export, args, "exported",
+                        ));
+                    }
+                    Action::Screenshot => {
+                        let path =
+                            screenshot_path(&config.screenshot_dir, &viewport, &settings, args);
+                        message = Some(format!("saving {}", path.display()));
+                        let export = (path, args.size);
+                        let sender = exports.clone();
+                        exporting.push(spawn_export(
+                            sender, &viewport, &settings, export, args, "saved",
+                        ));
+                    }
+                    Action::ToggleBuddhabrot => settings.buddhabrot = !settings.buddhabrot,
+                    Action::NextFractal => {
+                        for _ in 1..count {
+                            settings.next_fractal();
+                        }
+                        viewport.set_bounds(settings.next_fractal().default_bounds())
+                    }
+                    // The point under the pointer, or else at the center.
+                    Action::ToggleJulia => match &settings.params.fractal {
+                        Fractal::Mandelbrot => {
+                            let at = overlays.pointer.filter(|&at| on_screen(&viewport, at));
+                            let point = match at {
+                                Some((term_x, term_y)) => viewport.to_complex(term_x, term_y),
+                                None => viewport.center(),
+                            };
+                            julia_from = Some(viewport.location());
+                            settings.julia_c = C::from(point);
+                            settings.params.fractal = Fractal::Julia(settings.julia_c);
+                            viewport.set_bounds(settings.params.fractal.default_bounds());
+                            viewport.rotation = 0.;
+                            message = Some(format!("Julia set of {}", settings.julia_c));
+                        }
+                        Fractal::Julia(_) => {
+                            settings.params.fractal = Fractal::Mandelbrot;
+                            match julia_from.take() {
+                                Some(location) => viewport.set_location(location),
+                                None => viewport.set_bounds(Fractal::Mandelbrot.default_bounds()),
+                            }
+                        }
+                        _ => message = Some("only the Mandelbrot set opens Julia sets".to_string()),
+                    },
+                    Action::HalveIterations => settings.scale_iterations(0.5f64.powi(count as i32)),
+                    Action::DoubleIterations => settings.scale_iterations(2f64.powi(count as i32)),
+                    Action::ToggleAutoIterations => {
+                        settings.auto_iterations = !settings.auto_iterations
+                    }
+                    Action::LowerExponent | Action::RaiseExponent => {
+                        let delta = match action {
+                            Action::LowerExponent => -(count as f64),
+                            _ => count as f64,
+                        };
+                        if let Err(e) = settings.change_exponent(delta) {
+                            message = Some(e);
+                        }
+                    }
+                    Action::RotateLeft | Action::RotateRight => {
+                        let sign = if action == Action::RotateLeft {
+                            1.
+                        } else {
+                            -1.
+                        };
+                        let degrees =
+                            viewport.rotation.to_degrees() + sign * ROTATION_STEP * count as f64;
+                        // Rounded so that turning all the way around comes
+                        // back to straight.
+                        let degrees = (degrees.rem_euclid(360.) * 1e6).round() / 1e6 % 360.;
+                        viewport.rotation = degrees.to_radians();
+                    }
+                    Action::ResetRotation => viewport.rotation = 0.,
+                    Action::JuliaLeft => settings.nudge_julia(-step, 0.),
+                    Action::JuliaRight => settings.nudge_julia(step, 0.),
+                    Action::JuliaUp => settings.nudge_julia(0., -step),
+                    Action::JuliaDown => settings.nudge_julia(0., step),
                     _ => (),
                 };
-                draw_mandelbrot(term_width, term_height, bounds)
+                animation = None;
+                settings.adapt_iterations(&viewport);
+                renderer.start(&viewport, &settings);
             }
-            Event::Mouse(MouseEvent::Press(button, term_x, term_y)) => {
+            (
+                _,
+                Event::Mouse(MouseEvent::Press(
+                    button @ (MouseButton::Left | MouseButton::Right),
+                    term_x,
+                    term_y,
+                )),
+            ) => drag_start = Some((button, (term_x, term_y))),
+            (_, Event::Mouse(MouseEvent::Hold(term_x, term_y))) => {
+                // Drawing images again on every move is too slow, so the
+                // outlines left on them are only cleared on release.
+                if let Some((MouseButton::Right, start)) = drag_start {
+                    overlays.selection = Some((start, (term_x, term_y)));
+                }
+            }
+            (_, Event::Mouse(MouseEvent::Release(term_x, term_y))) => {
+                let end = (term_x, term_y);
+                overlays.selection = None;
+                let shown = animation
+                    .as_ref()
+                    .map_or_else(|| viewport.location(), Zoom::location);
+                let zoomed = match drag_start.take() {
+                    Some((MouseButton::Left, start)) if start != end => {
+                        viewport.drag(start, end);
+                        false
+                    }
+                    // A press and release on the same cell is a click.
+                    Some((MouseButton::Left, _)) if overlays.orbit.is_some() => {
+                        if let Some(orbit) = &mut overlays.orbit {
+                            orbit.trace(&viewport, end, &settings.params);
+                        }
+                        // Images are drawn again over the last orbit's markers.
+                        canvas.redraw();
+                        draw(
+                            ui,
+                            &mut canvas,
+                            &mut overlays,
+                            &viewport,
+                            &settings,
+                            &bookmarks,
+                            &config.keymap,
+                        )?;
+                        continue;
+                    }
+                    Some((MouseButton::Left, _)) => {
+                        viewport.scale(0.5, term_x, term_y);
+                        true
+                    }
+                    Some((_, start)) if start == end => {
+                        viewport.scale(1.5, term_x, term_y);
+                        true
+                    }
+                    Some((_, start)) => {
+                        viewport.zoom_to(start, end);
+                        true
+                    }
+                    None => continue,
+                };
+
+                if zoomed {
+                    animate_zoom(
+                        &mut animation,
+                        &mut renderer,
+                        shown,
+                        &viewport,
+                        &mut settings,
+                    );
+                } else {
+                    animation = None;
+                    renderer.start(&viewport, &settings);
+                }
+            }
+            (_, Event::Mouse(MouseEvent::Press(button, term_x, term_y))) => {
                 let f = match button {
-                    termion::event::MouseButton::Left => 0.5,
+                    MouseButton::WheelUp => WHEEL_ZOOM,
+                    MouseButton::WheelDown => 1. / WHEEL_ZOOM,
                     _ => 1.5,
                 };
+                let shown = animation
+                    .as_ref()
+                    .map_or_else(|| viewport.location(), Zoom::location);
 
-                bounds = scale_bounds(f, term_x, term_y, term_height, term_width, bounds);
+                viewport.scale(f, term_x, term_y);
 
-                draw_mandelbrot(term_width, term_height, bounds)
+                animate_zoom(
+                    &mut animation,
+                    &mut renderer,
+                    shown,
+                    &viewport,
+                    &mut settings,
+                );
             }
             _ => (),
         }
-        stdout.flush().unwrap();
+        history.visit(viewport.location());
+        draw(
+            ui,
+            &mut canvas,
+            &mut overlays,
+            &viewport,
+            &settings,
+            &bookmarks,
+            &config.keymap,
+        )?;
     }
+    ui.terminal().leave()?;
+    // Exports still running are finished rather than cut off.
+    for export in exporting {
+        let _ = export.join();
+    }
+    Ok(())
 }
 
-fn main() {
-    let (term_width, term_height) = termion::terminal_size().unwrap();
-    let bounds = ((-3f64, 4f64), (-2f64, 4f64));
+fn palettes(config: &Config) -> Vec<Palette> {
+    let mut palettes = config.palettes.clone();
+    for builtin in Palette::builtins() {
+        if !palettes.iter().any(|p| p.name == builtin.name) {
+            palettes.push(builtin);
+        }
+    }
+    palettes
+}
 
-    draw_mandelbrot(term_width, term_height, bounds);
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("mandelbrot: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Restores the session `--session` names, if any, then opens the view
+/// given.
+fn restore_session(
+    args: &Args,
+    viewport: &mut Viewport,
+    settings: &mut Settings,
+) -> Result<(), Error> {
+    viewport.rotation = args.rotation.to_radians();
+    if let Some(path) = &args.session {
+        session::load(path)
+            .map_err(|e| e.to_string())
+            .and_then(|session| session.restore(viewport, settings))
+            .map_err(|e| Error::Session(path.clone(), e))?;
+    }
+    if let Some(link) = args.view() {
+        link.apply(viewport, settings);
+    }
+    Ok(())
+}
+
+/// Renders `frames` into numbered PNG files in `frame_dir` if given, or else
+/// into the output file as an animated GIF or a video encoded by `ffmpeg`.
+fn export_animation(
+    viewport: &Viewport,
+    settings: &Settings,
+    args: &Args,
+    frames: &[movie::Shot],
+    fps: u32,
+    frame_dir: Option<&Path>,
+    ffmpeg: &Path,
+) -> Result<(), Error> {
+    let size = args.size();
+    match frame_dir {
+        Some(dir) => movie::export_frames(viewport, settings, dir, size, frames),
+        None if args.is_gif() => {
+            movie::export_gif(viewport, settings, &args.output(), size, frames, fps)
+        }
+        None => movie::export_video(
+            viewport,
+            settings,
+            &args.output(),
+            size,
+            frames,
+            fps,
+            ffmpeg,
+        ),
+    }
+}
+
+/// Renders the view `runs` times at `width * height` pixels after a run to
+/// warm up, printing how long each took and how quickly they went overall.
+fn bench(viewport: &Viewport, settings: &Settings, (width, height): (u32, u32), runs: u32) {
+    let viewport = viewport.with_aspect(width, height);
+    let time = || {
+        let start = Instant::now();
+        render_pixels(&viewport, settings, width, height);
+        start.elapsed()
+    };
+    time();
+
+    let ms = |d: Duration| d.as_secs_f64() * 1e3;
+    let mut times = Vec::new();
+    for run in 1..=runs {
+        let elapsed = time();
+        println!("run {}: {:.1} ms", run, ms(elapsed));
+        times.push(elapsed);
+    }
+    let total: Duration = times.iter().sum();
+    let mean = total / runs;
+    let pixels = width as f64 * height as f64 * runs as f64;
+    let threads = rayon::current_num_threads();
+    println!(
+        "{}x{} at {} iterations on {} thread{}: mean {:.1} ms, fastest {:.1} ms, slowest {:.1} ms, {:.1} Mpixel/s",
+        width,
+        height,
+        settings.params.max_iterations,
+        threads,
+        if threads == 1 { "" } else { "s" },
+        ms(mean),
+        ms(*times.iter().min().expect("runs are at least one")),
+        ms(*times.iter().max().expect("runs are at least one")),
+        pixels / total.as_secs_f64() / 1e6
+    );
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    let config = Config::load(&args)?;
+    let bounds = args.bounds();
+    let params = Params {
+        max_iterations: config
+            .iterations
+            .unwrap_or(Params::default().max_iterations),
+        ..args.params()
+    };
+
+    let palettes = palettes(&config);
+    let mut settings = Settings {
+        mode: config.color_mode.unwrap_or_else(detect_color_mode),
+        cells: config.cell_mode,
+        style: Style {
+            coloring: args.coloring.into(),
+            palette: palettes[0].clone(),
+            offset: 0.,
+            ramp: config.ramp.clone(),
+            color_map: None,
+        },
+        cycling: false,
+        palettes,
+        palette: 0,
+        trap: args.trap,
+        backend: detect_backend(),
+        julia_c: match params.fractal {
+            Fractal::Julia(c) => c,
+            _ => Fractal::DEFAULT_JULIA,
+        },
+        formula: match &params.fractal {
+            Fractal::Formula(formula) => Some(formula.clone()),
+            _ => None,
+        },
+        params,
+        auto_iterations: config.iterations.is_none(),
+        zoom_step: config.zoom_step,
+        easing: config.easing,
+        buddhabrot: args.buddhabrot,
+        samples: args.samples,
+    };
+
+    let command = match &args.command {
+        None | Some(Command::View { .. } | Command::Tour { .. }) => None,
+        // Sweeps play in the terminal unless there is somewhere to write them.
+        Some(Command::Morph {
+            frame_dir: None, ..
+        }) if args.output.is_none() => None,
+        Some(command) => Some(command),
+    };
+    if let Some(command) = command {
+        // Larger images are framed by a view of as many cells as there can
+        // be, which they are sampled from at their own size.
+        let side = |n: u32| n.min(u16::MAX as u32) as u16;
+        let (width, height) = args.size();
+        let (width, height) = (side(width), side(height));
+        let mut viewport = match ImageFormat::of(&args.output(), args.format) {
+            // Text keeps the proportions of terminal cells.
+            ImageFormat::Txt => Viewport::fit(width, height, bounds, args.cell_aspect),
+            _ => Viewport::new(width, height, bounds),
+        };
+        restore_session(&args, &mut viewport, &mut settings)?;
+        settings.adapt_iterations(&viewport);
+        return match command {
+            Command::View { .. } | Command::Tour { .. } => {
+                unreachable!("the view runs interactively")
+            }
+            Command::Render {
+                jobs: Some(path), ..
+            } => {
+                let jobs = jobs::load(path)?;
+                jobs::render(path, &jobs, &viewport, &settings, &args)
+            }
+            Command::Render { .. } => export_image(
+                &viewport,
+                &settings,
+                &args.output(),
+                Some(args.size()),
+                &args,
+            ),
+            Command::Bench { runs, .. } => {
+                bench(&viewport, &settings, args.size(), *runs);
+                Ok(())
+            }
+            Command::Gif(animation) => {
+                let frames = movie::zoom_frames(animation, &viewport, settings.easing)?;
+                export_animation(
+                    &viewport,
+                    &settings,
+                    &args,
+                    &frames,
+                    animation.fps,
+                    None,
+                    Path::new("ffmpeg"),
+                )
+            }
+            Command::Animate {
+                animation,
+                frame_dir,
+                ffmpeg,
+                ..
+            } => {
+                let frames = movie::zoom_frames(animation, &viewport, settings.easing)?;
+                let (dir, fps) = (frame_dir.as_deref(), animation.fps);
+                export_animation(&viewport, &settings, &args, &frames, fps, dir, ffmpeg)
+            }
+            Command::Morph {
+                morph,
+                frame_dir,
+                ffmpeg,
+            } => {
+                let frames = movie::morph_frames(morph.sweep(), morph.frames, &viewport);
+                let (dir, fps) = (frame_dir.as_deref(), morph.fps);
+                export_animation(&viewport, &settings, &args, &frames, fps, dir, ffmpeg)
+            }
+        };
+    }
+
+    if !terminal::is_interactive() {
+        return Err(Error::NotATerminal);
+    }
+    let terminal = terminal::open(args.interactive().terminal)?;
+    let (width, height) = view_size(terminal.size()?, false);
+    let mut viewport = Viewport::fit(width, height, bounds, args.cell_aspect);
+    restore_session(&args, &mut viewport, &mut settings)?;
+    settings.adapt_iterations(&viewport);
+    let (playback, keyframes) = match &args.command {
+        Some(Command::Tour { file, repeat }) => {
+            let tour = tour::load(file, &viewport, &settings)?;
+            let keyframes = tour.keyframes();
+            (Some(Playback::new(tour, *repeat)), keyframes)
+        }
+        _ => (None, Vec::new()),
+    };
+    let morph = match &args.command {
+        Some(Command::Morph { morph, .. }) => Some(Morph::new(morph.sweep(), morph.period)),
+        _ => None,
+    };
 
-    handle_mouse_events(term_height, term_width, bounds);
+    handle_mouse_events(
+        &mut Ui::new(terminal)?,
+        viewport,
+        settings,
+        &args,
+        &config,
+        playback,
+        keyframes,
+        morph,
+    )
 }
@@ -0,0 +1,96 @@
+use mandelbrot::{CellMode, Frame, Params, Viewport};
+use ratatui::buffer::Buffer;
+
+use crate::{
+    ui::{put, put_cells},
+    Settings,
+};
+
+/// Iteration limit of the inset, which only needs the outline of the set.
+const ITERATIONS: u32 = 200;
+/// Widest the inset gets, in cells.
+const MAX_WIDTH: u16 = 40;
+
+/// Inset in the top right corner of the screen showing the whole fractal,
+/// with the view on screen marked on it.
+pub struct Minimap {
+    /// Parameters the inset was iterated with, and the inset itself.
+    cached: Option<(Params, Viewport, Frame)>,
+}
+
+impl Minimap {
+    pub fn new() -> Self {
+        Minimap { cached: None }
+    }
+
+    /// Draws the inset into `buffer` over the view of `viewport`, iterating
+    /// the whole fractal again if it changed since the last time.
+    pub fn render(&mut self, viewport: &Viewport, settings: &Settings, buffer: &mut Buffer) {
+        let width = (viewport.width / 4).min(MAX_WIDTH);
+        // Cells are about twice as tall as they are wide.
+        let height = (width / 2).min(viewport.height / 2);
+        if width < 4 || height < 2 {
+            return;
+        }
+
+        let mut params = settings.params.clone();
+        params.max_iterations = ITERATIONS;
+        let current = |(p, v, _): &(Params, Viewport, Frame)| {
+            p.same_orbits(&params) && (v.width, v.height) == (width, height)
+        };
+        if !self.cached.as_ref().is_some_and(current) {
+            let bounds = params.fractal.default_bounds();
+            let inset = Viewport::fit(width, height, bounds, viewport.cell_aspect);
+            let frame = Frame::from_cells(&inset, &params, CellMode::Full);
+            self.cached = Some((params, inset, frame));
+        }
+        let Some((_, inset, frame)) = &self.cached else {
+            return;
+        };
+
+        let left = viewport.width - width + 1;
+        let cells = frame.to_cells(settings.mode, &settings.style);
+        put_cells(buffer, (left - 1, 0), &cells, frame.width);
+        render_marker(buffer, viewport, inset, left);
+    }
+}
+
+/// Outlines the bounds of `viewport` on `inset`, drawn from column `left`,
+/// or marks their center if they are smaller than a cell.
+fn render_marker(buffer: &mut Buffer, viewport: &Viewport, inset: &Viewport, left: u16) {
+    let ((x_min, width), (y_min, height)) = inset.bounds();
+    let (re, im) = viewport.center();
+    let (w, h) = viewport.extent();
+    let column = |x: f64| ((x - x_min) / width * inset.width as f64).floor();
+    let row = |y: f64| ((y - y_min) / height * inset.height as f64).floor();
+
+    let (x0, x1) = (column(re - w / 2.), column(re + w / 2.));
+    let (y0, y1) = (row(im - h / 2.), row(im + h / 2.));
+    let visible = |x: f64, y: f64| {
+        (0. ..inset.width as f64).contains(&x) && (0. ..inset.height as f64).contains(&y)
+    };
+    let mut mark = |x: f64, y: f64, c: char| {
+        if visible(x, y) {
+            put(buffer, (left + x as u16, y as u16 + 1), c);
+        }
+    };
+
+    if x1 - x0 < 1. && y1 - y0 < 1. {
+        mark(x0, y0, '+');
+        return;
+    }
+    let (x0, x1) = (x0.max(-1.), x1.min(inset.width as f64));
+    let (y0, y1) = (y0.max(-1.), y1.min(inset.height as f64));
+    for x in (x0 as i32 + 1)..x1 as i32 {
+        mark(x as f64, y0, '─');
+        mark(x as f64, y1, '─');
+    }
+    for y in (y0 as i32 + 1)..y1 as i32 {
+        mark(x0, y as f64, '│');
+        mark(x1, y as f64, '│');
+    }
+    mark(x0, y0, '┌');
+    mark(x1, y0, '┐');
+    mark(x0, y1, '└');
+    mark(x1, y1, '┘');
+}
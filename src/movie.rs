@@ -0,0 +1,214 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    process::{self, Stdio},
+};
+
+use image::ImageError;
+use mandelbrot::{gif, png, Fractal, Location, Viewport, C};
+
+use crate::{
+    animation::{Easing, Sweep, Zoom},
+    bookmarks,
+    cli::Animation,
+    create_output,
+    error::Error,
+    render_pixels, session, Settings,
+};
+
+/// Where a saved view looks: the session saved in the file `name`, or else
+/// the bookmark of that name or number.
+pub fn saved_view(name: &str) -> Result<Location, Error> {
+    let error = |e: String| Error::View(name.to_string(), e);
+    let path = Path::new(name);
+    if path.is_file() {
+        let session = session::load(path).map_err(|e| error(e.to_string()))?;
+        return session
+            .location()
+            .ok_or_else(|| error("session has a malformed origin".to_string()));
+    }
+
+    let bookmarks =
+        bookmarks::load().map_err(|e| error(format!("could not load bookmarks: {}", e)))?;
+    let bookmark = bookmarks::find(&bookmarks, name)
+        .ok_or_else(|| error("not a bookmark or session file".to_string()))?;
+    bookmark
+        .location()
+        .ok_or_else(|| error("bookmark has a malformed origin".to_string()))
+}
+
+/// Where a frame looks and the constant of the Julia set it shows, unless
+/// that of the settings.
+pub type Shot = (Location, Option<C>);
+
+/// Shots of the frames of `animation`, from the view of `viewport` unless it
+/// starts at a saved one, paced by `easing` and turning between the angles
+/// of the two views, and further as fast as it spins.
+pub fn zoom_frames(
+    animation: &Animation,
+    viewport: &Viewport,
+    easing: Easing,
+) -> Result<Vec<Shot>, Error> {
+    let from = match &animation.from {
+        Some(from) => saved_view(from)?,
+        None => viewport.location(),
+    };
+    let zoom = Zoom::new(from, saved_view(&animation.to)?, easing);
+    let last = (animation.frames - 1).max(1) as f64;
+    let spin = animation.spin.to_radians();
+    Ok((0..animation.frames)
+        .map(|i| {
+            let t = i as f64 / last;
+            let mut location = zoom.location_at(t);
+            location.rotation += spin * t;
+            (location, None)
+        })
+        .collect())
+}
+
+/// Shots of `frames` frames of the view of `viewport`, with the constant
+/// swept once along `sweep`. Circles end a step short of where they start,
+/// so that the frames loop.
+pub fn morph_frames(sweep: Sweep, frames: u32, viewport: &Viewport) -> Vec<Shot> {
+    let last = match sweep {
+        Sweep::Circle(..) => frames,
+        Sweep::Line(..) => frames - 1,
+    }
+    .max(1) as f64;
+    (0..frames)
+        .map(|i| {
+            let c = sweep.at(i as f64 / last);
+            (viewport.location(), Some(c))
+        })
+        .collect()
+}
+
+/// Renders the view as each of `frames` in turn, handing the pixels to
+/// `write` with the number of the frame and reporting progress on stderr.
+fn render_frames(
+    viewport: &Viewport,
+    settings: &Settings,
+    (width, height): (u32, u32),
+    frames: &[Shot],
+    mut write: impl FnMut(usize, &[[u8; 3]]) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut shown = viewport.with_aspect(width, height);
+    let mut settings = settings.clone();
+    for (i, (location, julia)) in frames.iter().enumerate() {
+        shown.set_location(location.clone());
+        if let Some(c) = julia {
+            settings.params.fractal = Fractal::Julia(*c);
+        }
+        settings.adapt_iterations(&shown);
+        write(i, &render_pixels(&shown, &settings, width, height))?;
+        eprint!("\rrendered frame {}/{}", i + 1, frames.len());
+    }
+    eprintln!();
+    Ok(())
+}
+
+pub fn export_error(path: &Path) -> impl Fn(io::Error) -> Error + '_ {
+    move |e| Error::Export(path.to_path_buf(), ImageError::IoError(e))
+}
+
+/// Renders `frames` and writes them to `path` as an animated GIF playing at
+/// `fps` frames per second.
+pub fn export_gif(
+    viewport: &Viewport,
+    settings: &Settings,
+    path: &Path,
+    (width, height): (u32, u32),
+    frames: &[Shot],
+    fps: u32,
+) -> Result<(), Error> {
+    let error = export_error(path);
+    let (Ok(w), Ok(h)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(error(io::Error::other(
+            "GIF frames are at most 65535 pixels a side",
+        )));
+    };
+    // GIFs time frames in hundredths of a second.
+    let delay = (100. / fps as f64).round().max(1.) as u16;
+    let mut encoder = gif::Encoder::new(create_output(path)?, w, h).map_err(&error)?;
+
+    render_frames(viewport, settings, (width, height), frames, |_, pixels| {
+        encoder.add_frame(pixels, delay)
+    })
+    .and_then(|()| encoder.finish().map(drop))
+    .map_err(error)
+}
+
+/// Renders `frames` into numbered PNG files in `dir`, creating it if needed.
+pub fn export_frames(
+    viewport: &Viewport,
+    settings: &Settings,
+    dir: &Path,
+    (width, height): (u32, u32),
+    frames: &[Shot],
+) -> Result<(), Error> {
+    fs::create_dir_all(dir).map_err(export_error(dir))?;
+    render_frames(viewport, settings, (width, height), frames, |i, pixels| {
+        let path = dir.join(format!("frame-{:05}.png", i + 1));
+        png::save(&path, width as usize, height as usize, pixels).map_err(io::Error::other)
+    })
+    .map_err(export_error(dir))
+}
+
+/// Renders `frames` and streams them as raw RGB into `ffmpeg`, which
+/// encodes them into a video at `path` playing at `fps` frames per second.
+pub fn export_video(
+    viewport: &Viewport,
+    settings: &Settings,
+    path: &Path,
+    (width, height): (u32, u32),
+    frames: &[Shot],
+    fps: u32,
+    ffmpeg: &Path,
+) -> Result<(), Error> {
+    let error = export_error(path);
+    let mut child = process::Command::new(ffmpeg)
+        .args([
+            "-loglevel",
+            "error",
+            "-y",
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgb24",
+        ])
+        .args(["-s", &format!("{}x{}", width, height)])
+        .args(["-r", &fps.to_string(), "-i", "-"])
+        // Most players only take 4:2:0 chroma, which needs even sides.
+        .args([
+            "-vf",
+            "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+            "-pix_fmt",
+            "yuv420p",
+        ])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            error(io::Error::new(
+                e.kind(),
+                format!("could not run {}: {}", ffmpeg.display(), e),
+            ))
+        })?;
+
+    let mut stdin = child.stdin.take().expect("stdin of ffmpeg is piped");
+    let streamed = render_frames(viewport, settings, (width, height), frames, |_, pixels| {
+        stdin.write_all(pixels.as_flattened())
+    });
+    // Closing the pipe ends the video.
+    drop(stdin);
+    let status = child.wait().map_err(&error)?;
+    match (status.success(), streamed) {
+        (true, streamed) => streamed.map_err(error),
+        (false, _) => Err(error(io::Error::other(format!(
+            "{} failed with {}",
+            ffmpeg.display(),
+            status
+        )))),
+    }
+}
@@ -0,0 +1,83 @@
+use mandelbrot::{orbit, Params, Viewport, C};
+use ratatui::buffer::Buffer;
+
+use crate::ui::put;
+
+/// Iterates of a traced orbit drawn over the fractal.
+const ITERATES: usize = 200;
+
+/// Orbit of a clicked point, drawn over the fractal with a marker on every
+/// cell one of its first iterates falls on.
+pub struct Orbit {
+    /// Point traced, and the parameters it was iterated with.
+    point: Option<(C, Params)>,
+    /// Decimal coordinates of the point.
+    label: (String, String),
+    iterates: Vec<C>,
+    /// Iterations until the orbit escaped, or `None` if it stays bounded.
+    escaped: Option<u32>,
+}
+
+impl Orbit {
+    pub fn new() -> Self {
+        Orbit {
+            point: None,
+            label: Default::default(),
+            iterates: Vec::new(),
+            escaped: None,
+        }
+    }
+
+    /// Traces the orbit of the point under `(term_x, term_y)` from now on.
+    pub fn trace(&mut self, viewport: &Viewport, (term_x, term_y): (u16, u16), params: &Params) {
+        let (re, im) = viewport.to_complex(term_x, term_y);
+        self.point = Some((C { re, im }, params.clone()));
+        self.label = viewport.to_decimal(term_x, term_y);
+        self.iterate();
+    }
+
+    fn iterate(&mut self) {
+        let Some((c, params)) = &self.point else {
+            return;
+        };
+        let mut iterates = Vec::with_capacity(ITERATES);
+        self.escaped = orbit(*c, params, |z| {
+            if iterates.len() < ITERATES {
+                iterates.push(z);
+            }
+        });
+        self.iterates = iterates;
+    }
+
+    /// What became of the orbit, for the status bar.
+    pub fn summary(&self) -> Option<String> {
+        self.point.as_ref()?;
+        let (re, im) = &self.label;
+        Some(match self.escaped {
+            Some(i) => format!("orbit of {}, {} escapes after {}", re, im, i),
+            None => format!("orbit of {}, {} stays bounded", re, im),
+        })
+    }
+
+    /// Draws the orbit into `buffer` over the view of `viewport`, iterating
+    /// it again if `params` changed since it was traced.
+    pub fn render(&mut self, viewport: &Viewport, params: &Params, buffer: &mut Buffer) {
+        let Some((c, traced)) = &mut self.point else {
+            return;
+        };
+        let c = *c;
+        if !traced.same_orbits(params) || traced.max_iterations != params.max_iterations {
+            *traced = params.clone();
+            self.iterate();
+        }
+
+        for (z, marker) in self.iterates.iter().map(|&z| (z, '•')).chain([(c, '◆')]) {
+            let (x, y) = viewport.from_complex(z.re, z.im);
+            let visible = (0. ..viewport.width as f64).contains(&x)
+                && (0. ..viewport.height as f64).contains(&y);
+            if visible {
+                put(buffer, (x as u16 + 1, y as u16 + 1), marker);
+            }
+        }
+    }
+}
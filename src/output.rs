@@ -0,0 +1,160 @@
+//! Outputs frames are drawn to: text cells, terminal graphics protocols and
+//! image files, behind one trait so the frontends don't depend on how each
+//! of them is encoded.
+
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use crate::{iterm, kitty, png, sixel, CellMode, ColorMode, Frame, Style};
+
+/// Something a frame of escape results is drawn to once colored.
+pub trait Output {
+    /// Colors `frame` with `style` and draws or writes it.
+    fn render(&mut self, frame: &Frame, style: &Style) -> io::Result<()>;
+}
+
+/// Text cells of a sample each, written to `writer` in the color mode
+/// `mode`: blocks of its color, or with `background` spaces on it, and
+/// monochrome glyphs for [`ColorMode::Ascii`]. Draws frames of
+/// [`CellMode::Full`] or [`CellMode::Background`] cells.
+pub struct Blocks<W> {
+    pub mode: ColorMode,
+    pub background: bool,
+    pub writer: W,
+}
+
+impl<W: Write> Output for Blocks<W> {
+    fn render(&mut self, frame: &Frame, style: &Style) -> io::Result<()> {
+        let cells = match self.background {
+            true => CellMode::Background,
+            false => CellMode::Full,
+        };
+        write_cells(&mut self.writer, frame, cells, self.mode, style)
+    }
+}
+
+/// Text cells of two stacked samples each, written to `writer` as `▀` in
+/// their colors in the color mode `mode`, or in ASCII as glyphs of the top
+/// one. Draws frames of [`CellMode::HalfBlock`] cells.
+pub struct HalfBlocks<W> {
+    pub mode: ColorMode,
+    pub writer: W,
+}
+
+impl<W: Write> Output for HalfBlocks<W> {
+    fn render(&mut self, frame: &Frame, style: &Style) -> io::Result<()> {
+        write_cells(
+            &mut self.writer,
+            frame,
+            CellMode::HalfBlock,
+            self.mode,
+            style,
+        )
+    }
+}
+
+/// Monochrome braille patterns of 2x4 samples each, with a dot for every
+/// sample inside the set, written to `writer`. Draws frames of
+/// [`CellMode::Braille`] cells.
+pub struct Braille<W> {
+    pub writer: W,
+}
+
+impl<W: Write> Output for Braille<W> {
+    fn render(&mut self, frame: &Frame, style: &Style) -> io::Result<()> {
+        let mode = ColorMode::Ascii;
+        write_cells(&mut self.writer, frame, CellMode::Braille, mode, style)
+    }
+}
+
+/// Text cells drawn by whichever of [`Blocks`], [`HalfBlocks`] and
+/// [`Braille`] takes the cells frames were sampled for.
+pub struct Text<W> {
+    pub mode: ColorMode,
+    pub writer: W,
+}
+
+impl<W: Write> Output for Text<W> {
+    fn render(&mut self, frame: &Frame, style: &Style) -> io::Result<()> {
+        let (mode, writer) = (self.mode, &mut self.writer);
+        match frame.cells() {
+            cells @ (CellMode::Full | CellMode::Background) => Blocks {
+                mode,
+                background: cells == CellMode::Background,
+                writer,
+            }
+            .render(frame, style),
+            CellMode::HalfBlock => HalfBlocks { mode, writer }.render(frame, style),
+            CellMode::Braille => Braille { writer }.render(frame, style),
+        }
+    }
+}
+
+/// Writes the rows of `frame` drawn as `cells` does, if the frame has the
+/// samples of each cell those take.
+fn write_cells(
+    writer: &mut impl Write,
+    frame: &Frame,
+    cells: CellMode,
+    mode: ColorMode,
+    style: &Style,
+) -> io::Result<()> {
+    if frame.cells().subdivision() != cells.subdivision() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{:?} cells can't be drawn as {:?}", frame.cells(), cells),
+        ));
+    }
+    for row in frame.rows_as(cells, mode, style) {
+        writer.write_all(row.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Graphics protocols of terminals that draw images inline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Sixel,
+    Kitty,
+    Iterm,
+}
+
+impl Protocol {
+    /// Encodes a row-major `width * height` RGB image in the protocol.
+    pub fn encode(self, width: usize, height: usize, pixels: &[[u8; 3]]) -> String {
+        match self {
+            Protocol::Sixel => sixel::encode(width, height, pixels),
+            Protocol::Kitty => kitty::encode(width, height, pixels),
+            Protocol::Iterm => iterm::encode(width, height, pixels),
+        }
+    }
+}
+
+/// An image of a sample per pixel written to `writer` in a terminal
+/// graphics protocol.
+pub struct Graphics<W> {
+    pub protocol: Protocol,
+    pub writer: W,
+}
+
+impl<W: Write> Output for Graphics<W> {
+    fn render(&mut self, frame: &Frame, style: &Style) -> io::Result<()> {
+        let pixels = frame.to_pixels(style);
+        let image = self.protocol.encode(frame.width, frame.height, &pixels);
+        self.writer.write_all(image.as_bytes())
+    }
+}
+
+/// A PNG file at `path`, with a pixel per sample.
+pub struct ImageFile {
+    pub path: PathBuf,
+}
+
+impl Output for ImageFile {
+    fn render(&mut self, frame: &Frame, style: &Style) -> io::Result<()> {
+        let pixels = frame.to_pixels(style);
+        png::save(&self.path, frame.width, frame.height, &pixels).map_err(io::Error::other)
+    }
+}
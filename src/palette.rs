@@ -0,0 +1,262 @@
+//! Color gradients used for escape-time coloring, built in or loaded from
+//! Fractint `.map` files, lists of hex colors, or TOML gradient stops.
+
+use std::{error::Error, fmt, fs, io, path::Path};
+
+use serde::{de, Deserialize, Deserializer};
+
+/// An RGB color with components in `0..=1`.
+pub type Rgb = (f64, f64, f64);
+
+/// A gradient through colors at increasing positions in `0..=1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    pub name: String,
+    stops: Vec<(f64, Rgb)>,
+}
+
+#[derive(Debug)]
+pub enum PaletteError {
+    Io(io::Error),
+    /// A malformed line of a `.map` or hex color file.
+    Parse {
+        line: usize,
+        message: String,
+    },
+    Toml(toml::de::Error),
+    /// A palette without any colors.
+    Empty,
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::Io(e) => write!(f, "{}", e),
+            PaletteError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            PaletteError::Toml(e) => write!(f, "{}", e),
+            PaletteError::Empty => write!(f, "palette has no colors"),
+        }
+    }
+}
+
+impl Error for PaletteError {}
+
+impl From<io::Error> for PaletteError {
+    fn from(e: io::Error) -> Self {
+        PaletteError::Io(e)
+    }
+}
+
+#[derive(Deserialize)]
+struct TomlPalette {
+    name: Option<String>,
+    stops: Vec<TomlStop>,
+}
+
+#[derive(Deserialize)]
+struct TomlStop {
+    position: f64,
+    #[serde(deserialize_with = "deserialize_hex")]
+    color: Rgb,
+}
+
+fn deserialize_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rgb, D::Error> {
+    parse_hex(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+}
+
+impl Default for Palette {
+    /// Blue, white and orange, wrapping around without a seam.
+    fn default() -> Self {
+        Palette::even(
+            "default",
+            vec![
+                (0.0, 0.03, 0.2),
+                (0.1, 0.4, 0.9),
+                (1.0, 1.0, 1.0),
+                (1.0, 0.65, 0.0),
+                (0.0, 0.03, 0.2),
+            ],
+        )
+    }
+}
+
+impl Palette {
+    /// A palette whose colors are spread evenly over the gradient.
+    pub fn even(name: &str, colors: Vec<Rgb>) -> Palette {
+        let last = colors.len().saturating_sub(1).max(1) as f64;
+
+        Palette {
+            name: name.to_string(),
+            stops: colors
+                .into_iter()
+                .enumerate()
+                .map(|(i, color)| (i as f64 / last, color))
+                .collect(),
+        }
+    }
+
+    /// A palette through colors at the given positions in `0..=1`, in any
+    /// order.
+    pub fn with_stops(name: &str, mut stops: Vec<(f64, Rgb)>) -> Palette {
+        for stop in &mut stops {
+            stop.0 = stop.0.clamp(0., 1.);
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Palette {
+            name: name.to_string(),
+            stops,
+        }
+    }
+
+    /// Colors of the gradient and their positions, in order.
+    pub fn stops(&self) -> &[(f64, Rgb)] {
+        &self.stops
+    }
+
+    /// The palettes shipped with the program, starting with the default one.
+    pub fn builtins() -> Vec<Palette> {
+        vec![
+            Palette::default(),
+            Palette::even(
+                "fire",
+                vec![
+                    (0.0, 0.0, 0.0),
+                    (0.5, 0.0, 0.0),
+                    (1.0, 0.45, 0.0),
+                    (1.0, 0.9, 0.3),
+                    (1.0, 1.0, 1.0),
+                    (0.0, 0.0, 0.0),
+                ],
+            ),
+            Palette::even(
+                "ocean",
+                vec![
+                    (0.0, 0.05, 0.1),
+                    (0.0, 0.3, 0.4),
+                    (0.2, 0.7, 0.7),
+                    (0.85, 0.95, 0.9),
+                    (0.0, 0.05, 0.1),
+                ],
+            ),
+            Palette::even("grayscale", vec![(0., 0., 0.), (1., 1., 1.), (0., 0., 0.)]),
+        ]
+    }
+
+    /// Looks up a built-in palette by name.
+    pub fn builtin(name: &str) -> Option<Palette> {
+        Palette::builtins().into_iter().find(|p| p.name == name)
+    }
+
+    /// Loads a palette, picking the format from the extension: `.map` for
+    /// Fractint maps, `.toml` for gradient stops, and a list of hex colors
+    /// otherwise.
+    pub fn load(path: &Path) -> Result<Palette, PaletteError> {
+        let source = fs::read_to_string(path)?;
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+
+        let palette = match path.extension().and_then(|e| e.to_str()) {
+            Some("map") => Palette::even(&name, parse_map(&source)?),
+            Some("toml") => parse_toml(&name, &source)?,
+            _ => Palette::even(&name, parse_hex_list(&source)?),
+        };
+
+        if palette.stops.is_empty() {
+            return Err(PaletteError::Empty);
+        }
+        Ok(palette)
+    }
+
+    /// Color at position `p` in `0..=1`, interpolated between neighboring stops.
+    pub fn sample(&self, p: f64) -> Rgb {
+        let i = self.stops.partition_point(|&(pos, _)| pos <= p);
+        match (self.stops.get(i.wrapping_sub(1)), self.stops.get(i)) {
+            (Some(&(p0, a)), Some(&(p1, b))) => {
+                let t = (p - p0) / (p1 - p0);
+                let lerp = |a: f64, b: f64| a + (b - a) * t;
+                (lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+            }
+            (Some(&(_, a)), None) | (None, Some(&(_, a))) => a,
+            (None, None) => (0., 0., 0.),
+        }
+    }
+}
+
+/// Parses a Fractint map: one `R G B` triple in `0..=255` per line, with
+/// anything after the third number treated as a comment.
+fn parse_map(source: &str) -> Result<Vec<Rgb>, PaletteError> {
+    let mut colors = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let error = |message: &str| PaletteError::Parse {
+            line: i + 1,
+            message: message.to_string(),
+        };
+        let values: Vec<&str> = line.split_whitespace().take(3).collect();
+        if values.is_empty() {
+            continue;
+        }
+        if values.len() < 3 {
+            return Err(error("expected three color components"));
+        }
+
+        let component = |v: &str| {
+            v.parse::<u8>()
+                .map(|v| v as f64 / 255.)
+                .map_err(|_| error("expected a color component in 0..=255"))
+        };
+        colors.push((
+            component(values[0])?,
+            component(values[1])?,
+            component(values[2])?,
+        ));
+    }
+
+    Ok(colors)
+}
+
+/// Parses whitespace-separated `#rrggbb` colors, skipping `;` comments.
+fn parse_hex_list(source: &str) -> Result<Vec<Rgb>, PaletteError> {
+    let mut colors = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let line = line.split(';').next().unwrap_or_default();
+        for word in line.split_whitespace() {
+            colors.push(parse_hex(word).map_err(|message| PaletteError::Parse {
+                line: i + 1,
+                message,
+            })?);
+        }
+    }
+
+    Ok(colors)
+}
+
+fn parse_toml(name: &str, source: &str) -> Result<Palette, PaletteError> {
+    let palette: TomlPalette = toml::from_str(source).map_err(PaletteError::Toml)?;
+    let stops = palette
+        .stops
+        .iter()
+        .map(|stop| (stop.position, stop.color))
+        .collect();
+
+    Ok(Palette::with_stops(
+        palette.name.as_deref().unwrap_or(name),
+        stops,
+    ))
+}
+
+fn parse_hex(s: &str) -> Result<Rgb, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(format!("expected a color as #rrggbb, got `{}`", s));
+    }
+
+    let component = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16)
+            .map(|v| v as f64 / 255.)
+            .map_err(|_| format!("invalid hex color `{}`", s))
+    };
+    Ok((component(0)?, component(2)?, component(4)?))
+}
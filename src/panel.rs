@@ -0,0 +1,113 @@
+use mandelbrot::{active_precision, Coloring, Fractal, Viewport};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::Line,
+    widgets::{Block, Paragraph, Widget},
+};
+
+use crate::{
+    bookmarks::Bookmark,
+    keymap::{Action, Keymap},
+    magnification, Settings,
+};
+
+/// Side panel listing the parameters of the view, and the bookmarks below
+/// them.
+pub struct Panel<'a> {
+    pub viewport: &'a Viewport,
+    pub settings: &'a Settings,
+    pub bookmarks: &'a [Bookmark],
+    /// Keymap the hints name the keys of.
+    pub keymap: &'a Keymap,
+}
+
+impl Panel<'_> {
+    /// Names and values of the parameters, one to a line.
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        let (settings, params) = (self.settings, &self.settings.params);
+        let (re, im) = self.viewport.center_decimal();
+        let iterations = match settings.auto_iterations {
+            true => format!("{} (auto)", params.max_iterations),
+            false => params.max_iterations.to_string(),
+        };
+        let fractal = match &params.fractal {
+            Fractal::Mandelbrot => "Mandelbrot".to_string(),
+            Fractal::Julia(c) => format!("Julia of {}", c),
+            Fractal::BurningShip => "burning ship".to_string(),
+            Fractal::Tricorn => "tricorn".to_string(),
+            Fractal::Newton => "Newton".to_string(),
+            Fractal::Formula(formula) => formula.to_string(),
+            Fractal::Plugin(_) => "plugin".to_string(),
+        };
+        let coloring = match settings.style.coloring {
+            Coloring::Cyclic => "cyclic",
+            Coloring::Histogram => "histogram",
+            Coloring::OrbitTrap => "orbit trap",
+            Coloring::Distance => "distance",
+        };
+        let lower = |debug: String| debug.to_lowercase();
+
+        let mut parameters = vec![
+            ("fractal", fractal),
+            ("re", re),
+            ("im", im),
+            (
+                "zoom",
+                format!("{:.3e}x", magnification(self.viewport, params)),
+            ),
+            (
+                "turned",
+                format!("{:.0}°", self.viewport.rotation.to_degrees()),
+            ),
+            ("iterations", iterations),
+            (
+                "precision",
+                active_precision(self.viewport, params).to_string(),
+            ),
+            ("exponent", params.exponent.to_string()),
+            ("coloring", coloring.to_string()),
+            ("palette", settings.style.palette.name.clone()),
+            ("colors", lower(format!("{:?}", settings.mode))),
+            ("cells", lower(format!("{:?}", settings.cells))),
+            ("backend", lower(format!("{:?}", settings.backend))),
+        ];
+        if settings.buddhabrot {
+            parameters.push(("samples", settings.samples.to_string()));
+        }
+        parameters
+    }
+}
+
+impl Widget for Panel<'_> {
+    fn render(self, area: Rect, buffer: &mut ratatui::buffer::Buffer) {
+        let parameters = self.parameters();
+        let [top, bottom] = Layout::vertical([
+            Constraint::Length(parameters.len() as u16 + 2),
+            Constraint::Min(0),
+        ])
+        .areas(area);
+
+        let lines: Vec<_> = parameters
+            .into_iter()
+            .map(|(name, value)| Line::raw(format!("{:<10} {}", name, value)))
+            .collect();
+        Paragraph::new(lines)
+            .block(Block::bordered().title("parameters"))
+            .render(top, buffer);
+
+        let lines: Vec<_> = match self.bookmarks {
+            [] => {
+                let hint = self.keymap.hint(Action::SaveBookmark, "to add one");
+                vec![Line::raw(format!("none yet{}", hint))]
+            }
+            bookmarks => bookmarks
+                .iter()
+                .enumerate()
+                .map(|(i, bookmark)| Line::raw(format!("{:>3} {}", i + 1, bookmark.name)))
+                .collect(),
+        };
+        Paragraph::new(lines)
+            .block(Block::bordered().title("bookmarks"))
+            .render(bottom, buffer);
+    }
+}
@@ -0,0 +1,254 @@
+use std::{fs, path::Path};
+
+use mandelbrot::{
+    palette::{Palette, Rgb},
+    precision, Fractal, Origin, C,
+};
+
+use crate::{cli::FractalKind, link::Link};
+
+/// Height of the plane Fractint shows at a magnification of 1.
+const FRACTINT_HEIGHT: f64 = 2.;
+
+/// An entry of a parameter file: its name and `key=value` fields.
+type Entry = (String, Vec<(String, String)>);
+
+/// Splits a parameter file into its entries, each written `Name { key=value
+/// ... }`, dropping `;` comments and joining lines continued with `\`.
+fn entries(source: &str) -> Result<Vec<Entry>, String> {
+    let mut text = String::new();
+    let mut continued = false;
+    for line in source.lines() {
+        let line = line.split_once(';').map_or(line, |(line, _)| line);
+        let line = if continued { line.trim_start() } else { line };
+        match line.trim_end().strip_suffix('\\') {
+            Some(line) => {
+                text.push_str(line);
+                continued = true;
+            }
+            None => {
+                text.push_str(line);
+                text.push('\n');
+                continued = false;
+            }
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut rest = text.as_str();
+    while let Some((name, after)) = rest.split_once('{') {
+        let (body, after) = after
+            .split_once('}')
+            .ok_or("unterminated entry, expected `}`")?;
+        let fields = body
+            .split_whitespace()
+            .map(|field| {
+                let (key, value) = field.split_once('=').unwrap_or((field, ""));
+                (key.to_ascii_lowercase(), value.to_string())
+            })
+            .collect();
+        entries.push((name.trim().to_string(), fields));
+        rest = after;
+    }
+    Ok(entries)
+}
+
+fn number(value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| format!("expected a number, got `{}`", value))
+}
+
+/// Value of a color component in the compressed palettes of parameters:
+/// `0`-`9`, `A`-`Z` and `_`-`z` for `0..64`.
+fn component(c: u8) -> Option<f64> {
+    let value = match c {
+        b'0'..=b'9' => c - b'0',
+        b'A'..=b'Z' => c - b'A' + 10,
+        b'_'..=b'z' => c - b'_' + 36,
+        _ => return None,
+    };
+    Some(value as f64 / 63.)
+}
+
+/// Decodes a palette given in parameters, three components to a color, with
+/// `<N>` standing for `N` colors shaded between those around it.
+fn colors(name: &str, value: &str) -> Result<Palette, String> {
+    let invalid = || format!("invalid colors at `{}`", value);
+    let mut colors: Vec<Rgb> = Vec::new();
+    let mut shaded = 0;
+    let mut rest = value.as_bytes();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix(b"<") {
+            let end = after.iter().position(|&c| c == b'>').ok_or_else(invalid)?;
+            shaded = std::str::from_utf8(&after[..end])
+                .ok()
+                .and_then(|n| n.parse::<usize>().ok())
+                .ok_or_else(invalid)?;
+            rest = &after[end + 1..];
+            continue;
+        }
+        let [r, g, b, ..] = *rest else {
+            return Err(invalid());
+        };
+        let color = match (component(r), component(g), component(b)) {
+            (Some(r), Some(g), Some(b)) => (r, g, b),
+            _ => return Err(invalid()),
+        };
+        rest = &rest[3..];
+
+        if shaded > 0 {
+            let from = *colors.last().ok_or_else(invalid)?;
+            for i in 1..=shaded {
+                let t = i as f64 / (shaded + 1) as f64;
+                let lerp = |a: f64, b: f64| a + (b - a) * t;
+                colors.push((
+                    lerp(from.0, color.0),
+                    lerp(from.1, color.1),
+                    lerp(from.2, color.2),
+                ));
+            }
+            shaded = 0;
+        }
+        colors.push(color);
+    }
+    if colors.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Palette::even(name, colors))
+}
+
+/// Parses the entry `name` of a Fractint parameter file, or its first: the
+/// corners or center and magnification of its view, the iteration limit,
+/// the Julia parameter and its colors, either given inline or as a `.map`
+/// file looked up next to `dir`.
+pub fn parse(source: &str, name: Option<&str>, dir: &Path) -> Result<Link, String> {
+    let entries = entries(source)?;
+    let (title, fields) = match name {
+        Some(name) => entries
+            .iter()
+            .find(|(title, _)| title.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("no entry `{}`", name))?,
+        None => entries.first().ok_or("no entries")?,
+    };
+    let field = |key: &str| {
+        fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    };
+    let numbers = |key: &str| -> Result<Option<Vec<f64>>, String> {
+        field(key)
+            .map(|value| value.split('/').map(number).collect())
+            .transpose()
+    };
+
+    let kind = field("type").unwrap_or("mandel").to_ascii_lowercase();
+    let (fractal, default) = match kind.as_str() {
+        "mandel" | "mandelfp" => (FractalKind::Mandelbrot, Fractal::Mandelbrot),
+        "julia" | "juliafp" => (FractalKind::Julia, Fractal::Julia(Fractal::DEFAULT_JULIA)),
+        _ => return Err(format!("fractal type `{}` isn't supported", kind)),
+    };
+    let ((_, default_width), (_, default_height)) = default.default_bounds();
+
+    // Corners keep the digits deep views need, so they are measured at the
+    // precision of the view rather than in floats.
+    let (origin, zoom, rotation) = if let Some(corners) = field("corners") {
+        let values: Vec<&str> = corners.split('/').collect();
+        let [x_min, x_max, y_min, y_max, third @ ..] = &values[..] else {
+            return Err(format!(
+                "expected corners=XMIN/XMAX/YMIN/YMAX, got `{}`",
+                corners
+            ));
+        };
+        if let [x3, y3] = third {
+            if number(x3)? != number(x_min)? || number(y3)? != number(y_min)? {
+                return Err("rotated or skewed corners aren't supported".to_string());
+            }
+        }
+        let invalid = || format!("invalid corners `{}`", corners);
+        let low = Origin::parse(x_min, y_min).ok_or_else(invalid)?;
+        let high = Origin::parse(x_max, y_max).ok_or_else(invalid)?;
+        let (width, height) = high.difference(&low);
+        if width <= 0. || height <= 0. {
+            return Err(format!("empty corners `{}`", corners));
+        }
+        let origin = low.offset((width / 2., height / 2.), precision(width.min(height)));
+        let zoom = (default_width / width).min(default_height / height);
+        (origin, zoom, 0.)
+    } else if let Some(center) = field("center-mag") {
+        let values: Vec<&str> = center.split('/').collect();
+        let [re, im, rest @ ..] = &values[..] else {
+            return Err(format!("expected center-mag=X/Y/MAG, got `{}`", center));
+        };
+        let magnification = rest.first().map_or(Ok(1.), |mag| number(mag))?;
+        if magnification <= 0. {
+            return Err("the magnification must be positive".to_string());
+        }
+        let origin = Origin::parse(re, im).ok_or_else(|| format!("invalid center `{}`", center))?;
+        let rotation = rest.get(2).map_or(Ok(0.), |angle| number(angle))?;
+        (
+            origin,
+            magnification * default_height / FRACTINT_HEIGHT,
+            rotation,
+        )
+    } else {
+        return Err("no corners or center-mag".to_string());
+    };
+
+    let mut link = Link::new(origin.to_decimal());
+    link.fractal = fractal;
+    link.zoom = zoom;
+    link.rotation = rotation;
+    if let Some(n) = field("maxiter") {
+        link.iterations = Some(
+            n.parse::<u32>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("invalid maxiter `{}`", n))?,
+        );
+    }
+    if matches!(fractal, FractalKind::Julia) {
+        if let Some([re, im, ..]) = numbers("params")?.as_deref() {
+            link.julia = Some(C { re: *re, im: *im });
+        }
+    }
+    link.palette = match field("colors") {
+        Some(value) => match value.strip_prefix('@') {
+            Some(map) => {
+                let path = dir.join(map);
+                let palette = Palette::load(&path).map_err(|e| format!("{}: {}", map, e))?;
+                Some(palette)
+            }
+            None => Some(colors(title, value)?),
+        },
+        None => None,
+    };
+    Ok(link)
+}
+
+/// Reads an entry of a Fractint parameter file, given as `FILE.par` for the
+/// first or `FILE.par#NAME` for the one named `NAME`.
+pub fn load(s: &str) -> Result<Link, String> {
+    let (file, name) = match s.rsplit_once('#') {
+        Some((file, name)) if is_par(file) => (file, Some(name)),
+        _ => (s, None),
+    };
+    let path = Path::new(file);
+    let dir = path.parent().unwrap_or(Path::new(""));
+    fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|source| parse(&source, name, dir))
+        .map_err(|e| format!("{}: {}", file, e))
+}
+
+/// Whether `s` names a Fractint parameter file, or an entry of one.
+pub fn is_par(s: &str) -> bool {
+    let file = s.rsplit_once('#').map_or(s, |(file, _)| file);
+    Path::new(file)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("par"))
+}
@@ -0,0 +1,123 @@
+//! Perturbation rendering of deep views: a single reference orbit is
+//! iterated in arbitrary precision, and every other point follows it as a
+//! small f64 difference `dz`, which keeps its precision where the points
+//! themselves can't be told apart in f64.
+//!
+//! Points whose orbit drifts away from the reference, or outlives it, are
+//! rebased onto the start of the reference orbit, which avoids the glitches
+//! of plain perturbation.
+
+use dashu_float::FBig;
+
+use crate::{
+    fractal::track,
+    precise::{add, approx, mul},
+    EscapeResult, Fractal, Params, Viewport, C,
+};
+
+/// The high-precision orbit the points of a deep view are iterated against.
+pub(crate) struct Reference {
+    /// The reference point, relative to the origin of the view.
+    offset: (f64, f64),
+    /// The reference point, rounded to f64.
+    center: C,
+    /// Iterates of the reference orbit, rounded to f64, starting from `z0`.
+    orbit: Vec<C>,
+    /// Whether orbits start from their point, as for Julia sets, rather than
+    /// from zero.
+    julia: bool,
+}
+
+/// Whether perturbation iterates the fractal of `params`: the quadratic
+/// Mandelbrot and Julia sets.
+pub(crate) fn supports(params: &Params) -> bool {
+    matches!(params.fractal, Fractal::Mandelbrot | Fractal::Julia(_)) && params.exponent == 2.
+}
+
+impl Reference {
+    /// Iterates the reference orbit through the center of `viewport`, or
+    /// returns `None` if perturbation doesn't [`supports`] the fractal.
+    pub(crate) fn new(viewport: &Viewport, params: &Params) -> Option<Reference> {
+        if !supports(params) {
+            return None;
+        }
+
+        let offset = (viewport.center.re, viewport.center.im);
+        let precision = viewport.precision();
+        let center = viewport.origin.offset(offset, precision);
+        let center = (center.re, center.im);
+        let approx_center = approx(&center);
+
+        let big = |v: f64| FBig::try_from(v).unwrap_or_default();
+        let (julia, mut z, k) = match &params.fractal {
+            Fractal::Mandelbrot => (false, (FBig::ZERO, FBig::ZERO), center),
+            Fractal::Julia(k) => (true, center, (big(k.re), big(k.im))),
+            _ => return None,
+        };
+
+        let mut orbit = Vec::new();
+        for _ in 0..=params.max_iterations + 1 {
+            let zf = approx(&z);
+            orbit.push(zf);
+            if zf.norm() > params.cutoff {
+                break;
+            }
+            z = add(&mul(&z, &z), &k);
+            // Keep the iterates from growing past the precision they need.
+            z = (
+                z.0.with_precision(precision).value(),
+                z.1.with_precision(precision).value(),
+            );
+        }
+        // A reference escaping right away has no orbit to follow.
+        if orbit.len() < 2 {
+            return None;
+        }
+
+        Some(Reference {
+            offset,
+            center: approx_center,
+            orbit,
+            julia,
+        })
+    }
+
+    /// Iterates the point at `(x, y)` from the origin of the view.
+    pub(crate) fn iterate(&self, (x, y): (f64, f64), params: &Params) -> EscapeResult {
+        let dc = C {
+            re: x - self.offset.0,
+            im: y - self.offset.1,
+        };
+        track(self.center + dc, params, |visit| {
+            self.escape(dc, params, visit)
+        })
+    }
+
+    fn escape(&self, dc: C, params: &Params, visit: &mut dyn FnMut(C)) -> Option<(u32, C)> {
+        let zero = C { re: 0., im: 0. };
+        let two = C { re: 2., im: 0. };
+        let (mut dz, add) = if self.julia { (dc, zero) } else { (zero, dc) };
+        let (mut n, mut i) = (0, 0);
+
+        loop {
+            let z = self.orbit[n] + dz;
+            if z.norm() > params.cutoff {
+                return Some((i, z));
+            }
+
+            if i > params.max_iterations {
+                return None;
+            }
+
+            if n + 1 == self.orbit.len() || z.norm() < dz.norm() {
+                dz = z - self.orbit[0];
+                n = 0;
+            }
+
+            dz = two * self.orbit[n] * dz + dz * dz + add;
+            n += 1;
+            i += 1;
+            visit(self.orbit[n] + dz);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+//! PNG encoding of rendered images.
+
+use std::{io::Cursor, path::Path};
+
+use image::{ImageFormat, ImageResult, RgbImage};
+
+fn to_image(width: usize, height: usize, pixels: &[[u8; 3]]) -> RgbImage {
+    RgbImage::from_raw(width as u32, height as u32, pixels.concat())
+        .expect("pixel buffer matches image size")
+}
+
+/// Encodes a row-major `width * height` RGB image as PNG file data.
+pub fn encode(width: usize, height: usize, pixels: &[[u8; 3]]) -> Vec<u8> {
+    let image = to_image(width, height, pixels);
+
+    let mut data = Cursor::new(Vec::new());
+    image.write_to(&mut data, ImageFormat::Png).unwrap();
+    data.into_inner()
+}
+
+/// Writes a row-major `width * height` RGB image to a PNG file at `path`.
+pub fn save(path: &Path, width: usize, height: usize, pixels: &[[u8; 3]]) -> ImageResult<()> {
+    to_image(width, height, pixels).save_with_format(path, ImageFormat::Png)
+}
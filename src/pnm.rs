@@ -0,0 +1,36 @@
+//! Binary PPM and PGM encoding, simple enough for any image tool to read
+//! from a pipe.
+
+use crate::EscapeResult;
+
+/// Encodes a row-major `width * height` RGB image as a binary PPM (`P6`).
+pub fn encode_ppm(width: usize, height: usize, pixels: &[[u8; 3]]) -> Vec<u8> {
+    let mut data = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    data.extend(pixels.iter().flatten());
+    data
+}
+
+/// Encodes the iteration counts of a row-major `width * height` image as a
+/// binary PGM (`P5`), points that never escaped at `max_iterations`. Counts
+/// are scaled down to 16 bits if they don't fit.
+pub fn encode_pgm(
+    width: usize,
+    height: usize,
+    results: &[EscapeResult],
+    max_iterations: u32,
+) -> Vec<u8> {
+    let max = max_iterations.max(1);
+    let maxval = max.min(u16::MAX as u32);
+    let mut data = format!("P5\n{} {}\n{}\n", width, height, maxval).into_bytes();
+
+    for result in results {
+        let iterations = result.iterations.unwrap_or(max).min(max);
+        let value = (iterations as u64 * maxval as u64 / max as u64) as u16;
+        // Samples take two bytes, most significant first, above 255.
+        match maxval {
+            0..=255 => data.push(value as u8),
+            _ => data.extend(value.to_be_bytes()),
+        }
+    }
+    data
+}
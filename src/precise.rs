@@ -0,0 +1,123 @@
+//! Direct iteration in arbitrary precision, for deep views of the fractals
+//! perturbation doesn't cover. Far slower than f64, but it resolves views
+//! however small they get.
+
+use dashu_base::Abs;
+use dashu_float::FBig;
+
+use crate::{fractal::track, EscapeResult, Fractal, Params, Viewport, C};
+
+/// `re + i im` in arbitrary precision.
+pub(crate) type BigC = (FBig, FBig);
+
+pub(crate) fn mul((a, b): &BigC, (c, d): &BigC) -> BigC {
+    (a * c - b * d, a * d + b * c)
+}
+
+pub(crate) fn add((a, b): &BigC, (c, d): &BigC) -> BigC {
+    (a + c, b + d)
+}
+
+pub(crate) fn approx((re, im): &BigC) -> C {
+    C {
+        re: re.to_f64().value(),
+        im: im.to_f64().value(),
+    }
+}
+
+/// `z^d` for a positive integer `d`, by repeated squaring.
+fn powi(z: &BigC, mut d: u32) -> BigC {
+    let (mut base, mut result) = (z.clone(), None::<BigC>);
+
+    while d > 0 {
+        if d & 1 == 1 {
+            result = Some(match result {
+                Some(result) => mul(&result, &base),
+                None => base.clone(),
+            });
+        }
+        d >>= 1;
+        if d > 0 {
+            base = mul(&base, &base);
+        }
+    }
+    result.unwrap_or((FBig::ONE, FBig::ZERO))
+}
+
+/// Whether arbitrary precision iterates the fractal of `params`: it covers
+/// those with integer exponents.
+pub(crate) fn supports(params: &Params) -> bool {
+    let fractal = matches!(
+        params.fractal,
+        Fractal::Mandelbrot | Fractal::Julia(_) | Fractal::BurningShip | Fractal::Tricorn
+    );
+    let d = params.exponent;
+    fractal && d.fract() == 0. && (1. ..=64.).contains(&d)
+}
+
+/// Iterates the points of a deep view one by one in arbitrary precision.
+pub(crate) struct Precise<'a> {
+    viewport: &'a Viewport,
+    precision: usize,
+    /// Integer power of the iteration.
+    exponent: u32,
+}
+
+impl<'a> Precise<'a> {
+    /// Iterates the points of `viewport`, or returns `None` if the fractal
+    /// isn't one arbitrary precision [`supports`].
+    pub(crate) fn new(viewport: &'a Viewport, params: &Params) -> Option<Self> {
+        if !supports(params) {
+            return None;
+        }
+
+        Some(Precise {
+            viewport,
+            precision: viewport.precision(),
+            exponent: params.exponent as u32,
+        })
+    }
+
+    /// Iterates the point at `(x, y)` from the origin of the view.
+    pub(crate) fn iterate(&self, (x, y): (f64, f64), params: &Params) -> EscapeResult {
+        let point = self.viewport.origin.offset((x, y), self.precision);
+        let point = (point.re, point.im);
+        track(approx(&point), params, |visit| {
+            self.escape(point, params, visit)
+        })
+    }
+
+    fn escape(&self, point: BigC, params: &Params, visit: &mut dyn FnMut(C)) -> Option<(u32, C)> {
+        let big = |v: f64| FBig::try_from(v).unwrap_or_default();
+        let (mut z, k) = match &params.fractal {
+            Fractal::Julia(k) => (point, (big(k.re), big(k.im))),
+            _ => ((FBig::ZERO, FBig::ZERO), point),
+        };
+        let mut i = 0;
+
+        loop {
+            let zf = approx(&z);
+            if zf.norm() > params.cutoff {
+                return Some((i, zf));
+            }
+
+            if i > params.max_iterations {
+                return None;
+            }
+
+            let base = match params.fractal {
+                Fractal::BurningShip => (z.0.abs(), z.1.abs()),
+                Fractal::Tricorn => (z.0, -z.1),
+                _ => z,
+            };
+            z = add(&powi(&base, self.exponent), &k);
+            // Keep the iterates from growing past the precision they need.
+            z = (
+                z.0.with_precision(self.precision).value(),
+                z.1.with_precision(self.precision).value(),
+            );
+            visit(approx(&z));
+            i += 1;
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use ratatui::text::Line;
+
+use crate::{
+    terminal::{Event, Key},
+    ui::{centered, dialog, Ui},
+};
+
+/// Columns left for typing after the label of a prompt, past which the line
+/// scrolls.
+const INPUT_WIDTH: usize = 30;
+
+/// Reads a line typed in a dialog after `label`, with the `listed` lines
+/// above it, or `None` if cancelled with Esc.
+///
+/// The line can be edited with the arrow keys, Home, End, Backspace, Delete
+/// and Ctrl-U to clear it. The cursor, hidden otherwise, is shown meanwhile.
+pub fn read_line(
+    ui: &mut Ui,
+    mut next_event: impl FnMut() -> Option<Event>,
+    label: &str,
+    listed: &[String],
+) -> Option<String> {
+    let base = ui.shown();
+    let label_width = label.chars().count();
+    let longest = listed.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let width = (label_width + INPUT_WIDTH).max(longest) + 2;
+    let mut line: Vec<char> = Vec::new();
+    let mut cursor = 0;
+
+    loop {
+        // A failed draw comes up again at the next one of the event loop.
+        let _ = ui.draw_over(&base, |frame| {
+            let height = frame.area().height;
+            let shown = listed.len().min(height.saturating_sub(3) as usize);
+            let area = centered(frame.area(), width as u16, shown as u16 + 3);
+            let columns = area.width.saturating_sub(2) as usize;
+            // The line scrolls to keep the cursor in the box.
+            let offset = (label_width + cursor + 1).saturating_sub(columns);
+            let typed: String = label
+                .chars()
+                .chain(line.iter().copied())
+                .skip(offset)
+                .take(columns)
+                .collect();
+
+            let mut lines: Vec<_> = listed[..shown].iter().map(Line::raw).collect();
+            lines.push(Line::raw(typed));
+            let inner = dialog(frame, area, "", lines);
+            let column = (label_width + cursor).saturating_sub(offset) as u16;
+            frame.set_cursor_position((inner.x + column, inner.y + shown as u16));
+        });
+
+        match next_event()? {
+            Event::Key(Key::Char('\n')) => return Some(line.iter().collect()),
+            Event::Key(Key::Esc) => return None,
+            Event::Key(Key::Backspace) if cursor > 0 => {
+                cursor -= 1;
+                line.remove(cursor);
+            }
+            Event::Key(Key::Delete) if cursor < line.len() => {
+                line.remove(cursor);
+            }
+            Event::Key(Key::Left) => cursor = cursor.saturating_sub(1),
+            Event::Key(Key::Right) => cursor = (cursor + 1).min(line.len()),
+            Event::Key(Key::Home) => cursor = 0,
+            Event::Key(Key::End) => cursor = line.len(),
+            Event::Key(Key::Ctrl('u')) => {
+                line.clear();
+                cursor = 0;
+            }
+            Event::Key(Key::Char(c)) if !c.is_control() => {
+                line.insert(cursor, c);
+                cursor += 1;
+            }
+            _ => (),
+        }
+    }
+}
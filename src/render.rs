@@ -0,0 +1,358 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use mandelbrot::{
+    buddhabrot, gpu_iterates,
+    output::{Graphics, Output, Protocol},
+    tiles::TileCache,
+    Cell, Frame, Viewport,
+};
+
+use crate::{render_pixels, Backend, Message, Settings};
+
+/// What a render shows besides its escape results.
+pub enum Picture {
+    /// Nothing: text frames are shown from their escape results.
+    None,
+    /// Colored cells of text without escape results, as of the Buddhabrot.
+    Cells(Vec<Cell>),
+    /// An image encoded for the graphics backend.
+    Image(String),
+}
+
+/// Graphics protocol of the image backends; `None` for text.
+fn protocol(backend: Backend) -> Option<Protocol> {
+    match backend {
+        Backend::Text => None,
+        Backend::Sixel => Some(Protocol::Sixel),
+        Backend::Kitty => Some(Protocol::Kitty),
+        Backend::Iterm => Some(Protocol::Iterm),
+    }
+}
+
+/// Viewport and pixel size of images drawn over `viewport`, which takes up
+/// `size` pixels of the terminal if it tells.
+fn image_viewport(viewport: &Viewport, size: Option<(u32, u32)>) -> (Viewport, u32, u32) {
+    let (width, height) = size.unwrap_or((viewport.width as u32 * 10, viewport.height as u32 * 20));
+    (viewport.clone(), width, height)
+}
+
+/// Colors `frame` and encodes it for the current image backend. Text frames
+/// have no output of their own: the canvas is drawn from their cells.
+pub fn frame_output(frame: &Frame, settings: &Settings) -> Picture {
+    let Some(protocol) = protocol(settings.backend) else {
+        return Picture::None;
+    };
+    let mut image = Graphics {
+        protocol,
+        writer: Vec::new(),
+    };
+    image.render(frame, &settings.style).unwrap();
+    Picture::Image(String::from_utf8(image.writer).unwrap())
+}
+
+fn buddhabrot_output(
+    viewport: &Viewport,
+    settings: &Settings,
+    image_size: Option<(u32, u32)>,
+) -> Picture {
+    match protocol(settings.backend) {
+        None => Picture::Cells(buddhabrot::render_to_cells(
+            viewport,
+            &settings.params,
+            settings.mode,
+            viewport.width as u64 * viewport.height as u64 * settings.samples,
+        )),
+        Some(protocol) => {
+            let (viewport, width, height) = image_viewport(viewport, image_size);
+            let pixels = render_pixels(&viewport, settings, width, height);
+            Picture::Image(protocol.encode(width as usize, height as usize, &pixels))
+        }
+    }
+}
+
+/// Rows of cells iterated at a time by full-resolution renders, each band
+/// reported as soon as it is done.
+const BAND_ROWS: u16 = 8;
+
+/// Called as full-resolution renders go with the fraction of the frame done
+/// so far, and the first row and cells of the band of rows just done if it
+/// can be drawn on its own, as text can.
+pub type Progress<'a> = dyn FnMut(f64, Option<(u16, Frame)>) + 'a;
+
+/// Renders the cells of the view band by band, passing each to `progress`,
+/// and taking samples from `cache` if one is given.
+fn render_bands(
+    viewport: &Viewport,
+    settings: &Settings,
+    cache: Option<&Mutex<TileCache>>,
+    cancel: &AtomicBool,
+    progress: &mut Progress,
+) -> Option<Frame> {
+    let (params, cells) = (&settings.params, settings.cells);
+    let mut cache = cache.map(|cache| cache.lock().unwrap());
+    let mut frame: Option<Frame> = None;
+
+    for y in (0..viewport.height).step_by(BAND_ROWS as usize) {
+        let rows = y..(y + BAND_ROWS).min(viewport.height);
+        let done = rows.end as f64 / viewport.height as f64;
+        let part =
+            Frame::try_from_rows(viewport, params, cells, rows, cache.as_deref_mut(), cancel)?;
+        progress(done, Some((y, part.clone())));
+        match &mut frame {
+            Some(frame) => frame.extend(part),
+            None => frame = Some(part),
+        }
+    }
+    frame
+}
+
+/// Like [`render_bands`], but for a `width * height` image of the view,
+/// whose bands can only be drawn once they are all done.
+fn render_pixel_bands(
+    viewport: &Viewport,
+    settings: &Settings,
+    (width, height): (u32, u32),
+    cache: &Mutex<TileCache>,
+    cancel: &AtomicBool,
+    progress: &mut Progress,
+) -> Option<Frame> {
+    let cache = &mut cache.lock().unwrap();
+    let band = (height / viewport.height.max(1) as u32).max(1) * BAND_ROWS as u32;
+    let mut frame: Option<Frame> = None;
+
+    for y in (0..height).step_by(band as usize) {
+        let rows = y..(y + band).min(height);
+        let done = rows.end as f64 / height as f64;
+        let size = (width, height);
+        let part = Frame::try_from_pixel_rows(
+            viewport,
+            &settings.params,
+            size,
+            rows,
+            Some(cache),
+            cancel,
+        )?;
+        progress(done, None);
+        match &mut frame {
+            Some(frame) => frame.extend(part),
+            None => frame = Some(part),
+        }
+    }
+    frame
+}
+
+/// Renders the view for the current backend, sampling every `step`-th cell or
+/// pixel, along with its escape results unless they can't be recolored, as
+/// for the Buddhabrot. Full-resolution renders reuse the results in `cache`
+/// and report how far they got to `progress`. Images take `image_size` pixels
+/// if the terminal tells. Returns `None` if `cancel` was set before the render
+/// finished.
+pub fn render(
+    viewport: &Viewport,
+    settings: &Settings,
+    image_size: Option<(u32, u32)>,
+    step: usize,
+    cache: &Mutex<TileCache>,
+    cancel: &AtomicBool,
+    progress: &mut Progress,
+) -> Option<(Option<Frame>, Picture)> {
+    if settings.buddhabrot {
+        return Some((None, buddhabrot_output(viewport, settings, image_size)));
+    }
+
+    let params = &settings.params;
+    // The GPU iterates whole frames faster than the cache fills them.
+    let cached = !gpu_iterates(viewport, params);
+    let frame = match settings.backend {
+        Backend::Text if step == 1 => {
+            let cache = cached.then_some(cache);
+            render_bands(viewport, settings, cache, cancel, progress)?
+        }
+        Backend::Text => Frame::try_from_cells(viewport, params, settings.cells, step, cancel)?,
+        _ => {
+            let (viewport, width, height) = image_viewport(viewport, image_size);
+            if step == 1 && cached {
+                let size = (width, height);
+                render_pixel_bands(&viewport, settings, size, cache, cancel, progress)?
+            } else {
+                Frame::try_from_pixels(&viewport, params, width, height, step, cancel)?
+            }
+        }
+    };
+    let picture = frame_output(&frame, settings);
+    Some((Some(frame), picture))
+}
+
+/// Steps between sampled cells of the passes of a progressive render, from a
+/// quick blocky pass to full resolution.
+const PASSES: &[usize] = &[4, 2, 1];
+
+/// A finished render pass, sent back to the event loop.
+pub struct Rendered {
+    generation: u64,
+    /// Whether this is the full-resolution pass, ending the render.
+    pub last: bool,
+    /// Escape results of the frame, or of the band of rows it is a part of.
+    pub frame: Option<Frame>,
+    /// What the pass shows besides `frame`.
+    pub picture: Picture,
+    /// Fraction of the pass done, if this only reports how far it got.
+    pub progress: Option<f64>,
+    /// First row of the band of rows `frame` covers, if it is a part of a
+    /// pass to draw over the previous one as it comes in.
+    pub band: Option<u16>,
+    /// Time since the render started.
+    pub elapsed: Duration,
+}
+
+/// Runs renders on a background thread, so input is handled while they run
+/// and can cancel them.
+pub struct Renderer {
+    sender: Sender<Message>,
+    generation: u64,
+    cancel: Arc<AtomicBool>,
+    cache: Arc<Mutex<TileCache>>,
+    /// Whether the latest render hasn't finished yet.
+    pub busy: bool,
+    /// Pixel size of the part of the terminal the view takes up, if it
+    /// tells, for rendering images.
+    pub image_size: Option<(u32, u32)>,
+}
+
+impl Renderer {
+    /// A renderer caching about `cache_size` bytes of escape results.
+    pub fn new(sender: Sender<Message>, cache_size: usize) -> Self {
+        Renderer {
+            sender,
+            generation: 0,
+            cancel: Arc::new(AtomicBool::new(false)),
+            cache: Arc::new(Mutex::new(TileCache::new(cache_size))),
+            busy: false,
+            image_size: None,
+        }
+    }
+
+    /// Cancels the running render, if any, and starts rendering `viewport`
+    /// progressively, refining it pass by pass.
+    pub fn start(&mut self, viewport: &Viewport, settings: &Settings) {
+        // The Buddhabrot scatters orbits rather than sampling cells.
+        let passes = if settings.buddhabrot { &[1] } else { PASSES };
+        let (viewport, settings, size) = (viewport.clone(), settings.clone(), self.image_size);
+        self.spawn(passes, move |step, cache, cancel, progress| {
+            render(&viewport, &settings, size, step, cache, cancel, progress)
+        });
+    }
+
+    /// Like [`Renderer::start`], but renders at full resolution in one pass,
+    /// for frames that are replaced right away.
+    pub fn start_once(&mut self, viewport: &Viewport, settings: &Settings) {
+        let (viewport, settings, size) = (viewport.clone(), settings.clone(), self.image_size);
+        self.spawn(&[1], move |step, cache, cancel, _| {
+            render(
+                &viewport,
+                &settings,
+                size,
+                step,
+                cache,
+                cancel,
+                &mut |_, _| (),
+            )
+        });
+    }
+
+    /// Like [`Renderer::start`], but for a `viewport` panned by whole cells
+    /// from the one of `frame`: its samples are moved by `shift` and only
+    /// the cells coming into view are iterated.
+    pub fn start_shifted(
+        &mut self,
+        viewport: &Viewport,
+        settings: &Settings,
+        frame: Frame,
+        shift: (isize, isize),
+    ) {
+        let (viewport, settings) = (viewport.clone(), settings.clone());
+        self.spawn(&[1], move |_, _, cancel, _| {
+            let frame = frame.try_shifted(&viewport, &settings.params, shift, cancel)?;
+            let picture = frame_output(&frame, &settings);
+            Some((Some(frame), picture))
+        });
+    }
+
+    /// Runs `pass` with each of `passes` on a new thread, sending back what
+    /// they render, and the progress they report before they are done.
+    fn spawn(
+        &mut self,
+        passes: &'static [usize],
+        pass: impl Fn(
+                usize,
+                &Mutex<TileCache>,
+                &AtomicBool,
+                &mut Progress,
+            ) -> Option<(Option<Frame>, Picture)>
+            + Send
+            + 'static,
+    ) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        self.generation += 1;
+        self.busy = true;
+
+        let (sender, cancel, cache, generation) = (
+            self.sender.clone(),
+            self.cancel.clone(),
+            self.cache.clone(),
+            self.generation,
+        );
+        thread::spawn(move || {
+            let started = Instant::now();
+            for (i, &step) in passes.iter().enumerate() {
+                let mut progress = |done, band: Option<(u16, Frame)>| {
+                    let (row, part) = band.unzip();
+                    let rendered = Rendered {
+                        generation,
+                        last: false,
+                        frame: part,
+                        picture: Picture::None,
+                        progress: Some(done),
+                        band: row,
+                        elapsed: started.elapsed(),
+                    };
+                    let _ = sender.send(Message::Rendered(rendered));
+                };
+                let Some((frame, picture)) = pass(step, &cache, &cancel, &mut progress) else {
+                    return;
+                };
+                let rendered = Rendered {
+                    generation,
+                    last: i + 1 == passes.len(),
+                    frame,
+                    picture,
+                    progress: None,
+                    band: None,
+                    elapsed: started.elapsed(),
+                };
+                if sender.send(Message::Rendered(rendered)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    /// Whether `rendered` is the latest render; older ones were superseded
+    /// and are dropped.
+    pub fn accept(&mut self, rendered: &Rendered) -> bool {
+        let latest = rendered.generation == self.generation;
+        if latest && rendered.last {
+            self.busy = false;
+        }
+        latest
+    }
+}
@@ -0,0 +1,144 @@
+use std::{error::Error, fs, path::Path, sync::Arc};
+
+use mandelbrot::{
+    formula::Formula, Bounds, CellMode, ColorMode, Coloring, Fractal, Location, Viewport, C,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bookmarks::{decode_origin, encode_origin},
+    cli::{self, FractalKind},
+    Backend, Settings,
+};
+
+/// File sessions are saved to and opened from when no other is given.
+pub const DEFAULT_PATH: &str = "mandelbrot-session.toml";
+
+/// Everything that decides what is on screen, to come back to exactly.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    /// Bounds of the view, relative to `origin`.
+    pub bounds: Bounds,
+    /// Decimal coordinates of the origin of deep views, as for bookmarks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<[String; 2]>,
+    #[serde(default)]
+    pub rotation: f64,
+    /// Fractal shown, unless `formula` is given.
+    pub fractal: FractalKind,
+    /// Formula iterated instead of `fractal`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formula: Option<String>,
+    /// Constant of the Julia set, kept while other fractals are shown.
+    pub julia: (f64, f64),
+    pub exponent: f64,
+    pub iterations: u32,
+    /// Whether the iteration limit follows the magnification.
+    pub auto_iterations: bool,
+    /// Name of the palette.
+    pub palette: String,
+    pub coloring: Coloring,
+    #[serde(default)]
+    pub offset: f64,
+    pub mode: ColorMode,
+    pub cells: CellMode,
+    pub backend: Backend,
+    pub buddhabrot: bool,
+}
+
+impl Session {
+    pub fn new(viewport: &Viewport, settings: &Settings) -> Self {
+        let params = &settings.params;
+        let fractal = match params.fractal {
+            Fractal::Julia(_) => FractalKind::Julia,
+            Fractal::BurningShip => FractalKind::BurningShip,
+            Fractal::Tricorn => FractalKind::Tricorn,
+            Fractal::Newton => FractalKind::Newton,
+            // Formulas are saved on their own, and the binary has no plugins.
+            _ => FractalKind::Mandelbrot,
+        };
+        let formula = match &params.fractal {
+            Fractal::Formula(formula) => Some(formula.source().to_string()),
+            _ => None,
+        };
+
+        Session {
+            bounds: viewport.bounds(),
+            origin: encode_origin(&viewport.origin),
+            rotation: viewport.rotation,
+            fractal,
+            formula,
+            julia: (settings.julia_c.re, settings.julia_c.im),
+            exponent: params.exponent,
+            iterations: params.max_iterations,
+            auto_iterations: settings.auto_iterations,
+            palette: settings.style.palette.name.clone(),
+            coloring: settings.style.coloring,
+            offset: settings.style.offset,
+            mode: settings.mode,
+            cells: settings.cells,
+            backend: settings.backend,
+            buddhabrot: settings.buddhabrot,
+        }
+    }
+
+    /// Where the saved view looks, or `None` if its origin is malformed.
+    pub fn location(&self) -> Option<Location> {
+        let origin = decode_origin(&self.origin)?;
+        Some(Location::new(origin, self.bounds, self.rotation))
+    }
+
+    /// Puts the view and settings back as they were saved. Fails without
+    /// changing either if the session can't be restored.
+    pub fn restore(&self, viewport: &mut Viewport, settings: &mut Settings) -> Result<(), String> {
+        let location = self.location().ok_or("session has a malformed origin")?;
+        let exponent = cli::check_exponent(self.exponent)?;
+        let palette = settings
+            .palettes
+            .iter()
+            .position(|p| p.name == self.palette)
+            .ok_or_else(|| format!("no palette `{}`", self.palette))?;
+        let formula = match &self.formula {
+            Some(source) => Some(Arc::new(
+                Formula::parse(source).map_err(|e| format!("formula `{}`: {}", source, e))?,
+            )),
+            None => None,
+        };
+
+        viewport.set_location(location);
+
+        settings.julia_c = C::from(self.julia);
+        settings.params.fractal = match (&formula, self.fractal) {
+            (Some(formula), _) => Fractal::Formula(formula.clone()),
+            (None, FractalKind::Mandelbrot) => Fractal::Mandelbrot,
+            (None, FractalKind::Julia) => Fractal::Julia(settings.julia_c),
+            (None, FractalKind::BurningShip) => Fractal::BurningShip,
+            (None, FractalKind::Tricorn) => Fractal::Tricorn,
+            (None, FractalKind::Newton) => Fractal::Newton,
+        };
+        if formula.is_some() {
+            settings.formula = formula;
+        }
+        settings.params.exponent = exponent;
+        settings.params.max_iterations = self.iterations;
+        settings.auto_iterations = self.auto_iterations;
+        settings.palette = palette;
+        settings.style.palette = settings.palettes[palette].clone();
+        settings.set_coloring(self.coloring);
+        settings.style.offset = self.offset;
+        settings.mode = self.mode;
+        settings.cells = self.cells;
+        settings.backend = self.backend;
+        settings.buddhabrot = self.buddhabrot;
+        Ok(())
+    }
+}
+
+pub fn load(path: &Path) -> Result<Session, Box<dyn Error>> {
+    Ok(toml::from_str(&fs::read_to_string(path)?)?)
+}
+
+pub fn save(path: &Path, session: &Session) -> Result<(), Box<dyn Error>> {
+    fs::write(path, toml::to_string(session)?)?;
+    Ok(())
+}
@@ -0,0 +1,189 @@
+//! Escape-time iteration of several points at once, one per SIMD lane.
+//! Lanes whose orbit escapes or falls into a cycle are masked out, and the
+//! points are iterated until none is left.
+//!
+//! Only the quadratic sets without orbit traps or distance estimates are
+//! vectorized; every lane computes exactly what the scalar iteration does,
+//! in f64 or, twice as many at once, in f32.
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+use wide::{f32x8, f64x4};
+
+use crate::{
+    fractal::{in_main_bulbs, smooth_iterations, PERIOD_TOLERANCE},
+    iterate, EscapeResult, Fractal, Params, Precision, C,
+};
+
+/// Points iterated together in f64.
+pub const LANES: usize = 4;
+/// Most points any vector iterates together.
+const MAX_LANES: usize = 8;
+
+/// A vector of floats the kernel iterates points in, one per lane.
+trait Lanes:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+    const LANES: usize;
+
+    fn splat(x: f64) -> Self;
+    /// The vector of `f(lane)` in each lane.
+    fn from_fn(f: impl FnMut(usize) -> f64) -> Self;
+    fn lane(self, lane: usize) -> f64;
+    fn abs(self) -> Self;
+    /// Bit mask of the lanes where `self > other`.
+    fn gt(self, other: Self) -> u32;
+    /// Bit mask of the lanes where `self < other`.
+    fn lt(self, other: Self) -> u32;
+}
+
+macro_rules! impl_lanes {
+    ($vector:ty, $float:ty, $lanes:expr) => {
+        impl Lanes for $vector {
+            const LANES: usize = $lanes;
+
+            fn splat(x: f64) -> Self {
+                <$vector>::splat(x as $float)
+            }
+
+            fn from_fn(mut f: impl FnMut(usize) -> f64) -> Self {
+                <$vector>::from(std::array::from_fn::<$float, $lanes, _>(|lane| {
+                    f(lane) as $float
+                }))
+            }
+
+            fn lane(self, lane: usize) -> f64 {
+                self.to_array()[lane] as f64
+            }
+
+            fn abs(self) -> Self {
+                <$vector>::abs(self)
+            }
+
+            fn gt(self, other: Self) -> u32 {
+                self.simd_gt(other).to_bitmask()
+            }
+
+            fn lt(self, other: Self) -> u32 {
+                self.simd_lt(other).to_bitmask()
+            }
+        }
+    };
+}
+
+impl_lanes!(f64x4, f64, LANES);
+impl_lanes!(f32x8, f32, 8);
+
+/// Whether the vector kernel iterates the points of `params`.
+fn supported(params: &Params) -> bool {
+    let fractal = matches!(
+        params.fractal,
+        Fractal::Mandelbrot | Fractal::Julia(_) | Fractal::BurningShip | Fractal::Tricorn
+    );
+    fractal && params.exponent == 2. && params.trap.is_none() && !params.distance_estimate
+}
+
+/// Iterates the points `c` of the plane together, with the same results as
+/// [`iterate`] gives each of them.
+pub fn iterate_lanes(c: [C; LANES], params: &Params) -> [EscapeResult; LANES] {
+    if !supported(params) {
+        return c.map(|c| iterate(c, params));
+    }
+
+    let escaped = escape_lanes::<f64x4>(&c, params);
+    std::array::from_fn(|lane| result(escaped[lane], params))
+}
+
+fn result(escaped: Option<(u32, C)>, params: &Params) -> EscapeResult {
+    match escaped {
+        Some((i, z)) => EscapeResult {
+            iterations: Some(i),
+            smooth: smooth_iterations(i, z, params),
+            ..EscapeResult::default()
+        },
+        None => EscapeResult::default(),
+    }
+}
+
+/// Iterates all of `points`, a vector of lanes at a time, in f32 if
+/// `precision` asks for it and f64 otherwise.
+pub fn iterate_all(points: &[C], params: &Params, precision: Precision) -> Vec<EscapeResult> {
+    if !supported(params) {
+        return points.iter().map(|&c| iterate(c, params)).collect();
+    }
+
+    match precision {
+        Precision::F32 => iterate_vectors::<f32x8>(points, params),
+        _ => iterate_vectors::<f64x4>(points, params),
+    }
+}
+
+fn iterate_vectors<V: Lanes>(points: &[C], params: &Params) -> Vec<EscapeResult> {
+    let mut results = Vec::with_capacity(points.len());
+    for chunk in points.chunks(V::LANES) {
+        // Pad the last chunk with copies of its last point.
+        let lanes: [C; MAX_LANES] = std::array::from_fn(|lane| chunk[lane.min(chunk.len() - 1)]);
+        let escaped = escape_lanes::<V>(&lanes, params);
+        results.extend(escaped[..chunk.len()].iter().map(|&e| result(e, params)));
+    }
+    results
+}
+
+/// Escape times and escaped iterates of the `V::LANES` points `c`.
+fn escape_lanes<V: Lanes>(c: &[C], params: &Params) -> [Option<(u32, C)>; MAX_LANES] {
+    let (cr, ci) = (V::from_fn(|lane| c[lane].re), V::from_fn(|lane| c[lane].im));
+    let (mut zr, mut zi, kr, ki) = match params.fractal {
+        Fractal::Julia(k) => (cr, ci, V::splat(k.re), V::splat(k.im)),
+        _ => (V::splat(0.), V::splat(0.), cr, ci),
+    };
+    let (cutoff, tolerance) = (V::splat(params.cutoff), V::splat(PERIOD_TOLERANCE));
+
+    let mut escaped = [None; MAX_LANES];
+    // Lanes still iterating, as a bit mask.
+    let mut active: u32 = (1 << V::LANES) - 1;
+    for (lane, &c) in c[..V::LANES].iter().enumerate() {
+        if in_main_bulbs(c, params) {
+            active &= !(1 << lane);
+        }
+    }
+
+    let mut i = 0;
+    let (mut sr, mut si, mut period, mut limit) = (zr, zi, 0, 1);
+    while active != 0 {
+        let out = (zi * zi + zr * zr).gt(cutoff) & active;
+        if out != 0 {
+            for (lane, escaped) in escaped[..V::LANES].iter_mut().enumerate() {
+                if out & 1 << lane != 0 {
+                    *escaped = Some((
+                        i,
+                        C {
+                            re: zr.lane(lane),
+                            im: zi.lane(lane),
+                        },
+                    ));
+                }
+            }
+            active &= !out;
+        }
+
+        if i > params.max_iterations {
+            break;
+        }
+
+        let (xr, xi) = match params.fractal {
+            Fractal::BurningShip => (zr.abs(), zi.abs()),
+            Fractal::Tricorn => (zr, -zi),
+            _ => (zr, zi),
+        };
+        (zr, zi) = (xr * xr - xi * xi + kr, xr * xi + xi * xr + ki);
+        i += 1;
+
+        let (dr, di) = (zr - sr, zi - si);
+        active &= !(di * di + dr * dr).lt(tolerance);
+        period += 1;
+        if period == limit {
+            (sr, si, period, limit) = (zr, zi, 0, limit * 2);
+        }
+    }
+    escaped
+}
@@ -0,0 +1,64 @@
+//! Encoder for DEC sixel graphics.
+
+use std::fmt::Write;
+
+// Pixels are quantized to a 6x6x6 color cube so every image fits in 216
+// palette registers.
+fn register(pixel: [u8; 3]) -> usize {
+    let q = |v: u8| (v as usize * 5 + 127) / 255;
+    q(pixel[0]) * 36 + q(pixel[1]) * 6 + q(pixel[2])
+}
+
+fn push_run(out: &mut String, sixel: u8, run: usize) {
+    let c = (63 + sixel) as char;
+    if run > 3 {
+        write!(out, "!{}{}", run, c).unwrap();
+    } else {
+        out.extend(std::iter::repeat_n(c, run));
+    }
+}
+
+/// Encodes a row-major `width * height` RGB image as a sixel escape sequence.
+pub fn encode(width: usize, height: usize, pixels: &[[u8; 3]]) -> String {
+    let registers: Vec<usize> = pixels.iter().map(|&p| register(p)).collect();
+    let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+
+    for i in 0..216 {
+        let (r, g, b) = (i / 36, i / 6 % 6, i % 6);
+        write!(out, "#{};2;{};{};{}", i, r * 20, g * 20, b * 20).unwrap();
+    }
+
+    for band in (0..height).step_by(6) {
+        let rows = band..(band + 6).min(height);
+        let mut used = [false; 216];
+        for y in rows.clone() {
+            for &reg in &registers[y * width..(y + 1) * width] {
+                used[reg] = true;
+            }
+        }
+
+        for reg in (0..216).filter(|&reg| used[reg]) {
+            write!(out, "#{}", reg).unwrap();
+
+            let mut run = (0, 0);
+            for x in 0..width {
+                let sixel = rows
+                    .clone()
+                    .filter(|y| registers[y * width + x] == reg)
+                    .fold(0, |bits, y| bits | 1 << (y - band));
+
+                if sixel != run.0 {
+                    push_run(&mut out, run.0, run.1);
+                    run = (sixel, 0);
+                }
+                run.1 += 1;
+            }
+            push_run(&mut out, run.0, run.1);
+            out.push('$');
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
@@ -0,0 +1,152 @@
+//! Mariani–Silver subdivision: as the set is connected, a rectangle whose
+//! border lies entirely in it holds no escaping points, so its inside is
+//! filled without being iterated. Other rectangles are split in two and
+//! handled the same way.
+//!
+//! Only rectangles bounded by points that don't escape are filled. Escaping
+//! points are cheap, and their smooth values and orbit traps vary even
+//! where their iteration counts agree.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rayon::prelude::*;
+
+use crate::EscapeResult;
+
+/// Side of the squares the grid is cut into to spread them over threads.
+pub(crate) const BLOCK: usize = 32;
+/// Rectangles narrower than this are iterated point by point.
+const MIN_SIZE: usize = 4;
+/// Difference in smooth escape time between neighbouring points above which
+/// they lie on an edge.
+const EDGE_CONTRAST: f64 = 1.;
+
+/// A rectangle `(x, y, width, height)` of points, including its border.
+type Rect = (usize, usize, usize, usize);
+
+/// Escape results of the `width * height` grid of points `sample` iterates,
+/// row-major, passing it the points to iterate in batches. Returns `None`
+/// once `cancel` is set.
+pub(crate) fn sample_grid(
+    (width, height): (usize, usize),
+    cancel: &AtomicBool,
+    sample: impl Fn(&[(usize, usize)]) -> Vec<EscapeResult> + Sync,
+) -> Option<Vec<EscapeResult>> {
+    let blocks: Vec<(usize, usize)> = (0..height)
+        .step_by(BLOCK)
+        .flat_map(|y| (0..width).step_by(BLOCK).map(move |x| (x, y)))
+        .collect();
+
+    let blocks: Vec<(Rect, Vec<EscapeResult>)> = blocks
+        .into_par_iter()
+        .map(|(x, y)| {
+            (!cancel.load(Ordering::Relaxed)).then(|| {
+                let (w, h) = (BLOCK.min(width - x), BLOCK.min(height - y));
+                let mut block = vec![None; w * h];
+                let sample = |points: &[(usize, usize)]| {
+                    let points: Vec<_> = points.iter().map(|&(i, j)| (x + i, y + j)).collect();
+                    sample(&points)
+                };
+                subdivide(&mut block, w, (0, 0, w, h), &sample);
+                let block = block.into_iter().map(Option::unwrap_or_default).collect();
+                ((x, y, w, h), block)
+            })
+        })
+        .collect::<Option<_>>()?;
+
+    let mut grid = vec![EscapeResult::default(); width * height];
+    for ((x, y, w, _), block) in blocks {
+        for (j, row) in block.chunks(w).enumerate() {
+            grid[(y + j) * width + x..][..w].copy_from_slice(row);
+        }
+    }
+    Some(grid)
+}
+
+/// Iterates those of `points` that haven't been yet, all in one batch.
+fn fill(
+    grid: &mut [Option<EscapeResult>],
+    stride: usize,
+    points: impl Iterator<Item = (usize, usize)>,
+    sample: &impl Fn(&[(usize, usize)]) -> Vec<EscapeResult>,
+) {
+    let missing: Vec<(usize, usize)> = points
+        .filter(|&(i, j)| grid[j * stride + i].is_none())
+        .collect();
+    for (&(i, j), result) in missing.iter().zip(sample(&missing)) {
+        grid[j * stride + i] = Some(result);
+    }
+}
+
+fn subdivide(
+    grid: &mut [Option<EscapeResult>],
+    stride: usize,
+    (x, y, w, h): Rect,
+    sample: &impl Fn(&[(usize, usize)]) -> Vec<EscapeResult>,
+) {
+    if w < MIN_SIZE || h < MIN_SIZE {
+        let points = (y..y + h).flat_map(|j| (x..x + w).map(move |i| (i, j)));
+        fill(grid, stride, points, sample);
+        return;
+    }
+
+    let (right, bottom) = (x + w - 1, y + h - 1);
+    let rows = [y, bottom].into_iter();
+    let columns = [x, right].into_iter();
+    let mut border = rows
+        .flat_map(|j| (x..=right).map(move |i| (i, j)))
+        .chain(columns.flat_map(|i| (y + 1..bottom).map(move |j| (i, j))));
+    fill(grid, stride, border.clone(), sample);
+
+    let first = grid[y * stride + x].unwrap();
+    let uniform =
+        first.iterations.is_none() && border.all(|(i, j)| grid[j * stride + i] == Some(first));
+
+    if uniform {
+        for j in y + 1..bottom {
+            grid[j * stride + x + 1..j * stride + right].fill(Some(first));
+        }
+    } else if w >= h {
+        // The halves share the column or row they are split along.
+        let mid = w / 2;
+        subdivide(grid, stride, (x, y, mid + 1, h), sample);
+        subdivide(grid, stride, (x + mid, y, w - mid, h), sample);
+    } else {
+        let mid = h / 2;
+        subdivide(grid, stride, (x, y, w, mid + 1), sample);
+        subdivide(grid, stride, (x, y + mid, w, h - mid), sample);
+    }
+}
+
+/// Whether neighbouring samples `a` and `b` lie on different sides of an
+/// edge: the boundary of the set, of a Newton basin, or a steep slope of
+/// escape times.
+fn contrasts(a: &EscapeResult, b: &EscapeResult) -> bool {
+    match (a.iterations, b.iterations) {
+        (Some(_), Some(_)) => a.root != b.root || (a.smooth - b.smooth).abs() > EDGE_CONTRAST,
+        (None, None) => false,
+        _ => true,
+    }
+}
+
+/// Points of the row-major `width * height` grid that contrast with one of
+/// their neighbours along the axes.
+pub(crate) fn edges(grid: &[EscapeResult], (width, height): (usize, usize)) -> Vec<(usize, usize)> {
+    let at = |x: usize, y: usize| &grid[y * width + x];
+
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| {
+            let neighbours = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            neighbours
+                .into_iter()
+                .filter(|&(nx, ny)| nx < width && ny < height)
+                .any(|(nx, ny)| contrasts(at(x, y), at(nx, ny)))
+        })
+        .collect()
+}
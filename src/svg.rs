@@ -0,0 +1,161 @@
+//! SVG line art of the contours between iteration bands, traced by marching
+//! squares over the escape values of an image.
+
+use std::{collections::HashMap, fmt::Write};
+
+use crate::EscapeResult;
+
+/// Level of the contour around the set itself, between the points that
+/// escape and those that don't.
+pub const BOUNDARY: f64 = f64::INFINITY;
+
+/// `count` contour levels: escape values spread geometrically from 2 up to
+/// `max_iterations`, and last the [`BOUNDARY`].
+pub fn levels(max_iterations: u32, count: usize) -> Vec<f64> {
+    let bands = count.saturating_sub(1);
+    let max = (max_iterations as f64).max(2.);
+    let mut levels: Vec<f64> = (0..bands)
+        .map(|i| 2. * (max / 2.).powf((i + 1) as f64 / (bands + 1) as f64))
+        .collect();
+    levels.push(BOUNDARY);
+    levels
+}
+
+/// Encodes the contours at `levels` of a row-major `width * height` image of
+/// escape results as an SVG document, a path per level.
+pub fn encode(width: usize, height: usize, results: &[EscapeResult], levels: &[f64]) -> String {
+    let field: Vec<f64> = results
+        .iter()
+        .map(|r| match r.iterations {
+            Some(_) => r.smooth,
+            None => BOUNDARY,
+        })
+        .collect();
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+        w = width,
+        h = height
+    );
+    for &level in levels {
+        let lines = trace(width, height, &field, level);
+        if lines.is_empty() {
+            continue;
+        }
+        svg.push_str("<path fill=\"none\" stroke=\"black\" stroke-width=\"1\" d=\"");
+        for line in lines {
+            for (i, (x, y)) in line.iter().enumerate() {
+                let command = if i == 0 { 'M' } else { 'L' };
+                write!(svg, "{}{:.2} {:.2}", command, x, y).unwrap();
+            }
+        }
+        svg.push_str("\"/>\n");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Edge of the grid between two samples, by its first sample and whether it
+/// runs down rather than across.
+type Edge = (usize, usize, bool);
+
+/// Lines along which `field` crosses `level`, with samples at the centers
+/// of the pixels.
+fn trace(width: usize, height: usize, field: &[f64], level: f64) -> Vec<Vec<(f64, f64)>> {
+    let value = |x: usize, y: usize| field[y * width + x];
+    let above = |x: usize, y: usize| value(x, y) >= level;
+    // Where the field crosses the level along an edge.
+    let point = |(x, y, down): Edge| {
+        let (x1, y1) = if down { (x, y + 1) } else { (x + 1, y) };
+        let (a, b) = (value(x, y), value(x1, y1));
+        let t = match (a.is_finite() && b.is_finite(), b - a) {
+            (true, d) if d != 0. => ((level - a) / d).clamp(0., 1.),
+            _ => 0.5,
+        };
+        let (x0, y0) = (x as f64 + 0.5, y as f64 + 0.5);
+        match down {
+            true => (x0, y0 + t),
+            false => (x0 + t, y0),
+        }
+    };
+
+    let mut segments: Vec<(Edge, Edge)> = Vec::new();
+    for y in 0..height.saturating_sub(1) {
+        for x in 0..width.saturating_sub(1) {
+            let case = (above(x, y) as u8) << 3
+                | (above(x + 1, y) as u8) << 2
+                | (above(x + 1, y + 1) as u8) << 1
+                | above(x, y + 1) as u8;
+            let (top, right, bottom, left) = (
+                (x, y, false),
+                (x + 1, y, true),
+                (x, y + 1, false),
+                (x, y, true),
+            );
+            // The middle of the square, to tell the two ways of joining
+            // opposite corners apart.
+            let center = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)]
+                .iter()
+                .map(|&(x, y)| value(x, y).min(f64::MAX))
+                .sum::<f64>()
+                / 4.
+                >= level;
+            match case {
+                0 | 15 => (),
+                1 | 14 => segments.push((left, bottom)),
+                2 | 13 => segments.push((bottom, right)),
+                3 | 12 => segments.push((left, right)),
+                4 | 11 => segments.push((top, right)),
+                6 | 9 => segments.push((top, bottom)),
+                7 | 8 => segments.push((left, top)),
+                5 if center => segments.extend([(left, top), (bottom, right)]),
+                5 => segments.extend([(left, bottom), (top, right)]),
+                10 if center => segments.extend([(left, bottom), (top, right)]),
+                _ => segments.extend([(left, top), (bottom, right)]),
+            }
+        }
+    }
+
+    join(&segments)
+        .into_iter()
+        .map(|edges| edges.into_iter().map(point).collect())
+        .collect()
+}
+
+/// Chains segments sharing edges into lines, closed ones ending where they
+/// start.
+fn join(segments: &[(Edge, Edge)]) -> Vec<Vec<Edge>> {
+    let mut at: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (i, &(a, b)) in segments.iter().enumerate() {
+        at.entry(a).or_default().push(i);
+        at.entry(b).or_default().push(i);
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut lines = Vec::new();
+    // Open lines are followed from their ends first, so they come out whole.
+    let ends = (0..segments.len()).filter(|&i| {
+        let (a, b) = segments[i];
+        at[&a].len() == 1 || at[&b].len() == 1
+    });
+    let order: Vec<usize> = ends.chain(0..segments.len()).collect();
+    for start in order {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let (a, b) = segments[start];
+        let (mut line, mut end) = match at[&a].len() {
+            1 => (vec![a, b], b),
+            _ => (vec![b, a], a),
+        };
+        while let Some(&next) = at[&end].iter().find(|&&i| !used[i]) {
+            used[next] = true;
+            let (a, b) = segments[next];
+            end = if a == end { b } else { a };
+            line.push(end);
+        }
+        lines.push(line);
+    }
+    lines
+}
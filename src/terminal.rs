@@ -0,0 +1,165 @@
+mod crossterm;
+#[cfg(unix)]
+mod termion;
+
+use std::{
+    io::{self, IsTerminal},
+    panic, process,
+};
+
+use mandelbrot::Color;
+
+use crate::cli::TerminalKind;
+
+/// A key typed, with Enter read as `Char('\n')` and Tab as `Char('\t')`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    Ctrl(char),
+    Alt(char),
+    F(u8),
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    BackTab,
+    Backspace,
+    Delete,
+    Insert,
+    Esc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    WheelUp,
+    WheelDown,
+}
+
+/// What the mouse did over the cell `(x, y)`, counted from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseEvent {
+    Press(MouseButton, u16, u16),
+    /// The pointer moved with a button held.
+    Hold(u16, u16),
+    Release(u16, u16),
+    /// The pointer moved with no button held.
+    Moved(u16, u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Key(Key),
+    Mouse(MouseEvent),
+}
+
+/// Events of a terminal, for reading on their own thread.
+pub type Events = Box<dyn Iterator<Item = io::Result<Event>> + Send>;
+
+/// Colors and attributes text is printed in, which are the terminal's own
+/// where the colors are `None`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pen {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    /// Whether the colors are swapped.
+    pub inverted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clear {
+    All,
+    /// The line the cursor is on.
+    Line,
+}
+
+/// A terminal the view runs in. Output is kept until [`Terminal::flush`],
+/// which reports what went wrong writing it.
+pub trait Terminal {
+    /// Switches to raw mode on the alternate screen, with the cursor hidden
+    /// and mouse and motion reports on, until [`Terminal::leave`]. Dropping
+    /// the terminal or a panic leave it too.
+    fn enter(&mut self) -> io::Result<()>;
+
+    /// Puts the terminal back as it was before [`Terminal::enter`].
+    fn leave(&mut self) -> io::Result<()>;
+
+    /// Events typed and clicked from now on.
+    fn events(&self) -> Events;
+
+    /// Size of the terminal in cells.
+    fn size(&self) -> io::Result<(u16, u16)>;
+
+    /// Size of the terminal in pixels, if it tells.
+    fn size_pixels(&self) -> Option<(u16, u16)>;
+
+    /// Moves the cursor to the cell `(x, y)`, counted from 1.
+    fn goto(&mut self, x: u16, y: u16);
+
+    fn show_cursor(&mut self, shown: bool);
+
+    fn clear(&mut self, clear: Clear);
+
+    /// Prints what follows with `pen`.
+    fn set_pen(&mut self, pen: Pen);
+
+    /// Prints `text` at the cursor.
+    fn print(&mut self, text: &str);
+
+    /// Writes escape sequences such as images as they are, after which the
+    /// pen is not known.
+    fn write_raw(&mut self, output: &str);
+
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+/// The terminal driven through `kind`, by default termion on Unix and
+/// crossterm elsewhere. It is set up once entered.
+pub fn open(kind: Option<TerminalKind>) -> io::Result<Box<dyn Terminal>> {
+    match kind {
+        #[cfg(unix)]
+        None | Some(TerminalKind::Termion) => Ok(Box::new(termion::Termion::new())),
+        #[cfg(not(unix))]
+        Some(TerminalKind::Termion) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "termion only drives terminals on Unix",
+        )),
+        #[cfg(not(unix))]
+        None => Ok(Box::new(crossterm::Crossterm::new())),
+        Some(TerminalKind::Crossterm) => Ok(Box::new(crossterm::Crossterm::new())),
+    }
+}
+
+/// Pen printing goes on from when `from` is changed to `to`, and whether
+/// it takes a reset: setting colors or inverting goes on from `from`, but
+/// taking them away starts over from the default pen.
+fn pen_change(from: Option<Pen>, to: Pen) -> (bool, Pen) {
+    let kept = from.filter(|from| {
+        (from.fg.is_none() || to.fg.is_some())
+            && (from.bg.is_none() || to.bg.is_some())
+            && (!from.inverted || to.inverted)
+    });
+    (kept.is_none(), kept.unwrap_or_default())
+}
+
+/// Has a panic call `restore` before its message is printed, and exit.
+fn restore_on_panic(restore: fn()) {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        hook(info);
+        // The event loop can't go on drawing on a restored terminal.
+        process::exit(101);
+    }));
+}
+
+/// Whether standard input and output are both a terminal.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
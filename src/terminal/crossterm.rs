@@ -0,0 +1,218 @@
+use std::{
+    io::{self, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use ::crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    style::{self, Attribute, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    Command,
+};
+use mandelbrot::Color;
+
+use super::{Clear, Event, Events, Key, MouseButton, MouseEvent, Pen, Terminal};
+
+/// Whether the terminal is set up for the event loop, until it is restored.
+static ENTERED: AtomicBool = AtomicBool::new(false);
+
+/// The terminal driven through crossterm, which also runs in the Windows
+/// console.
+pub struct Crossterm {
+    output: String,
+    /// Pen set last, if it is known.
+    pen: Option<Pen>,
+}
+
+impl Crossterm {
+    pub fn new() -> Self {
+        Crossterm {
+            output: String::new(),
+            pen: None,
+        }
+    }
+
+    fn queue(&mut self, command: impl Command) {
+        command.write_ansi(&mut self.output).unwrap();
+    }
+}
+
+impl Drop for Crossterm {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+impl Terminal for Crossterm {
+    fn enter(&mut self) -> io::Result<()> {
+        terminal::enable_raw_mode()?;
+        ENTERED.store(true, Ordering::Relaxed);
+        super::restore_on_panic(restore);
+        // Mouse capture reports motion with no button held too.
+        execute!(io::stdout(), EnterAlternateScreen, Hide, EnableMouseCapture)
+    }
+
+    fn leave(&mut self) -> io::Result<()> {
+        self.flush()?;
+        restore();
+        Ok(())
+    }
+
+    fn events(&self) -> Events {
+        Box::new(
+            std::iter::repeat_with(event::read).filter_map(|event| match event {
+                Ok(event) => convert(event).map(Ok),
+                Err(e) => Some(Err(e)),
+            }),
+        )
+    }
+
+    fn size(&self) -> io::Result<(u16, u16)> {
+        terminal::size()
+    }
+
+    fn size_pixels(&self) -> Option<(u16, u16)> {
+        terminal::window_size()
+            .ok()
+            .map(|size| (size.width, size.height))
+            .filter(|&(w, h)| w > 0 && h > 0)
+    }
+
+    fn goto(&mut self, x: u16, y: u16) {
+        self.queue(MoveTo(x.saturating_sub(1), y.saturating_sub(1)));
+    }
+
+    fn show_cursor(&mut self, shown: bool) {
+        match shown {
+            true => self.queue(Show),
+            false => self.queue(Hide),
+        }
+    }
+
+    fn clear(&mut self, clear: Clear) {
+        self.queue(terminal::Clear(match clear {
+            Clear::All => ClearType::All,
+            Clear::Line => ClearType::CurrentLine,
+        }));
+    }
+
+    fn set_pen(&mut self, pen: Pen) {
+        let (reset, from) = super::pen_change(self.pen, pen);
+        if reset {
+            self.queue(SetAttribute(Attribute::Reset));
+        }
+        if let Some(color) = pen.fg.filter(|_| pen.fg != from.fg) {
+            self.queue(SetForegroundColor(convert_color(color)));
+        }
+        if let Some(color) = pen.bg.filter(|_| pen.bg != from.bg) {
+            self.queue(SetBackgroundColor(convert_color(color)));
+        }
+        if pen.inverted && !from.inverted {
+            self.queue(SetAttribute(Attribute::Reverse));
+        }
+        self.pen = Some(pen);
+    }
+
+    fn print(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn write_raw(&mut self, output: &str) {
+        self.output.push_str(output);
+        self.pen = None;
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        stdout.write_all(self.output.as_bytes())?;
+        self.output.clear();
+        stdout.flush()
+    }
+}
+
+/// Puts the terminal back as it was, if it was set up.
+fn restore() {
+    if ENTERED.swap(false, Ordering::Relaxed) {
+        let _ = execute!(
+            io::stdout(),
+            DisableMouseCapture,
+            Show,
+            LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn convert_color(color: Color) -> style::Color {
+    match color {
+        Color::Ansi(v) => style::Color::AnsiValue(v),
+        Color::Rgb(r, g, b) => style::Color::Rgb { r, g, b },
+    }
+}
+
+/// `event` as the view reads it, if it reads it at all.
+fn convert(event: event::Event) -> Option<Event> {
+    match event {
+        // Some consoles report keys as they are released too.
+        event::Event::Key(key) if key.kind != KeyEventKind::Release => {
+            convert_key(key.code, key.modifiers).map(Event::Key)
+        }
+        event::Event::Mouse(mouse) => {
+            let (x, y) = (mouse.column + 1, mouse.row + 1);
+            Some(Event::Mouse(match mouse.kind {
+                event::MouseEventKind::Down(button) => {
+                    MouseEvent::Press(convert_button(button), x, y)
+                }
+                event::MouseEventKind::Up(_) => MouseEvent::Release(x, y),
+                event::MouseEventKind::Drag(_) => MouseEvent::Hold(x, y),
+                event::MouseEventKind::Moved => MouseEvent::Moved(x, y),
+                event::MouseEventKind::ScrollUp => MouseEvent::Press(MouseButton::WheelUp, x, y),
+                event::MouseEventKind::ScrollDown => {
+                    MouseEvent::Press(MouseButton::WheelDown, x, y)
+                }
+                event::MouseEventKind::ScrollLeft | event::MouseEventKind::ScrollRight => {
+                    return None
+                }
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Key of `code`, as termion reads it: modifiers only go with characters.
+fn convert_key(code: KeyCode, modifiers: KeyModifiers) -> Option<Key> {
+    let plain = (modifiers - KeyModifiers::SHIFT).is_empty();
+    Some(match code {
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => Key::Ctrl(c),
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::ALT) => Key::Alt(c),
+        KeyCode::Char(c) => Key::Char(c),
+        _ if !plain => return None,
+        KeyCode::Enter => Key::Char('\n'),
+        KeyCode::Tab => Key::Char('\t'),
+        KeyCode::F(n) => Key::F(n),
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::BackTab => Key::BackTab,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Insert => Key::Insert,
+        KeyCode::Esc => Key::Esc,
+        _ => return None,
+    })
+}
+
+fn convert_button(button: event::MouseButton) -> MouseButton {
+    match button {
+        event::MouseButton::Left => MouseButton::Left,
+        event::MouseButton::Right => MouseButton::Right,
+        event::MouseButton::Middle => MouseButton::Middle,
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    fmt::Write as _,
+    io::{self, Stdout, Write},
+    sync::{Mutex, PoisonError},
+};
+
+use ::termion::{
+    clear,
+    color::{AnsiValue, Bg, Fg, Rgb},
+    cursor::{self, Goto, HideCursor},
+    event,
+    input::{MouseTerminal, TermRead},
+    raw::{IntoRawMode, RawTerminal},
+    screen::{AlternateScreen, IntoAlternateScreen},
+    style,
+};
+use mandelbrot::Color;
+
+use super::{Clear, Event, Events, Key, MouseButton, MouseEvent, Pen, Terminal};
+
+type Setup = MouseTerminal<HideCursor<AlternateScreen<RawTerminal<Stdout>>>>;
+
+/// Terminal set up for the event loop, until it is restored.
+static SETUP: Mutex<Option<Setup>> = Mutex::new(None);
+
+/// Turns on reports of the pointer moving with no button held, which the
+/// view keeps on to know where the pointer is.
+const MOTION_ON: &str = "\x1b[?1003h";
+const MOTION_OFF: &str = "\x1b[?1003l";
+
+/// The terminal driven through termion, which writes escape sequences to
+/// standard output and reads them from standard input.
+pub struct Termion {
+    output: String,
+    /// Pen set last, if it is known.
+    pen: Option<Pen>,
+}
+
+impl Termion {
+    pub fn new() -> Self {
+        Termion {
+            output: String::new(),
+            pen: None,
+        }
+    }
+}
+
+impl Drop for Termion {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+impl Terminal for Termion {
+    fn enter(&mut self) -> io::Result<()> {
+        let screen = io::stdout().into_raw_mode()?.into_alternate_screen()?;
+        let mut setup = MouseTerminal::from(HideCursor::from(screen));
+        write!(setup, "{}", MOTION_ON)?;
+        *SETUP.lock().unwrap_or_else(PoisonError::into_inner) = Some(setup);
+        super::restore_on_panic(restore);
+        Ok(())
+    }
+
+    fn leave(&mut self) -> io::Result<()> {
+        self.flush()?;
+        restore();
+        Ok(())
+    }
+
+    fn events(&self) -> Events {
+        Box::new(io::stdin().events().filter_map(|event| match event {
+            Ok(event) => convert(event).map(Ok),
+            Err(e) => Some(Err(e)),
+        }))
+    }
+
+    fn size(&self) -> io::Result<(u16, u16)> {
+        ::termion::terminal_size()
+    }
+
+    fn size_pixels(&self) -> Option<(u16, u16)> {
+        ::termion::terminal_size_pixels()
+            .ok()
+            .filter(|&(w, h)| w > 0 && h > 0)
+    }
+
+    fn goto(&mut self, x: u16, y: u16) {
+        write!(self.output, "{}", Goto(x, y)).unwrap();
+    }
+
+    fn show_cursor(&mut self, shown: bool) {
+        match shown {
+            true => write!(self.output, "{}", cursor::Show),
+            false => write!(self.output, "{}", cursor::Hide),
+        }
+        .unwrap();
+    }
+
+    fn clear(&mut self, clear: Clear) {
+        match clear {
+            Clear::All => write!(self.output, "{}", clear::All),
+            Clear::Line => write!(self.output, "{}", clear::CurrentLine),
+        }
+        .unwrap();
+    }
+
+    fn set_pen(&mut self, pen: Pen) {
+        let (reset, from) = super::pen_change(self.pen, pen);
+        if reset {
+            write!(self.output, "{}", style::Reset).unwrap();
+        }
+        match pen.fg.filter(|_| pen.fg != from.fg) {
+            Some(Color::Ansi(v)) => write!(self.output, "{}", Fg(AnsiValue(v))).unwrap(),
+            Some(Color::Rgb(r, g, b)) => write!(self.output, "{}", Fg(Rgb(r, g, b))).unwrap(),
+            None => (),
+        }
+        match pen.bg.filter(|_| pen.bg != from.bg) {
+            Some(Color::Ansi(v)) => write!(self.output, "{}", Bg(AnsiValue(v))).unwrap(),
+            Some(Color::Rgb(r, g, b)) => write!(self.output, "{}", Bg(Rgb(r, g, b))).unwrap(),
+            None => (),
+        }
+        if pen.inverted && !from.inverted {
+            write!(self.output, "{}", style::Invert).unwrap();
+        }
+        self.pen = Some(pen);
+    }
+
+    fn print(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn write_raw(&mut self, output: &str) {
+        self.output.push_str(output);
+        self.pen = None;
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        stdout.write_all(self.output.as_bytes())?;
+        self.output.clear();
+        stdout.flush()
+    }
+}
+
+/// Puts the terminal back as it was, if it was set up.
+fn restore() {
+    let setup = SETUP.lock().unwrap_or_else(PoisonError::into_inner).take();
+    if let Some(mut setup) = setup {
+        let _ = write!(setup, "{}", MOTION_OFF);
+        // Each layer writes its reset as it is dropped.
+        drop(setup);
+        let _ = io::stdout().flush();
+    }
+}
+
+/// `event` as the view reads it, if it reads it at all.
+fn convert(event: event::Event) -> Option<Event> {
+    match event {
+        event::Event::Key(key) => convert_key(key).map(Event::Key),
+        event::Event::Mouse(mouse) => Some(Event::Mouse(match mouse {
+            event::MouseEvent::Press(button, x, y) => {
+                MouseEvent::Press(convert_button(button), x, y)
+            }
+            event::MouseEvent::Hold(x, y) => MouseEvent::Hold(x, y),
+            event::MouseEvent::Release(x, y) => MouseEvent::Release(x, y),
+        })),
+        event::Event::Unsupported(bytes) => match motion(&bytes)? {
+            (true, x, y) => Some(Event::Mouse(MouseEvent::Hold(x, y))),
+            (false, x, y) => Some(Event::Mouse(MouseEvent::Moved(x, y))),
+        },
+    }
+}
+
+fn convert_key(key: event::Key) -> Option<Key> {
+    Some(match key {
+        event::Key::Char(c) => Key::Char(c),
+        event::Key::Ctrl(c) => Key::Ctrl(c),
+        event::Key::Alt(c) => Key::Alt(c),
+        event::Key::F(n) => Key::F(n),
+        event::Key::Left => Key::Left,
+        event::Key::Right => Key::Right,
+        event::Key::Up => Key::Up,
+        event::Key::Down => Key::Down,
+        event::Key::Home => Key::Home,
+        event::Key::End => Key::End,
+        event::Key::PageUp => Key::PageUp,
+        event::Key::PageDown => Key::PageDown,
+        event::Key::BackTab => Key::BackTab,
+        event::Key::Backspace => Key::Backspace,
+        event::Key::Delete => Key::Delete,
+        event::Key::Insert => Key::Insert,
+        event::Key::Esc => Key::Esc,
+        _ => return None,
+    })
+}
+
+fn convert_button(button: event::MouseButton) -> MouseButton {
+    match button {
+        event::MouseButton::Left => MouseButton::Left,
+        event::MouseButton::Right => MouseButton::Right,
+        event::MouseButton::Middle => MouseButton::Middle,
+        event::MouseButton::WheelUp => MouseButton::WheelUp,
+        event::MouseButton::WheelDown => MouseButton::WheelDown,
+    }
+}
+
+/// Position of an SGR mouse motion report termion doesn't parse, and
+/// whether a button is held: it only parses motion with the left one held.
+fn motion(bytes: &[u8]) -> Option<(bool, u16, u16)> {
+    let report = bytes.strip_prefix(b"\x1b[<")?.strip_suffix(b"M")?;
+    let mut fields = std::str::from_utf8(report)
+        .ok()?
+        .split(';')
+        .map(|field| field.parse::<u16>().ok());
+    let (cb, term_x, term_y) = (fields.next()??, fields.next()??, fields.next()??);
+
+    (cb & 32 != 0).then_some((cb & 3 != 3, term_x, term_y))
+}
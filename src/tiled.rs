@@ -0,0 +1,130 @@
+use std::{
+    ffi::OsString,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use mandelbrot::{png, Coloring, Frame, Viewport};
+
+use crate::{cli::ImageFormat, create_output, error::Error, movie::export_error, Settings};
+
+/// Pixels of the largest image exported whole unless asked otherwise.
+pub const MAX_WHOLE_PIXELS: u64 = 100_000_000;
+/// Pixels rendered at once in a band of rows.
+const BAND_PIXELS: u64 = 1 << 20;
+/// Width of the preview that histogram coloring ranks values in, so bands
+/// are colored alike.
+const PREVIEW_WIDTH: u32 = 1024;
+
+/// Directory the bands of an export to `path` are kept in until it is done.
+fn bands_dir(path: &Path) -> PathBuf {
+    let mut dir = OsString::from(path.as_os_str());
+    dir.push(".tiles");
+    PathBuf::from(dir)
+}
+
+/// Renders the view into a `width * height` image band by band, keeping
+/// them in a directory next to `path` and then streaming them into it as
+/// `format`, PNG or PPM. An export that is interrupted picks up from the
+/// bands already there when run again.
+pub fn export(
+    viewport: &Viewport,
+    settings: &Settings,
+    path: &Path,
+    (width, height): (u32, u32),
+    format: ImageFormat,
+) -> Result<(), Error> {
+    let error = export_error(path);
+    if settings.buddhabrot {
+        return Err(error(io::Error::other(
+            "Buddhabrot images can't be rendered in bands",
+        )));
+    }
+    if !matches!(format, ImageFormat::Png | ImageFormat::Ppm) {
+        return Err(error(io::Error::other(
+            "only PNG and PPM images can be rendered in bands",
+        )));
+    }
+
+    let shown = viewport.with_aspect(width, height);
+    let rows = (BAND_PIXELS / width as u64).clamp(1, height as u64) as u32;
+    let bands: Vec<_> = (0..height)
+        .step_by(rows as usize)
+        .map(|start| start..(start + rows).min(height))
+        .collect();
+
+    // Bands left by an export of anything else would be stitched in wrong.
+    let dir = bands_dir(path);
+    let view = format!(
+        "{}x{} in bands of {}\n{:?}\n{:?}\n{:?}\n",
+        width, height, rows, shown, settings.params, settings.style
+    );
+    let view_path = dir.join("view");
+    fs::create_dir_all(&dir).map_err(&error)?;
+    match fs::read_to_string(&view_path) {
+        Ok(old) if old != view => {
+            return Err(error(io::Error::other(format!(
+                "{} holds the bands of another image; remove it to start over",
+                dir.display()
+            ))))
+        }
+        Ok(_) => (),
+        Err(_) => fs::write(&view_path, &view).map_err(&error)?,
+    }
+
+    let preview = (settings.style.coloring == Coloring::Histogram).then(|| {
+        let w = width.min(PREVIEW_WIDTH);
+        let h = (height as u64 * w as u64 / width as u64).max(1) as u32;
+        Frame::from_pixels(&shown.with_aspect(w, h), &settings.params, w, h)
+    });
+    let band_path = |i: usize| dir.join(format!("band-{:05}.png", i + 1));
+    for (i, band) in bands.iter().enumerate() {
+        let file = band_path(i);
+        if !file.exists() {
+            let frame =
+                Frame::from_pixel_rows(&shown, &settings.params, (width, height), band.clone());
+            let pixels = match &preview {
+                Some(whole) => frame.to_pixels_as(&settings.style, whole),
+                None => frame.to_pixels(&settings.style),
+            };
+            // Written aside first, so an interrupted write isn't taken for
+            // a finished band.
+            let part = file.with_extension("part");
+            png::save(&part, width as usize, band.len(), &pixels)
+                .map_err(|e| Error::Export(path.to_path_buf(), e))?;
+            fs::rename(&part, &file).map_err(&error)?;
+        }
+        eprint!("\rrendered band {}/{}", i + 1, bands.len());
+    }
+    eprintln!();
+
+    let copy = |out: &mut dyn Write| -> Result<(), Error> {
+        for i in 0..bands.len() {
+            let band =
+                image::open(band_path(i)).map_err(|e| Error::Export(path.to_path_buf(), e))?;
+            out.write_all(band.as_bytes()).map_err(&error)?;
+        }
+        Ok(())
+    };
+    let mut out = create_output(path)?;
+    match format {
+        ImageFormat::Png => {
+            let mut encoder = ::png::Encoder::new(out, width, height);
+            encoder.set_color(::png::ColorType::Rgb);
+            encoder.set_depth(::png::BitDepth::Eight);
+            let mut stream = encoder
+                .write_header()
+                .and_then(|writer| writer.into_stream_writer())
+                .map_err(|e| error(io::Error::other(e)))?;
+            copy(&mut stream)?;
+            stream.finish().map_err(|e| error(io::Error::other(e)))?;
+        }
+        _ => {
+            write!(out, "P6\n{} {}\n255\n", width, height).map_err(&error)?;
+            copy(&mut out)?;
+            out.flush().map_err(&error)?;
+        }
+    }
+    fs::remove_dir_all(&dir).map_err(error)
+}
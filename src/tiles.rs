@@ -0,0 +1,143 @@
+//! Cache of escape results in tiles of a grid fixed in the plane, so a view
+//! overlapping an earlier one at the same scale, as after a pan, only
+//! iterates the samples it hasn't seen yet.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use rayon::prelude::*;
+
+use crate::{EscapeResult, Origin, Params, Sampler};
+
+/// Samples along each side of a tile.
+const TILE: i64 = 8;
+
+/// Tile position on the grid, sample spacing as bits, and iteration limit.
+type Key = (i64, i64, (u64, u64), u32);
+
+type Tile = Arc<[EscapeResult]>;
+
+/// Least recently used tiles are evicted once the cache outgrows its budget.
+pub struct TileCache {
+    capacity: usize,
+    /// Parameters and origin of the view the cached tiles were iterated for.
+    params: Option<(Params, Origin)>,
+    tiles: HashMap<Key, (u64, Tile)>,
+    /// Keys of the cached tiles by the time they were last used.
+    recency: BTreeMap<u64, Key>,
+    clock: u64,
+}
+
+fn iterate_tile(
+    (tx, ty, _, _): Key,
+    sampler: &Sampler,
+    spacing: (f64, f64),
+    cancel: &AtomicBool,
+) -> Option<Tile> {
+    let size = (TILE as usize, TILE as usize);
+    let point = |i: usize, j: usize| {
+        let (x, y) = (tx * TILE + i as i64, ty * TILE + j as i64);
+        ((x as f64 + 0.5) * spacing.0, (y as f64 + 0.5) * spacing.1)
+    };
+    let tile = sampler.at_grid(size, point, spacing, cancel)?;
+    Some(tile.into())
+}
+
+impl TileCache {
+    /// A cache holding about `budget` bytes of escape results.
+    pub fn new(budget: usize) -> Self {
+        let tile_size = (TILE * TILE) as usize * mem::size_of::<EscapeResult>();
+
+        TileCache {
+            capacity: (budget / tile_size).max(1),
+            params: None,
+            tiles: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.recency.clear();
+    }
+
+    fn store(&mut self, key: Key, tile: Tile) {
+        if let Some((used, _)) = self.tiles.insert(key, (self.clock, tile)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(self.clock, key);
+        self.clock += 1;
+
+        while self.tiles.len() > self.capacity {
+            let Some((_, key)) = self.recency.pop_first() else {
+                break;
+            };
+            self.tiles.remove(&key);
+        }
+    }
+
+    /// Escape results of the centers of the `cols * rows` cells `start + (i,
+    /// j) * spacing` of size `spacing` from the origin of the view of
+    /// `sampler`, row-major, with `start` snapped to the nearest corner of
+    /// the grid of that spacing. Returns `None` once `cancel` is set.
+    pub(crate) fn sample(
+        &mut self,
+        sampler: &Sampler,
+        start: (f64, f64),
+        spacing: (f64, f64),
+        (cols, rows): (usize, usize),
+        cancel: &AtomicBool,
+    ) -> Option<Vec<EscapeResult>> {
+        let (params, origin) = (sampler.params, &sampler.viewport.origin);
+        let same = |(p, o): &(Params, Origin)| p.same_orbits(params) && o == origin;
+        if !self.params.as_ref().is_some_and(same) {
+            self.clear();
+            self.params = Some((params.clone(), origin.clone()));
+        }
+
+        let (i0, j0) = (
+            (start.0 / spacing.0).round() as i64,
+            (start.1 / spacing.1).round() as i64,
+        );
+        let scale = (spacing.0.to_bits(), spacing.1.to_bits());
+        let tiles = |start: i64, len: usize| {
+            start.div_euclid(TILE)..=(start + len.max(1) as i64 - 1).div_euclid(TILE)
+        };
+        let keys: Vec<Key> = tiles(j0, rows)
+            .flat_map(|ty| tiles(i0, cols).map(move |tx| (tx, ty, scale, params.max_iterations)))
+            .collect();
+
+        let (cached, missing): (Vec<Key>, Vec<Key>) = keys
+            .into_iter()
+            .partition(|key| self.tiles.contains_key(key));
+        let computed: Vec<(Key, Tile)> = missing
+            .into_par_iter()
+            .map(|key| Some((key, iterate_tile(key, sampler, spacing, cancel)?)))
+            .collect::<Option<_>>()?;
+
+        // Keep the tiles of this view at hand, as storing the new ones may
+        // evict some of them when the view outgrows the budget.
+        let cached: Vec<(Key, Tile)> = cached
+            .into_iter()
+            .map(|key| (key, self.tiles[&key].1.clone()))
+            .collect();
+        let mut view = HashMap::new();
+        for (key, tile) in cached.into_iter().chain(computed) {
+            view.insert((key.0, key.1), tile.clone());
+            self.store(key, tile);
+        }
+
+        let mut samples = Vec::with_capacity(cols * rows);
+        for j in j0..j0 + rows as i64 {
+            for i in i0..i0 + cols as i64 {
+                let tile = &view[&(i.div_euclid(TILE), j.div_euclid(TILE))];
+                samples.push(tile[(j.rem_euclid(TILE) * TILE + i.rem_euclid(TILE)) as usize]);
+            }
+        }
+        Some(samples)
+    }
+}
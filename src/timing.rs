@@ -0,0 +1,86 @@
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+/// Frames the rolling average is taken over.
+const WINDOW: usize = 16;
+
+/// How long the last frames took to render and to draw, optionally logged
+/// to a file frame by frame.
+pub struct FrameTimes {
+    recent: VecDeque<(Duration, Duration)>,
+    log: Option<File>,
+}
+
+impl FrameTimes {
+    pub fn new() -> Self {
+        FrameTimes {
+            recent: VecDeque::with_capacity(WINDOW),
+            log: None,
+        }
+    }
+
+    /// Appends the times of every frame recorded from now on to `path`.
+    pub fn log_to(&mut self, path: &Path) -> io::Result<()> {
+        let mut log = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(log, "render_ms\tdraw_ms\taverage_ms")?;
+        self.log = Some(log);
+        Ok(())
+    }
+
+    /// Records a frame that took `render` to render and `draw` to draw. The
+    /// log is closed if writing to it fails.
+    pub fn record(&mut self, render: Duration, draw: Duration) -> io::Result<()> {
+        if self.recent.len() == WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((render, draw));
+        let average = self.average();
+
+        let Some(log) = &mut self.log else {
+            return Ok(());
+        };
+        let ms = |d: Duration| d.as_secs_f64() * 1e3;
+        let line = writeln!(
+            log,
+            "{:.3}\t{:.3}\t{:.3}",
+            ms(render),
+            ms(draw),
+            ms(average)
+        );
+        if line.is_err() {
+            self.log = None;
+        }
+        line
+    }
+
+    /// Average time from the start of a render to the frame being drawn,
+    /// over the last frames.
+    fn average(&self) -> Duration {
+        let total: Duration = self
+            .recent
+            .iter()
+            .map(|(render, draw)| *render + *draw)
+            .sum();
+        total / self.recent.len().max(1) as u32
+    }
+
+    /// Times of the last frame and the average, for the status bar.
+    pub fn summary(&self) -> String {
+        let Some((render, draw)) = self.recent.back() else {
+            return String::new();
+        };
+        let average = self.average();
+        format!(
+            "render {} ms  draw {} ms  avg {} ms ({:.1} fps)",
+            render.as_millis(),
+            draw.as_millis(),
+            average.as_millis(),
+            1. / average.as_secs_f64().max(1e-3)
+        )
+    }
+}
@@ -0,0 +1,239 @@
+use std::{fs, path::Path, time::Instant};
+
+use mandelbrot::{Location, Viewport};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::{Easing, Zoom},
+    error::Error,
+    link, magnification, Settings,
+};
+
+/// File tours are saved to from the editor when no other is given.
+pub const DEFAULT_PATH: &str = "mandelbrot-tour.toml";
+/// Seconds flown to a keyframe from the one before, unless given.
+const DEFAULT_DURATION: f64 = 4.;
+
+/// A view of a tour, such as
+///
+/// ```toml
+/// [[keyframe]]
+/// center = "-0.7436,0.1318"
+/// zoom = 2000
+/// duration = 8
+/// hold = 2
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Keyframe {
+    /// Decimal coordinates of the center, as RE,IM.
+    pub center: String,
+    /// Magnification over the default view of the fractal.
+    pub zoom: f64,
+    /// Seconds taken to fly here from the keyframe before, if any.
+    #[serde(default = "default_duration")]
+    pub duration: f64,
+    /// Seconds the view stays here.
+    #[serde(default)]
+    pub hold: f64,
+    /// Pace of the flight here, unless that of the settings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub easing: Option<Easing>,
+}
+
+fn default_duration() -> f64 {
+    DEFAULT_DURATION
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TourFile {
+    #[serde(default, rename = "keyframe")]
+    keyframes: Vec<Keyframe>,
+}
+
+/// Keyframes with the locations they place the view at.
+pub struct Tour {
+    keyframes: Vec<(Keyframe, Location)>,
+    /// Pace of flights whose keyframes don't give one.
+    easing: Easing,
+}
+
+impl Keyframe {
+    /// A keyframe of the view, flown to in the default time and not held.
+    pub fn of(viewport: &Viewport, settings: &Settings) -> Keyframe {
+        let (re, im) = viewport.center_decimal_to(viewport.scale * link::RESOLUTION);
+        Keyframe {
+            center: format!("{},{}", re, im),
+            zoom: magnification(viewport, &settings.params),
+            duration: DEFAULT_DURATION,
+            hold: 0.,
+            easing: None,
+        }
+    }
+
+    /// Where the keyframe places a view of the fractal of `settings`.
+    pub fn location(&self, viewport: &Viewport, settings: &Settings) -> Result<Location, String> {
+        let (re, im) = self
+            .center
+            .split_once(',')
+            .ok_or_else(|| format!("expected a center of RE,IM, got `{}`", self.center))?;
+        if !(self.zoom > 0. && self.zoom.is_finite()) {
+            return Err("zoom must be positive".to_string());
+        }
+        if !(self.duration >= 0. && self.hold >= 0.) {
+            return Err("durations can't be negative".to_string());
+        }
+        let mut viewport = viewport.clone();
+        link::place(&mut viewport, settings, (re.trim(), im.trim()), self.zoom)
+            .ok_or_else(|| format!("invalid center `{}`", self.center))?;
+        Ok(viewport.location())
+    }
+}
+
+/// Reads the `[[keyframe]]` tables of a tour file, placing them in views of
+/// the fractal of `settings`.
+pub fn load(path: &Path, viewport: &Viewport, settings: &Settings) -> Result<Tour, Error> {
+    let error = |e: String| Error::Tour(path.to_path_buf(), e);
+    let source = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    let file: TourFile = toml::from_str(&source).map_err(|e| error(e.to_string()))?;
+    Tour::new(file.keyframes, viewport, settings).map_err(error)
+}
+
+/// Writes `keyframes` to a tour file.
+pub fn save(path: &Path, keyframes: &[Keyframe]) -> Result<(), String> {
+    let file = TourFile {
+        keyframes: keyframes.to_vec(),
+    };
+    let source = toml::to_string(&file).map_err(|e| e.to_string())?;
+    fs::write(path, source).map_err(|e| e.to_string())
+}
+
+impl Tour {
+    /// The tour of `keyframes`, placed in views of the fractal of `settings`.
+    pub fn new(
+        keyframes: Vec<Keyframe>,
+        viewport: &Viewport,
+        settings: &Settings,
+    ) -> Result<Tour, String> {
+        if keyframes.is_empty() {
+            return Err("no keyframes".to_string());
+        }
+        let keyframes = keyframes
+            .into_iter()
+            .enumerate()
+            .map(|(i, keyframe)| {
+                let location = keyframe
+                    .location(viewport, settings)
+                    .map_err(|e| format!("keyframe {}: {}", i + 1, e))?;
+                Ok((keyframe, location))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Tour {
+            keyframes,
+            easing: settings.easing,
+        })
+    }
+
+    pub fn keyframes(&self) -> Vec<Keyframe> {
+        self.keyframes
+            .iter()
+            .map(|(keyframe, _)| keyframe.clone())
+            .collect()
+    }
+
+    /// Seconds the tour plays for.
+    fn length(&self) -> f64 {
+        self.keyframes
+            .iter()
+            .enumerate()
+            .map(|(i, (keyframe, _))| keyframe.hold + if i > 0 { keyframe.duration } else { 0. })
+            .sum()
+    }
+
+    /// Location `seconds` into the tour and whether the view is flying
+    /// between keyframes then, or `None` once it is over.
+    fn at(&self, mut seconds: f64) -> Option<(Location, bool)> {
+        let mut previous: Option<&Location> = None;
+        for (keyframe, location) in &self.keyframes {
+            if let Some(from) = previous {
+                if seconds < keyframe.duration {
+                    let easing = keyframe.easing.unwrap_or(self.easing);
+                    let zoom = Zoom::new(from.clone(), location.clone(), easing);
+                    return Some((zoom.location_at(seconds / keyframe.duration), true));
+                }
+                seconds -= keyframe.duration;
+            }
+            if seconds < keyframe.hold {
+                return Some((location.clone(), false));
+            }
+            seconds -= keyframe.hold;
+            previous = Some(location);
+        }
+        None
+    }
+
+    /// Where the tour ends.
+    fn last(&self) -> Location {
+        let (_, location) = self.keyframes.last().expect("tours have keyframes");
+        location.clone()
+    }
+}
+
+/// What to show next of a tour being played.
+pub enum Step {
+    /// A view between keyframes, to render quickly.
+    Fly(Location),
+    /// A keyframe reached, to render in full.
+    Arrive(Location),
+    /// Nothing new, while a keyframe already shown is held.
+    Hold,
+    /// The end of the tour, which stays on screen.
+    Done,
+}
+
+/// A tour being played, from when it started.
+pub struct Playback {
+    tour: Tour,
+    start: Instant,
+    /// Whether the tour starts over once it is done.
+    repeat: bool,
+    /// Keyframe shown in full, while it is held.
+    held: Option<Location>,
+}
+
+impl Playback {
+    pub fn new(tour: Tour, repeat: bool) -> Self {
+        Playback {
+            tour,
+            start: Instant::now(),
+            repeat,
+            held: None,
+        }
+    }
+
+    /// What to show of the tour now.
+    pub fn step(&mut self) -> Step {
+        let mut seconds = self.start.elapsed().as_secs_f64();
+        let length = self.tour.length();
+        if self.repeat && length > 0. {
+            seconds %= length;
+        }
+        let (location, moving, over) = match self.tour.at(seconds) {
+            Some((location, moving)) => (location, moving, false),
+            None => (self.tour.last(), false, true),
+        };
+        if moving {
+            self.held = None;
+            return Step::Fly(location);
+        }
+        match self.held.as_ref() == Some(&location) {
+            true if over => Step::Done,
+            true => Step::Hold,
+            false => {
+                self.held = Some(location.clone());
+                Step::Arrive(location)
+            }
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::io;
+
+use mandelbrot::{Cell, Color};
+use ratatui::{
+    backend::{Backend, ClearType, WindowSize},
+    buffer::{self, Buffer},
+    layout::{Constraint, Layout, Position, Rect, Size},
+    style::{self, Modifier},
+    text::Line,
+    widgets::{Block, Clear as Blank, Paragraph},
+    Frame,
+};
+
+use crate::terminal::{Clear, Pen, Terminal};
+
+/// Columns the side panel takes up when it is shown.
+const PANEL_WIDTH: u16 = 32;
+
+/// Ratatui backend drawing through a [`Terminal`], so the view's widgets
+/// run on either of the libraries it drives terminals through.
+pub struct TerminalBackend {
+    terminal: Box<dyn Terminal>,
+    /// Cell the cursor was last moved to, counted from 0.
+    cursor: Position,
+}
+
+impl Backend for TerminalBackend {
+    type Error = io::Error;
+
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a buffer::Cell)>,
+    {
+        for (x, y, cell) in content {
+            // Cells printed one after another need no move in between.
+            if self.cursor != Position::new(x, y) {
+                self.terminal.goto(x + 1, y + 1);
+            }
+            self.terminal.set_pen(Pen {
+                fg: color(cell.fg),
+                bg: color(cell.bg),
+                inverted: cell.modifier.contains(Modifier::REVERSED),
+            });
+            self.terminal.print(cell.symbol());
+            self.cursor = Position::new(x + 1, y);
+        }
+        self.terminal.set_pen(Pen::default());
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.terminal.show_cursor(false);
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.terminal.show_cursor(true);
+        Ok(())
+    }
+
+    fn get_cursor_position(&mut self) -> io::Result<Position> {
+        Ok(self.cursor)
+    }
+
+    fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> io::Result<()> {
+        self.cursor = position.into();
+        self.terminal.goto(self.cursor.x + 1, self.cursor.y + 1);
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.clear_region(ClearType::All)
+    }
+
+    fn clear_region(&mut self, clear_type: ClearType) -> io::Result<()> {
+        match clear_type {
+            ClearType::All => self.terminal.clear(Clear::All),
+            ClearType::CurrentLine => self.terminal.clear(Clear::Line),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("terminals can't clear {}", clear_type),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        let (width, height) = self.terminal.size()?;
+        Ok(Size::new(width, height))
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        let (width, height) = self.terminal.size_pixels().unwrap_or((0, 0));
+        Ok(WindowSize {
+            columns_rows: self.size()?,
+            pixels: Size::new(width, height),
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.terminal.flush()
+    }
+}
+
+/// Ratatui color of a cell colored `color`.
+pub fn style_color(color: Option<Color>) -> style::Color {
+    match color {
+        None => style::Color::Reset,
+        Some(Color::Ansi(v)) => style::Color::Indexed(v),
+        Some(Color::Rgb(r, g, b)) => style::Color::Rgb(r, g, b),
+    }
+}
+
+/// Color a cell colored `color` by ratatui is printed in.
+fn color(color: style::Color) -> Option<Color> {
+    Some(match color {
+        style::Color::Reset => return None,
+        style::Color::Black => Color::Ansi(0),
+        style::Color::Red => Color::Ansi(1),
+        style::Color::Green => Color::Ansi(2),
+        style::Color::Yellow => Color::Ansi(3),
+        style::Color::Blue => Color::Ansi(4),
+        style::Color::Magenta => Color::Ansi(5),
+        style::Color::Cyan => Color::Ansi(6),
+        style::Color::Gray => Color::Ansi(7),
+        style::Color::DarkGray => Color::Ansi(8),
+        style::Color::LightRed => Color::Ansi(9),
+        style::Color::LightGreen => Color::Ansi(10),
+        style::Color::LightYellow => Color::Ansi(11),
+        style::Color::LightBlue => Color::Ansi(12),
+        style::Color::LightMagenta => Color::Ansi(13),
+        style::Color::LightCyan => Color::Ansi(14),
+        style::Color::White => Color::Ansi(15),
+        style::Color::Indexed(v) => Color::Ansi(v),
+        style::Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    })
+}
+
+/// The terminal the view is drawn on, whole frames at a time, of which
+/// ratatui only prints the cells that changed.
+pub struct Ui {
+    tui: ratatui::Terminal<TerminalBackend>,
+    /// What was drawn last, which dialogs are drawn over.
+    shown: Buffer,
+    /// Whether a dialog was drawn since the last image, over cells ratatui
+    /// leaves to it.
+    overdrawn: bool,
+}
+
+impl Ui {
+    pub fn new(terminal: Box<dyn Terminal>) -> io::Result<Self> {
+        let tui = ratatui::Terminal::new(TerminalBackend {
+            terminal,
+            cursor: Position::ORIGIN,
+        })?;
+        Ok(Ui {
+            tui,
+            shown: Buffer::default(),
+            overdrawn: false,
+        })
+    }
+
+    /// The terminal itself, for what ratatui doesn't draw: setting it up,
+    /// reading its events and writing images.
+    pub fn terminal(&mut self) -> &mut dyn Terminal {
+        self.tui.backend_mut().terminal.as_mut()
+    }
+
+    /// Draws the frame `render` makes.
+    pub fn draw(&mut self, render: impl FnOnce(&mut Frame)) -> io::Result<()> {
+        let drawn = self.tui.draw(render)?;
+        self.shown = drawn.buffer.clone();
+        Ok(())
+    }
+
+    /// What was drawn last, to draw dialogs over.
+    pub fn shown(&self) -> Buffer {
+        self.shown.clone()
+    }
+
+    /// Draws what `render` makes over `base`, as much of it as still fits.
+    pub fn draw_over(&mut self, base: &Buffer, render: impl FnOnce(&mut Frame)) -> io::Result<()> {
+        self.draw(|frame| {
+            let area = frame.area().intersection(base.area);
+            let buffer = frame.buffer_mut();
+            for position in area.positions() {
+                buffer[position] = base[position].clone();
+            }
+            render(frame);
+        })?;
+        self.overdrawn = true;
+        Ok(())
+    }
+
+    /// Whether a dialog was drawn over the image since it was last drawn.
+    pub fn overdrawn(&self) -> bool {
+        self.overdrawn
+    }
+
+    /// Clears the screen for `image`, which is written from the top left
+    /// corner. The next frame is drawn over it in full, but for the cells
+    /// it leaves to the image.
+    pub fn draw_image(&mut self, image: &str) -> io::Result<()> {
+        self.tui.clear()?;
+        let terminal = self.terminal();
+        terminal.goto(1, 1);
+        terminal.write_raw(image);
+        // Wherever the image leaves the cursor, the next cell drawn moves it.
+        self.tui.backend_mut().cursor = Position::new(u16::MAX, u16::MAX);
+        self.overdrawn = false;
+        Ok(())
+    }
+}
+
+/// Areas of the view, the side panel if it is shown and there is room for
+/// it, and the status bar below them, in a terminal of `area`.
+pub fn layout(area: Rect, panel: bool) -> (Rect, Option<Rect>, Rect) {
+    let [main, status] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(area);
+    if !panel || main.width < 2 * PANEL_WIDTH {
+        return (main, None, status);
+    }
+    let [view, panel] =
+        Layout::horizontal([Constraint::Min(1), Constraint::Length(PANEL_WIDTH)]).areas(main);
+    (view, Some(panel), status)
+}
+
+/// Draws `cells`, `width` to a row, into `buffer` from its cell `(x, y)`
+/// on, leaving out those outside it.
+pub fn put_cells(buffer: &mut Buffer, (x, y): (u16, u16), cells: &[Cell], width: usize) {
+    for (i, cell) in cells.iter().enumerate() {
+        let at = (x + (i % width) as u16, y + (i / width) as u16);
+        if let Some(drawn) = buffer.cell_mut(at) {
+            drawn
+                .set_char(cell.symbol)
+                .set_fg(style_color(cell.fg))
+                .set_bg(style_color(cell.bg));
+        }
+    }
+}
+
+/// Draws `symbol` in the terminal's colors into `buffer` at the cell
+/// `(term_x, term_y)`, counted from 1 as the terminal counts them, unless it
+/// is outside.
+pub fn put(buffer: &mut Buffer, (term_x, term_y): (u16, u16), symbol: char) {
+    let at = (term_x.wrapping_sub(1), term_y.wrapping_sub(1));
+    if let Some(cell) = buffer.cell_mut(at) {
+        cell.reset();
+        cell.set_char(symbol);
+    }
+}
+
+/// Area of `width * height` cells in the middle of `area`, or as much of
+/// it as fits.
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let (width, height) = (width.min(area.width), height.min(area.height));
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Draws a box titled `title` over `area` of `frame`, with `lines` in it,
+/// and returns the area inside its border.
+pub fn dialog(frame: &mut Frame, area: Rect, title: &str, lines: Vec<Line>) -> Rect {
+    let block = Block::bordered().title(title);
+    let inner = block.inner(area);
+    frame.render_widget(Blank, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+    inner
+}
@@ -0,0 +1,270 @@
+use std::{collections::HashMap, fmt::Write, fs, path::Path};
+
+use mandelbrot::{
+    palette::{Palette, Rgb},
+    Fractal, Origin, Viewport, C,
+};
+
+use crate::{
+    cli::{self, FractalKind},
+    link::{self, Link},
+    Settings,
+};
+
+/// Height of the plane Ultra Fractal shows at a magnification of 1.
+const UF_HEIGHT: f64 = 3.;
+/// Positions of an Ultra Fractal gradient, which wraps around after them.
+const GRADIENT_LENGTH: f64 = 400.;
+
+/// `key=value` fields of each section of a parameter block, in order.
+type Sections = HashMap<String, Vec<(String, String)>>;
+
+/// Splits a parameter block such as `Name { mapping: center=-0.5/0 magn=1
+/// ... }` into its title and sections.
+fn sections(source: &str) -> Result<(String, Sections), String> {
+    let (title, body) = source
+        .split_once('{')
+        .ok_or("expected a parameter block, as `Name { ... }`")?;
+    let body = body.split_once('}').map_or(body, |(body, _)| body);
+
+    let mut sections = Sections::new();
+    let mut section = String::new();
+    let mut rest = body;
+    while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let key = &rest[..end];
+        rest = &rest[end..];
+        let Some(value) = rest.strip_prefix('=') else {
+            if let Some(name) = key.strip_suffix(':') {
+                section = name.to_ascii_lowercase();
+            }
+            continue;
+        };
+        // Quoted values may hold spaces.
+        let (value, after) = match value.strip_prefix('"') {
+            Some(quoted) => quoted
+                .split_once('"')
+                .ok_or_else(|| format!("unterminated quote in `{}`", key))?,
+            None => value.split_at(value.find(char::is_whitespace).unwrap_or(value.len())),
+        };
+        rest = after;
+        sections
+            .entry(section.clone())
+            .or_default()
+            .push((key.to_ascii_lowercase(), value.to_string()));
+    }
+    // Comments come before the title, on lines of their own.
+    let title = title.trim().lines().last().unwrap_or("").trim();
+    Ok((title.to_string(), sections))
+}
+
+/// The first value of `key` in `section`.
+fn field<'a>(sections: &'a Sections, section: &str, key: &str) -> Option<&'a str> {
+    let fields = sections.get(section)?;
+    fields
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+}
+
+fn number(value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| format!("expected a number, got `{}`", value))
+}
+
+/// Parses a complex parameter, written `RE/IM`.
+fn complex(value: &str) -> Result<C, String> {
+    let (re, im) = value.split_once('/').unwrap_or((value, "0"));
+    Ok(C {
+        re: number(re)?,
+        im: number(im)?,
+    })
+}
+
+/// Color of a gradient, packed as `red + 256 * green + 65536 * blue`.
+fn unpack(color: u32) -> Rgb {
+    let channel = |shift: u32| ((color >> shift) & 0xff) as f64 / 255.;
+    (channel(0), channel(8), channel(16))
+}
+
+fn pack((r, g, b): Rgb) -> u32 {
+    let channel = |v: f64| (v.clamp(0., 1.) * 255.).round() as u32;
+    channel(r) | channel(g) << 8 | channel(b) << 16
+}
+
+/// Palette of the `index=N color=C` pairs of a gradient, wrapping around
+/// from its last color to its first as Ultra Fractal does.
+fn gradient(name: &str, fields: &[(String, String)]) -> Result<Option<Palette>, String> {
+    let mut stops = Vec::new();
+    let mut index = None;
+    for (key, value) in fields {
+        match key.as_str() {
+            "index" => index = Some(number(value)?.rem_euclid(GRADIENT_LENGTH)),
+            "color" => {
+                let color = value
+                    .parse::<u32>()
+                    .map_err(|_| format!("invalid color `{}`", value))?;
+                let index = index.take().ok_or("gradient color without an index")?;
+                stops.push((index, unpack(color)));
+            }
+            _ => (),
+        }
+    }
+    stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let (Some(&(first, a)), Some(&(last, b))) = (stops.first(), stops.last()) else {
+        return Ok(None);
+    };
+
+    let t = match first + GRADIENT_LENGTH - last {
+        0. => 0.,
+        gap => (GRADIENT_LENGTH - last) / gap,
+    };
+    let lerp = |a: f64, b: f64| b + (a - b) * t;
+    let wrap = (lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2));
+    let mut stops: Vec<_> = stops
+        .into_iter()
+        .map(|(index, color)| (index / GRADIENT_LENGTH, color))
+        .collect();
+    stops.insert(0, (0., wrap));
+    stops.push((1., wrap));
+    Ok(Some(Palette::with_stops(name, stops)))
+}
+
+/// Parses an Ultra Fractal parameter block: the location of its mapping,
+/// the iteration limit, power and seed of a Mandelbrot or Julia formula,
+/// and its gradient.
+pub fn parse(source: &str) -> Result<Link, String> {
+    let (title, sections) = sections(source)?;
+    let center = field(&sections, "mapping", "center").ok_or("no center in the mapping")?;
+    let (re, im) = center.split_once('/').unwrap_or((center, "0"));
+    if Origin::parse(re, im).is_none() {
+        return Err(format!("invalid center `{}`", center));
+    }
+    let mut link = Link::new((re.to_string(), im.to_string()));
+
+    let entry = field(&sections, "formula", "entry").unwrap_or("Mandelbrot");
+    let (fractal, default) = match entry.to_ascii_lowercase().as_str() {
+        "mandelbrot" => (FractalKind::Mandelbrot, Fractal::Mandelbrot),
+        "julia" => (FractalKind::Julia, Fractal::Julia(Fractal::DEFAULT_JULIA)),
+        _ => return Err(format!("formula `{}` isn't supported", entry)),
+    };
+    link.fractal = fractal;
+    if let Some(seed) = field(&sections, "formula", "p_seed") {
+        link.julia = Some(complex(seed)?);
+    }
+    if let Some(power) = field(&sections, "formula", "p_power") {
+        link.exponent = Some(cli::check_exponent(complex(power)?.re)?);
+    }
+    if let Some(n) = field(&sections, "formula", "maxiter") {
+        link.iterations = Some(
+            n.parse::<u32>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("invalid maxiter `{}`", n))?,
+        );
+    }
+
+    let magn = field(&sections, "mapping", "magn").map_or(Ok(1.), number)?;
+    if magn <= 0. {
+        return Err("magn must be positive".to_string());
+    }
+    let (_, (_, height)) = default.default_bounds();
+    link.zoom = magn * height / UF_HEIGHT;
+    link.rotation = field(&sections, "mapping", "angle").map_or(Ok(0.), number)?;
+
+    if let Some(fields) = sections.get("gradient") {
+        link.palette = gradient(&title, fields)?;
+    }
+    Ok(link)
+}
+
+pub fn load(path: &Path) -> Result<Link, String> {
+    fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|source| parse(&source))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// An Ultra Fractal parameter block of the view, named `title`. Only the
+/// Mandelbrot and Julia sets have formulas there.
+pub fn encode(viewport: &Viewport, settings: &Settings, title: &str) -> Result<String, String> {
+    let params = &settings.params;
+    let seed = match params.fractal {
+        Fractal::Mandelbrot => None,
+        Fractal::Julia(c) => Some(c),
+        _ => return Err("only Mandelbrot and Julia sets can be saved for Ultra Fractal".into()),
+    };
+    let (re, im) = viewport.center_decimal_to(viewport.scale * link::RESOLUTION);
+    let (w, h) = viewport.extent();
+    let width = 640;
+    let height = (width as f64 * h / w).round().max(1.) as u32;
+
+    let mut block = format!("{} {{\nfractal:\n", title);
+    writeln!(
+        block,
+        "  title=\"{}\" width={} height={} layers=1",
+        title, width, height
+    )
+    .unwrap();
+    block.push_str("layer:\n  caption=\"Background\" opacity=100\n");
+    writeln!(
+        block,
+        "mapping:\n  center={}/{} magn={:e} angle={}",
+        re,
+        im,
+        UF_HEIGHT / h,
+        viewport.rotation.to_degrees()
+    )
+    .unwrap();
+    let entry = if seed.is_some() {
+        "Julia"
+    } else {
+        "Mandelbrot"
+    };
+    writeln!(
+        block,
+        "formula:\n  maxiter={} filename=\"Standard.ufm\" entry=\"{}\"",
+        params.max_iterations, entry
+    )
+    .unwrap();
+    write!(
+        block,
+        "  p_power={}/0 p_bailout={}",
+        params.exponent, params.cutoff
+    )
+    .unwrap();
+    if let Some(c) = seed {
+        write!(block, " p_seed={}/{}", c.re, c.im).unwrap();
+    }
+    block.push_str("\ninside:\n  transfer=none\n");
+    block.push_str("outside:\n  transfer=linear filename=\"Standard.ucl\" entry=\"Smooth\"\n");
+
+    // The gradient wraps around, so a last stop at its end would come again
+    // as the first.
+    block.push_str("gradient:\n  smooth=yes");
+    let mut last = None;
+    for &(position, color) in settings.style.palette.stops() {
+        let index = (position * GRADIENT_LENGTH).round() as u32;
+        if index < GRADIENT_LENGTH as u32 && last != Some(index) {
+            write!(block, " index={} color={}", index, pack(color)).unwrap();
+            last = Some(index);
+        }
+    }
+    block.push_str("\nopacity:\n  smooth=no index=0 opacity=255\n}\n");
+    Ok(block)
+}
+
+pub fn save(path: &Path, viewport: &Viewport, settings: &Settings) -> Result<(), String> {
+    let title = path
+        .file_stem()
+        .map_or_else(|| "mandelbrot".into(), |s| s.to_string_lossy());
+    let block = encode(viewport, settings, &title)?;
+    fs::write(path, block).map_err(|e| e.to_string())
+}
@@ -0,0 +1,459 @@
+use std::f64::consts::LOG2_10;
+
+use dashu_float::{
+    round::mode::{HalfAway, Zero},
+    DBig, FBig,
+};
+
+use crate::C;
+
+pub type Bounds = ((f64, f64), (f64, f64));
+
+/// Where a view looks: its center relative to an origin, the width of the
+/// plane a cell covers and the angle it is turned by, with the shape of the
+/// view they were taken from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub origin: Origin,
+    pub center: C,
+    pub scale: f64,
+    pub rotation: f64,
+    /// Cells across and down, and the cell aspect. Views of the same shape
+    /// take the center and scale as they are, and others frame the bounds.
+    shape: (u16, u16, f64),
+}
+
+impl Location {
+    /// The location covering exactly `bounds` of the plane, relative to
+    /// `origin`, turned by `rotation`.
+    pub fn new(origin: Origin, ((x_min, w), (y_min, h)): Bounds, rotation: f64) -> Self {
+        Location {
+            origin,
+            center: C {
+                re: x_min + w / 2.,
+                im: y_min + h / 2.,
+            },
+            scale: w,
+            rotation,
+            shape: (1, 1, h / w),
+        }
+    }
+
+    /// The box of the plane in view, relative to the origin and before it is
+    /// turned.
+    pub fn bounds(&self) -> Bounds {
+        let (width, height, cell_aspect) = self.shape;
+        let w = self.scale * width as f64;
+        let h = self.scale * cell_aspect * height as f64;
+        ((self.center.re - w / 2., w), (self.center.im - h / 2., h))
+    }
+}
+
+/// Views whose center is this many times their extent away from the origin
+/// move the origin to their center, so f64 offsets keep resolving them.
+const REBASE_RATIO: f64 = 1e6;
+
+/// Cells smaller than this fraction of their distance from zero can't be
+/// resolved iterating f64 coordinates directly.
+const DEEP: f64 = 1e-12;
+
+/// Cells at least this fraction of their distance from zero are resolved
+/// iterating f32 coordinates.
+const SHALLOW: f64 = 1e-5;
+
+/// Point of the plane the bounds of a view are measured from, held in
+/// arbitrary precision so views can be far smaller than the spacing of f64
+/// values around them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Origin {
+    pub re: FBig,
+    pub im: FBig,
+    /// `re` and `im` rounded to f64.
+    approx: (f64, f64),
+}
+
+impl Origin {
+    pub fn new(re: FBig, im: FBig) -> Self {
+        let approx = (re.to_f64().value(), im.to_f64().value());
+        Origin { re, im, approx }
+    }
+
+    /// Parses an origin from decimal coordinates, keeping all their digits.
+    pub fn parse(re: &str, im: &str) -> Option<Origin> {
+        Origin::parse_to(re, im, 0)
+    }
+
+    /// Like [`Origin::parse`], but with at least `precision` significant
+    /// bits, for coordinates of deeper views than their digits resolve.
+    pub fn parse_to(re: &str, im: &str, precision: usize) -> Option<Origin> {
+        let parse = |s: &str| {
+            let decimal: DBig = s.trim().parse().ok()?;
+            let bits = (decimal.precision() as f64 * LOG2_10).ceil() as usize + 1;
+            let bits = bits.max(precision);
+            let binary = decimal.with_base_and_precision::<2>(bits).value();
+            Some(binary.with_rounding::<Zero>())
+        };
+        Some(Origin::new(parse(re)?, parse(im)?))
+    }
+
+    /// Decimal coordinates of the origin, with as many digits as it has bits
+    /// of precision to tell apart.
+    pub fn to_decimal(&self) -> (String, String) {
+        let format = |x: &FBig| {
+            let digits = (x.precision() as f64 / LOG2_10).ceil() as usize + 1;
+            x.clone()
+                .with_base_and_precision::<10>(digits)
+                .value()
+                .to_string()
+        };
+        (format(&self.re), format(&self.im))
+    }
+
+    /// The origin rounded to f64.
+    pub fn approx(&self) -> (f64, f64) {
+        self.approx
+    }
+
+    /// The point `(dx, dy)` away, with `precision` significant bits.
+    pub fn offset(&self, (dx, dy): (f64, f64), precision: usize) -> Origin {
+        let add = |a: &FBig, d: f64| {
+            a.clone().with_precision(precision).value() + FBig::try_from(d).unwrap_or_default()
+        };
+        Origin::new(add(&self.re, dx), add(&self.im, dy))
+    }
+
+    /// `self - other`, rounded to f64.
+    pub fn difference(&self, other: &Origin) -> (f64, f64) {
+        let sub = |a: &FBig, b: &FBig| {
+            let precision = a.precision().max(b.precision());
+            (a.clone().with_precision(precision).value() - b)
+                .to_f64()
+                .value()
+        };
+        (sub(&self.re, &other.re), sub(&self.im, &other.im))
+    }
+}
+
+/// Significant bits that resolve a distance of `extent` anywhere in the
+/// interesting part of the plane, with bits to spare for iterating.
+pub fn precision(extent: f64) -> usize {
+    (4. / extent.abs()).log2().max(0.) as usize + 64
+}
+
+/// A `width` x `height` cell window onto the complex plane.
+///
+/// The view is centered on `center`, relative to `origin`, and each cell
+/// covers `scale` of the plane across and `scale * cell_aspect` down, turned
+/// by `rotation` about the center.
+#[derive(Debug, Clone)]
+pub struct Viewport {
+    pub width: u16,
+    pub height: u16,
+    pub center: C,
+    /// Width of the plane a cell covers.
+    pub scale: f64,
+    /// Angle in radians the view is turned by, counterclockwise on screen.
+    pub rotation: f64,
+    pub origin: Origin,
+    /// Height of the plane a cell covers over its width, which is kept as
+    /// the view is resized or set to new bounds so the plane isn't stretched
+    /// more than it is.
+    pub cell_aspect: f64,
+}
+
+impl Viewport {
+    /// The view of `width * height` cells stretched to cover exactly `bounds`.
+    pub fn new(width: u16, height: u16, ((x_min, w), (y_min, h)): Bounds) -> Self {
+        let scale = w / width.max(1) as f64;
+        let mut viewport = Viewport {
+            width,
+            height,
+            center: C {
+                re: x_min + w / 2.,
+                im: y_min + h / 2.,
+            },
+            scale,
+            rotation: 0.,
+            origin: Origin::default(),
+            cell_aspect: h / height.max(1) as f64 / scale,
+        };
+        viewport.rebase();
+        viewport
+    }
+
+    /// The view of `width * height` cells `cell_aspect` times as tall as they
+    /// are wide framing `bounds`, centered and undistorted.
+    pub fn fit(width: u16, height: u16, bounds: Bounds, cell_aspect: f64) -> Self {
+        Viewport::new(width, height, bounds).with_cell_aspect(cell_aspect)
+    }
+
+    /// Keeps the plane in proportion to cells `aspect` times as tall as they
+    /// are wide, covering at least what the view did.
+    pub fn with_cell_aspect(mut self, aspect: f64) -> Self {
+        let bounds = self.bounds();
+        self.cell_aspect = aspect;
+        self.frame(bounds);
+        self
+    }
+
+    /// Views `bounds` of the plane, measured from the zero origin, or as much
+    /// more of it as keeps the cell aspect.
+    pub fn set_bounds(&mut self, bounds: Bounds) {
+        self.origin = Origin::default();
+        self.frame(bounds);
+        self.rebase();
+    }
+
+    /// Changes the size in cells, keeping at least the same part of the
+    /// plane in view.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        let (w, h) = self.extent();
+        self.width = width;
+        self.height = height;
+        self.scale = self.covering(w, h);
+    }
+
+    /// Centers the view on `bounds`, relative to the origin, at the scale
+    /// that just covers them.
+    fn frame(&mut self, ((x_min, w), (y_min, h)): Bounds) {
+        self.center = C {
+            re: x_min + w / 2.,
+            im: y_min + h / 2.,
+        };
+        self.scale = self.covering(w, h);
+    }
+
+    /// Smallest scale at which the view covers `w * h` of the plane.
+    fn covering(&self, w: f64, h: f64) -> f64 {
+        let across = w / self.width.max(1) as f64;
+        let down = h / (self.height.max(1) as f64 * self.cell_aspect);
+        across.max(down)
+    }
+
+    /// Size of the plane the view covers, before it is rotated.
+    pub fn extent(&self) -> (f64, f64) {
+        let (w, h) = self.cell_size();
+        (w * self.width as f64, h * self.height as f64)
+    }
+
+    /// Size of the plane a cell covers, before it is rotated.
+    pub fn cell_size(&self) -> (f64, f64) {
+        (self.scale, self.scale * self.cell_aspect)
+    }
+
+    /// The box of the plane the view covers, relative to the origin and
+    /// before it is rotated.
+    pub fn bounds(&self) -> Bounds {
+        let (w, h) = self.extent();
+        ((self.center.re - w / 2., w), (self.center.im - h / 2., h))
+    }
+
+    /// Where the view looks, for restoring it with [`Viewport::set_location`].
+    pub fn location(&self) -> Location {
+        Location {
+            origin: self.origin.clone(),
+            center: self.center,
+            scale: self.scale,
+            rotation: self.rotation,
+            shape: (self.width, self.height, self.cell_aspect),
+        }
+    }
+
+    /// Looks at a location: exactly as it was taken if the view has the same
+    /// shape, which keeps undo and redo from drifting, and otherwise framed
+    /// as by [`Viewport::set_bounds`].
+    pub fn set_location(&mut self, location: Location) {
+        let bounds = location.bounds();
+        self.origin = location.origin;
+        self.rotation = location.rotation;
+        match location.shape == (self.width, self.height, self.cell_aspect) {
+            true => {
+                self.center = location.center;
+                self.scale = location.scale;
+            }
+            false => self.frame(bounds),
+        }
+    }
+
+    /// Maps the center of the cell at the 1-based terminal position
+    /// `(term_x, term_y)` to the complex plane.
+    pub fn to_complex(&self, term_x: u16, term_y: u16) -> (f64, f64) {
+        self.sample(term_x as f64 - 0.5, term_y as f64 - 0.5)
+    }
+
+    /// Maps a fractional cell position to the complex plane, for renderers
+    /// that take several samples per cell. Cell `(x, y)` covers the
+    /// positions from `(x, y)` to `(x + 1, y + 1)`.
+    pub fn sample(&self, term_x: f64, term_y: f64) -> (f64, f64) {
+        let (x, y) = self.offset(term_x, term_y);
+        let (re, im) = self.origin.approx();
+        (re + x, im + y)
+    }
+
+    /// Like [`Viewport::sample`], but relative to `origin`, which keeps the
+    /// precision f64 coordinates lose in deep views.
+    pub fn offset(&self, term_x: f64, term_y: f64) -> (f64, f64) {
+        let (w, h) = self.cell_size();
+        let dx = (term_x - self.width as f64 / 2.) * w;
+        let dy = (term_y - self.height as f64 / 2.) * h;
+        let (dx, dy) = self.rotate((dx, dy), self.rotation);
+
+        (self.center.re + dx, self.center.im + dy)
+    }
+
+    /// `(dx, dy)` turned by `angle`.
+    fn rotate(&self, (dx, dy): (f64, f64), angle: f64) -> (f64, f64) {
+        if angle == 0. {
+            return (dx, dy);
+        }
+        let (sin, cos) = angle.sin_cos();
+        (dx * cos - dy * sin, dx * sin + dy * cos)
+    }
+
+    /// Significant bits needed for points of the plane a cell apart.
+    pub fn precision(&self) -> usize {
+        precision(self.scale)
+    }
+
+    /// Whether the cells are too small to iterate their f64 coordinates.
+    pub fn is_deep(&self) -> bool {
+        let (x, y) = self.center();
+        self.scale.abs() < x.hypot(y).max(1.) * DEEP
+    }
+
+    /// Whether the cells are large enough to iterate their f32 coordinates.
+    pub fn is_shallow(&self) -> bool {
+        let (x, y) = self.center();
+        self.scale.abs() >= x.hypot(y).max(1.) * SHALLOW
+    }
+
+    /// Moves the origin to the center of the view once the view is so small
+    /// next to its distance from the origin that offsets would lose precision.
+    fn rebase(&mut self) {
+        let C { re: x, im: y } = self.center;
+        let (w, h) = self.extent();
+        if x.abs().max(y.abs()) <= w.abs().max(h.abs()) * REBASE_RATIO {
+            return;
+        }
+
+        self.origin = self.origin.offset((x, y), self.precision());
+        self.center = C { re: 0., im: 0. };
+    }
+
+    /// Scales the view by `f` while keeping the point under `(term_x, term_y)` fixed.
+    pub fn scale(&mut self, f: f64, term_x: u16, term_y: u16) {
+        let (x, y) = self.offset(term_x as f64 - 0.5, term_y as f64 - 0.5);
+        self.center = C {
+            re: x + (self.center.re - x) * f,
+            im: y + (self.center.im - y) * f,
+        };
+        self.scale *= f;
+        self.rebase();
+    }
+
+    /// Scales the view by `f` about its center.
+    pub fn zoom(&mut self, f: f64) {
+        self.scale *= f;
+        self.rebase();
+    }
+
+    /// Maps a point of the complex plane to a fractional cell position, the
+    /// inverse of [`Viewport::sample`].
+    pub fn from_complex(&self, x: f64, y: f64) -> (f64, f64) {
+        let (re, im) = self.origin.approx();
+        let d = (x - re - self.center.re, y - im - self.center.im);
+        let (dx, dy) = self.rotate(d, -self.rotation);
+        let (w, h) = self.cell_size();
+
+        (
+            dx / w + self.width as f64 / 2.,
+            dy / h + self.height as f64 / 2.,
+        )
+    }
+
+    /// The center of the view in the plane.
+    pub fn center(&self) -> (f64, f64) {
+        let (re, im) = self.origin.approx();
+        (re + self.center.re, im + self.center.im)
+    }
+
+    /// Decimal coordinates of the center, with enough digits to tell the
+    /// points a cell apart, however deep the view.
+    pub fn center_decimal(&self) -> (String, String) {
+        self.decimal((self.center.re, self.center.im))
+    }
+
+    /// Decimal coordinates of the point under `(term_x, term_y)`, like
+    /// [`Viewport::center_decimal`].
+    pub fn to_decimal(&self, term_x: u16, term_y: u16) -> (String, String) {
+        self.decimal(self.offset(term_x as f64 - 0.5, term_y as f64 - 0.5))
+    }
+
+    /// Like [`Viewport::center_decimal`], but in as many digits as tell
+    /// points `resolution` apart.
+    pub fn center_decimal_to(&self, resolution: f64) -> (String, String) {
+        self.decimal_to((self.center.re, self.center.im), resolution)
+    }
+
+    /// Decimal coordinates of the point `offset` from the origin.
+    fn decimal(&self, offset: (f64, f64)) -> (String, String) {
+        self.decimal_to(offset, self.scale)
+    }
+
+    /// Decimal coordinates of the point `offset` from the origin, in as
+    /// many digits as tell points `cell` apart.
+    fn decimal_to(&self, offset: (f64, f64), cell: f64) -> (String, String) {
+        let point = self.origin.offset(offset, self.precision());
+        let format = |x: &FBig| {
+            let magnitude = x.to_f64().value().abs().max(cell);
+            let digits = (magnitude / cell).log10().ceil().max(0.) as usize + 1;
+            x.clone()
+                .with_rounding::<HalfAway>()
+                .with_base_and_precision::<10>(digits)
+                .value()
+                .to_string()
+        };
+        (format(&point.re), format(&point.im))
+    }
+
+    /// Returns a copy whose horizontal extent is adjusted so a `width * height`
+    /// image of it has square pixels, keeping the center and vertical extent.
+    pub fn with_aspect(&self, width: u32, height: u32) -> Viewport {
+        let (_, h) = self.extent();
+        let scale = h * width as f64 / height as f64 / self.width.max(1) as f64;
+
+        Viewport {
+            scale,
+            cell_aspect: h / self.height.max(1) as f64 / scale,
+            ..self.clone()
+        }
+    }
+
+    /// Moves the view by fractions of its width and height.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        let (w, h) = self.extent();
+        let (dx, dy) = self.rotate((w * dx, h * dy), self.rotation);
+        self.center = self.center + C { re: dx, im: dy };
+        self.rebase();
+    }
+
+    /// Moves the view so the point under cell `from` ends up under cell `to`.
+    pub fn drag(&mut self, from: (u16, u16), to: (u16, u16)) {
+        let dx = (from.0 as f64 - to.0 as f64) / self.width as f64;
+        let dy = (from.1 as f64 - to.1 as f64) / self.height as f64;
+        self.pan(dx, dy);
+    }
+
+    /// Zooms so the box of cells from `from` to `to` fills the view, growing
+    /// the box along one axis so the view keeps its aspect ratio.
+    pub fn zoom_to(&mut self, from: (u16, u16), to: (u16, u16)) {
+        let (x0, x1) = (from.0.min(to.0) as f64 - 1., from.0.max(to.0) as f64);
+        let (y0, y1) = (from.1.min(to.1) as f64 - 1., from.1.max(to.1) as f64);
+        let f = ((x1 - x0) / self.width as f64).max((y1 - y0) / self.height as f64);
+
+        let (x, y) = self.offset((x0 + x1) / 2., (y0 + y1) / 2.);
+        self.center = C { re: x, im: y };
+        self.scale *= f;
+        self.rebase();
+    }
+}
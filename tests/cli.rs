@@ -0,0 +1,30 @@
+use std::{path::Path, process::Command};
+
+/// What the renderer prints on stderr when run with `args`.
+fn errors(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_mandelbrot"))
+        .args(args)
+        .env("XDG_CONFIG_HOME", "/nonexistent")
+        .output()
+        .unwrap();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn tiled_sizes_past_a_view_are_framed() {
+    // A file can't hold the bands, so the export fails once it is framed.
+    let path = Path::new(env!("CARGO_BIN_EXE_mandelbrot")).join("big.png");
+    let path = path.to_str().unwrap();
+    let errors = errors(&["render", "--tiled", "--size", "100000x100000", "-o", path]);
+    assert!(
+        errors.contains(&format!("could not export {}", path)),
+        "{errors}"
+    );
+}
+
+#[test]
+fn text_is_at_most_as_large_as_a_view() {
+    let errors = errors(&["render", "--size", "70000x10", "-o", "big.txt"]);
+    assert!(errors.contains("at most 65535 cells"), "{errors}");
+    assert!(!Path::new("big.txt").exists());
+}
@@ -0,0 +1,57 @@
+use mandelbrot::{
+    formula::{Formula, ParseError},
+    C,
+};
+
+fn error(source: &str) -> ParseError {
+    Formula::parse(source).expect_err(source)
+}
+
+fn assert_error(source: &str, position: usize, message: &str) {
+    let expected = ParseError {
+        position,
+        message: message.to_string(),
+    };
+    assert_eq!(error(source), expected, "{source}");
+}
+
+#[test]
+fn formulas_evaluate() {
+    let formula = Formula::parse("z^2 + c").unwrap();
+    let (z, c) = (C { re: 1., im: 2. }, C { re: -0.5, im: 0.25 });
+    assert_eq!(formula.eval(z, c), z * z + c);
+    assert_eq!(formula.source(), "z^2 + c");
+}
+
+#[test]
+fn incomplete_formulas_fail_where_they_end() {
+    assert_error("", 0, "unexpected end of formula");
+    assert_error("z^", 2, "unexpected end of formula");
+    assert_error("((((", 4, "unexpected end of formula");
+    assert_error("(z", 2, "expected `)`");
+    assert_error("z +* c", 3, "unexpected character");
+    assert_error("z c", 2, "unexpected input");
+    assert_error("sin z", 4, "expected `(`");
+    assert_error("2 * w", 4, "unknown name `w`");
+}
+
+#[test]
+fn deep_nesting_is_an_error_rather_than_an_overflow() {
+    let deep = format!("{}z{}", "(".repeat(20_000), ")".repeat(20_000));
+    assert!(error(&deep).message.contains("nested"));
+    assert!(error(&"-".repeat(50_000)).message.contains("nested"));
+    assert!(error(&format!("{}z", "sin(".repeat(1000)))
+        .message
+        .contains("nested"));
+    assert!(error(&vec!["z"; 1000].join("^")).message.contains("nested"));
+    // Operations in a row nest too, into the left of the one after.
+    assert!(error(&vec!["z"; 1000].join("+")).message.contains("nested"));
+}
+
+#[test]
+fn nesting_up_to_the_limit_parses() {
+    let nested = format!("{}z{}", "(".repeat(200), ")".repeat(200));
+    assert!(Formula::parse(&nested).is_ok());
+    assert!(Formula::parse(&format!("{}z", "-".repeat(200))).is_ok());
+    assert!(Formula::parse(&vec!["z"; 200].join(" + ")).is_ok());
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use mandelbrot::{iterate, Fractal, Iteration, Params, C};
+
+/// Escape time of `c` under `z = z^d + c` from `z = c`, stepped one
+/// iterate at a time.
+fn escape_time(c: C, d: f64, params: &Params) -> Option<u32> {
+    let mut z = c;
+    for i in 0..=params.max_iterations + 1 {
+        if z.norm() > params.cutoff {
+            return Some(i);
+        }
+        z = z.powf(d) + c;
+    }
+    None
+}
+
+#[test]
+fn negative_exponents_iterate_from_c() {
+    let params = Params {
+        exponent: -2.,
+        max_iterations: 100,
+        ..Params::default()
+    };
+    let points: Vec<C> = (0..40)
+        .flat_map(|y| (0..40).map(move |x| C::from((-2. + x as f64 / 10., -2. + y as f64 / 10.))))
+        .collect();
+    let escaped = points
+        .iter()
+        .filter(|&&c| escape_time(c, -2., &params).is_some());
+    assert!(escaped.count() > 0);
+    for c in points {
+        assert_eq!(
+            iterate(c, &params).iterations,
+            escape_time(c, -2., &params),
+            "{c}"
+        );
+    }
+}
+
+#[test]
+fn negative_powers_of_zero_escape() {
+    let zero = C::from((0., 0.));
+    assert!(zero.powf(-2.).norm().is_infinite());
+    assert_eq!(zero.powf(2.), zero);
+}
+
+/// The Mandelbrot set, as a user of the library would plug it in.
+#[derive(Debug)]
+struct Squared;
+
+impl Iteration for Squared {
+    fn start(&self, _: C) -> C {
+        C::from((0., 0.))
+    }
+
+    fn step(&self, z: C, c: C) -> C {
+        z * z + c
+    }
+}
+
+#[test]
+fn plugins_iterate_through_the_shared_escape_loop() {
+    let params = Params {
+        fractal: Fractal::Plugin(Arc::new(Squared)),
+        max_iterations: 100,
+        ..Params::default()
+    };
+    let mandelbrot = Params {
+        max_iterations: 100,
+        ..Params::default()
+    };
+    for c in [(-0.75, 0.1), (0.3, 0.5), (-2., 1.), (0.25, 0.)] {
+        let c = C::from(c);
+        let result = Squared.iterate(c, 100);
+        assert_eq!(result, iterate(c, &params), "{c}");
+        assert_eq!(result.iterations, iterate(c, &mandelbrot).iterations, "{c}");
+    }
+}
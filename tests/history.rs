@@ -0,0 +1,54 @@
+use mandelbrot::{history::History, Viewport};
+
+const BOUNDS: ((f64, f64), (f64, f64)) = ((-2.5, 3.5), (-1., 2.));
+
+/// Views as they are after pans and zooms, which bounds don't give back
+/// to the last bit.
+fn panned_and_zoomed() -> Vec<Viewport> {
+    let mut viewport = Viewport::fit(80, 24, BOUNDS, 2.);
+    let mut views = Vec::new();
+    for i in 0..200 {
+        match i % 4 {
+            0 => viewport.pan(0.137, -0.071),
+            1 => viewport.zoom(0.61),
+            2 => viewport.scale(0.83, 11 + i % 50, 3 + i % 20),
+            _ => viewport.drag((7, 5), (i % 80 + 1, 19)),
+        }
+        views.push(viewport.clone());
+    }
+    views
+}
+
+#[test]
+fn locations_round_trip_exactly() {
+    for view in panned_and_zoomed() {
+        let mut restored = Viewport::fit(80, 24, BOUNDS, 2.);
+        restored.set_location(view.location());
+        assert_eq!(restored.location(), view.location());
+    }
+}
+
+#[test]
+fn undo_then_redo_comes_back() {
+    let views = panned_and_zoomed();
+    let mut viewport = Viewport::fit(80, 24, BOUNDS, 2.);
+    let mut history = History::new(viewport.location());
+    for view in &views {
+        viewport = view.clone();
+        history.visit(viewport.location());
+    }
+
+    // The view records every location it lands on, as the interactive one
+    // does after each key.
+    let undone = views.len() - 1;
+    for _ in 0..undone {
+        viewport.set_location(history.undo().expect("there are views to undo"));
+        history.visit(viewport.location());
+    }
+    assert_eq!(viewport.location(), views[0].location());
+    for _ in 0..undone {
+        viewport.set_location(history.redo().expect("undone views can be redone"));
+        history.visit(viewport.location());
+    }
+    assert_eq!(viewport.location(), views[views.len() - 1].location());
+}
@@ -0,0 +1,109 @@
+use mandelbrot::{
+    output::{Blocks, Braille, HalfBlocks, Output, Text},
+    CellMode, ColorMode, Frame, Params, Style, Viewport,
+};
+
+const MODE: ColorMode = ColorMode::TrueColor;
+
+fn frame(cells: CellMode) -> Frame {
+    let viewport = Viewport::fit(20, 8, ((-2.5, 3.5), (-1., 2.)), 2.);
+    Frame::from_cells(&viewport, &Params::default(), cells)
+}
+
+#[derive(Clone, Copy)]
+enum Renderer {
+    Blocks { background: bool },
+    HalfBlocks,
+    Braille,
+    Text,
+}
+
+/// What `renderer` writes of `frame`, or the error it fails with.
+fn drawn(renderer: Renderer, frame: &Frame) -> Result<String, String> {
+    let (style, mut writer) = (Style::default(), Vec::new());
+    match renderer {
+        Renderer::Blocks { background } => Blocks {
+            mode: MODE,
+            background,
+            writer: &mut writer,
+        }
+        .render(frame, &style),
+        Renderer::HalfBlocks => HalfBlocks {
+            mode: MODE,
+            writer: &mut writer,
+        }
+        .render(frame, &style),
+        Renderer::Braille => Braille {
+            writer: &mut writer,
+        }
+        .render(frame, &style),
+        Renderer::Text => Text {
+            mode: MODE,
+            writer: &mut writer,
+        }
+        .render(frame, &style),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(String::from_utf8(writer).unwrap())
+}
+
+#[test]
+fn text_draws_cells_with_the_renderer_of_their_mode() {
+    let style = Style::default();
+    for cells in [
+        CellMode::Full,
+        CellMode::Background,
+        CellMode::HalfBlock,
+        CellMode::Braille,
+    ] {
+        let frame = frame(cells);
+        let expected = match cells {
+            CellMode::Full => drawn(Renderer::Blocks { background: false }, &frame),
+            CellMode::Background => drawn(Renderer::Blocks { background: true }, &frame),
+            CellMode::HalfBlock => drawn(Renderer::HalfBlocks, &frame),
+            CellMode::Braille => drawn(Renderer::Braille, &frame),
+        };
+        assert_eq!(drawn(Renderer::Text, &frame), expected, "{cells:?}");
+        assert_eq!(expected.unwrap(), frame.to_buffer(MODE, &style));
+    }
+}
+
+#[test]
+fn cells_of_a_sample_draw_as_blocks_either_way() {
+    let full = frame(CellMode::Full);
+    let background = frame(CellMode::Background);
+    assert_eq!(
+        drawn(Renderer::Blocks { background: true }, &full),
+        drawn(Renderer::Text, &background)
+    );
+}
+
+#[test]
+fn renderers_reject_frames_sampled_for_others() {
+    assert!(drawn(Renderer::HalfBlocks, &frame(CellMode::Full)).is_err());
+    assert!(drawn(Renderer::Braille, &frame(CellMode::HalfBlock)).is_err());
+    assert!(drawn(
+        Renderer::Blocks { background: false },
+        &frame(CellMode::Braille)
+    )
+    .is_err());
+}
+
+#[test]
+fn cells_are_those_of_the_text() {
+    let style = Style::default();
+    for cells in [CellMode::Full, CellMode::HalfBlock, CellMode::Braille] {
+        let frame = frame(cells);
+        let colored = frame.to_cells(ColorMode::Ascii, &style);
+        let symbols: String = colored.iter().map(|cell| cell.symbol).collect();
+        assert_eq!(symbols, frame.to_buffer(ColorMode::Ascii, &style));
+
+        let colored = frame.to_cells(MODE, &style);
+        let last = (frame.width - 1, frame.height - 1);
+        let at = frame.cells_at(&[(3, 2), last], MODE, &style);
+        assert_eq!(
+            at,
+            [colored[2 * frame.width + 3], colored[colored.len() - 1]]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use mandelbrot::{render_iterations, EscapeResult, Origin, Params, Precision, Viewport, C};
+
+/// A `width * height` view 1e-20 across next to the center of a spiral of
+/// the seahorse valley, where orbits run for thousands of iterations, and
+/// those nearer the spiral outlive the reference through the center of the
+/// view.
+fn deep_view(width: u16, height: u16) -> Viewport {
+    let mut viewport = Viewport::fit(width, height, ((-2.5, 3.5), (-1., 2.)), 1.);
+    viewport.origin = Origin::parse(
+        "-0.743643887037158704752191506114774",
+        "0.131825904205311970493132056385139",
+    )
+    .unwrap();
+    viewport.center = C {
+        re: 3e-21,
+        im: -2e-21,
+    };
+    viewport.scale = 1e-20 / width as f64;
+    viewport
+}
+
+fn iterations(viewport: &Viewport, precision: Precision) -> Vec<Option<u32>> {
+    let params = Params {
+        max_iterations: 20_000,
+        precision: Some(precision),
+        ..Params::default()
+    };
+    let (width, height) = (viewport.width as u32, viewport.height as u32);
+    render_iterations(viewport, &params, width, height)
+        .iter()
+        .map(|result: &EscapeResult| result.iterations)
+        .collect()
+}
+
+/// Asserts that `perturbed` escape times are those of `direct` iteration:
+/// all but a few exactly, and those within a percent. Orbits this long
+/// amplify rounding, which no two ways of iterating them make alike.
+fn assert_matches(perturbed: &[Option<u32>], direct: &[Option<u32>]) {
+    let mut differing = 0;
+    for (p, d) in perturbed.iter().zip(direct) {
+        if p == d {
+            continue;
+        }
+        differing += 1;
+        match (p, d) {
+            (Some(p), Some(d)) => assert!(p.abs_diff(*d) * 100 < *d, "{p} != {d}"),
+            _ => panic!("{p:?} != {d:?}"),
+        }
+    }
+    assert!(differing * 50 <= direct.len(), "{differing} points differ");
+}
+
+#[test]
+fn perturbation_matches_double_double_iteration() {
+    let viewport = deep_view(24, 16);
+    let perturbed = iterations(&viewport, Precision::Perturbed);
+    assert_matches(&perturbed, &iterations(&viewport, Precision::DoubleDouble));
+
+    // The view has detail, which a reference alone would miss.
+    let mut distinct = perturbed.clone();
+    distinct.sort();
+    distinct.dedup();
+    assert!(distinct.len() > 100);
+}
+
+#[test]
+fn perturbation_matches_arbitrary_precision_iteration() {
+    // Arbitrary precision is slow enough to only check a few points.
+    let viewport = deep_view(6, 4);
+    let perturbed = iterations(&viewport, Precision::Perturbed);
+    assert_matches(&perturbed, &iterations(&viewport, Precision::Arbitrary));
+}
@@ -0,0 +1,77 @@
+use mandelbrot::{iterate, simd::iterate_all, EscapeResult, Fractal, Params, Precision, C};
+
+/// Points of a `cols * rows` grid over a box around the sets, a number of
+/// them that leaves the last vector of either width partly filled.
+fn grid(cols: usize, rows: usize) -> Vec<C> {
+    (0..rows)
+        .flat_map(|y| {
+            (0..cols).map(move |x| C {
+                re: -2.2 + 3.2 * x as f64 / cols as f64,
+                im: -1.3 + 2.6 * y as f64 / rows as f64,
+            })
+        })
+        .collect()
+}
+
+/// Parameters of each fractal the vector kernel iterates.
+fn vectorized() -> Vec<Params> {
+    [
+        Fractal::Mandelbrot,
+        Fractal::Julia(Fractal::DEFAULT_JULIA),
+        Fractal::BurningShip,
+        Fractal::Tricorn,
+    ]
+    .into_iter()
+    .map(|fractal| Params {
+        max_iterations: 200,
+        fractal,
+        ..Params::default()
+    })
+    .collect()
+}
+
+fn scalar(points: &[C], params: &Params) -> Vec<EscapeResult> {
+    points.iter().map(|&c| iterate(c, params)).collect()
+}
+
+#[test]
+fn f64_lanes_match_scalar_iteration() {
+    let points = grid(61, 37);
+    assert_ne!(points.len() % 4, 0);
+    for params in vectorized() {
+        let lanes = iterate_all(&points, &params, Precision::F64);
+        assert_eq!(lanes, scalar(&points, &params), "{:?}", params.fractal);
+    }
+}
+
+#[test]
+fn f32_lanes_match_scalar_iteration_of_quick_escapes() {
+    let points = grid(61, 37);
+    assert_ne!(points.len() % 8, 0);
+    for params in vectorized() {
+        let lanes = iterate_all(&points, &params, Precision::F32);
+        // Rounding only shows in orbits long enough to compound it.
+        let quick = scalar(&points, &params)
+            .into_iter()
+            .zip(lanes)
+            .filter(|(expected, _)| expected.iterations.is_some_and(|i| i < 20));
+        for (expected, lane) in quick {
+            assert_eq!(lane.iterations, expected.iterations, "{:?}", params.fractal);
+            assert!((lane.smooth - expected.smooth).abs() < 0.01);
+        }
+    }
+}
+
+#[test]
+fn padded_chunks_give_the_results_of_full_ones() {
+    let points = grid(7, 5);
+    for params in vectorized() {
+        for precision in [Precision::F32, Precision::F64] {
+            let whole = iterate_all(&points, &params, precision);
+            for len in 1..=17 {
+                let part = iterate_all(&points[..len], &params, precision);
+                assert_eq!(part, whole[..len], "{:?} {len}", params.fractal);
+            }
+        }
+    }
+}
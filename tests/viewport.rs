@@ -0,0 +1,110 @@
+use mandelbrot::Viewport;
+
+const BOUNDS: ((f64, f64), (f64, f64)) = ((-2.5, 3.5), (-1., 2.));
+
+fn assert_close((x, y): (f64, f64), (ex, ey): (f64, f64), eps: f64) {
+    assert!(
+        (x - ex).abs() <= eps && (y - ey).abs() <= eps,
+        "({x}, {y}) != ({ex}, {ey})"
+    );
+}
+
+/// Every cell center of `viewport` maps back to itself, to within `eps` of
+/// a cell.
+fn assert_round_trips(viewport: &Viewport, eps: f64) {
+    for term_y in 1..=viewport.height {
+        for term_x in 1..=viewport.width {
+            let (re, im) = viewport.to_complex(term_x, term_y);
+            let expected = (term_x as f64 - 0.5, term_y as f64 - 0.5);
+            assert_close(viewport.from_complex(re, im), expected, eps);
+        }
+    }
+}
+
+#[test]
+fn cells_round_trip() {
+    assert_round_trips(&Viewport::new(80, 24, BOUNDS), 1e-9);
+    assert_round_trips(&Viewport::fit(80, 24, BOUNDS, 2.), 1e-9);
+}
+
+#[test]
+fn rotated_cells_round_trip() {
+    let mut viewport = Viewport::fit(80, 24, BOUNDS, 2.);
+    for rotation in [0.3, 1., -2.5, std::f64::consts::PI] {
+        viewport.rotation = rotation;
+        assert_round_trips(&viewport, 1e-9);
+    }
+}
+
+#[test]
+fn deep_cells_round_trip() {
+    let mut viewport = Viewport::fit(40, 20, BOUNDS, 2.);
+    viewport.set_bounds(((-0.75, 1e-6), (0.1, 1e-6)));
+    // f64 points of the plane are only this close to each other here.
+    assert_round_trips(&viewport, 1e-6);
+}
+
+#[test]
+fn new_covers_exactly_the_bounds() {
+    let viewport = Viewport::new(70, 20, BOUNDS);
+    assert_close(viewport.sample(0., 0.), (-2.5, -1.), 1e-12);
+    assert_close(viewport.sample(70., 20.), (1., 1.), 1e-12);
+}
+
+#[test]
+fn fit_contains_the_bounds() {
+    for (width, height) in [(80, 24), (20, 40), (1, 1)] {
+        let viewport = Viewport::fit(width, height, BOUNDS, 2.);
+        let ((x_min, w), (y_min, h)) = viewport.bounds();
+        assert!(x_min <= -2.5 && x_min + w >= 1. - 1e-12);
+        assert!(y_min <= -1. && y_min + h >= 1. - 1e-12);
+        assert_close(viewport.center(), (-0.75, 0.), 1e-12);
+        let (cw, ch) = viewport.cell_size();
+        assert!((ch / cw - 2.).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn scaling_keeps_the_pointed_at_point() {
+    let mut viewport = Viewport::fit(80, 24, BOUNDS, 2.);
+    viewport.rotation = 0.7;
+    let point = viewport.to_complex(13, 5);
+    viewport.scale(0.25, 13, 5);
+    assert_close(viewport.to_complex(13, 5), point, 1e-12);
+}
+
+#[test]
+fn dragging_moves_the_point_with_the_pointer() {
+    let mut viewport = Viewport::fit(80, 24, BOUNDS, 2.);
+    viewport.rotation = -1.2;
+    let point = viewport.to_complex(10, 10);
+    viewport.drag((10, 10), (30, 4));
+    assert_close(viewport.to_complex(30, 4), point, 1e-12);
+}
+
+#[test]
+fn panning_by_the_width_shifts_a_screen() {
+    let mut viewport = Viewport::fit(80, 24, BOUNDS, 2.);
+    let right = viewport.sample(80., 12.);
+    viewport.pan(1., 0.);
+    assert_close(viewport.sample(0., 12.), right, 1e-12);
+}
+
+#[test]
+fn zooming_to_a_box_fills_the_view() {
+    let mut viewport = Viewport::fit(80, 24, BOUNDS, 2.);
+    let corner = viewport.sample(20., 6.);
+    let center = viewport.sample(40., 12.);
+    viewport.zoom_to((21, 7), (60, 18));
+    assert_close(viewport.sample(0., 0.), corner, 1e-12);
+    assert_close(viewport.center(), center, 1e-12);
+}
+
+#[test]
+fn images_larger_than_a_view_frame_the_same_plane() {
+    let image = (100_000, 100_000);
+    let whole = Viewport::new(u16::MAX, u16::MAX, BOUNDS).with_aspect(image.0, image.1);
+    let small = Viewport::new(1000, 1000, BOUNDS).with_aspect(image.0, image.1);
+    assert_close(whole.extent(), small.extent(), 1e-12);
+    assert_close(whole.bounds().0, small.bounds().0, 1e-12);
+}